@@ -0,0 +1,390 @@
+use crate::convert::{self, WEBP_QUALITY};
+use crate::{placeholder, video};
+use crate::{write_output, CompressOptions, FileOutcome, ImageType};
+use ::gif::{ColorOutput, DecodeOptions, DisposalMethod, Encoder, Frame, Repeat};
+use image::codecs::gif::GifDecoder;
+use image::{AnimationDecoder, ImageDecoder, ImageFormat};
+use maya_common::error::{Error, Result};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use webp::{AnimEncoder, AnimFrame, WebPConfig};
+
+/// 浏览器把不超过该值的帧延迟按 100ms 播放，转换时保持相同的节奏
+const MIN_FRAME_DELAY_MS: u32 = 10;
+const BROWSER_FRAME_DELAY_MS: u32 = 100;
+
+/// 处理 GIF：按选项转换为动态 WebP 或视频，否则做无损优化
+pub(crate) fn process_gif(
+    image_path: &Path,
+    output_path: &Path,
+    target: Option<&ImageType>,
+    options: &CompressOptions,
+) -> Result<FileOutcome> {
+    let original = fs::read(image_path)?;
+    // 占位数据取自第一帧
+    let placeholder = placeholder::for_data(&original, ImageFormat::Gif, options)?;
+    let outcome = match (options.video, target) {
+        (Some(format), _) => {
+            let encoded = video::encode_video(image_path, format)?;
+            convert::write_converted(
+                image_path,
+                output_path,
+                &original,
+                format.label(),
+                format.extension(),
+                &encoded,
+                options,
+            )?
+        }
+        (None, Some(ImageType::Webp)) => {
+            let (encoded, dimensions) = encode_animated_webp(&original, options)?;
+            let outcome =
+                convert::write_converted(image_path, output_path, &original, "WebP", "webp", &encoded, options)?;
+            // 动态 WebP 的图片头不能直接读取尺寸
+            FileOutcome { dimensions: Some(dimensions), ..outcome }
+        }
+        (None, Some(target)) => return Err(Error::compression(format!("GIF 不支持转换为 {}", target.label()))),
+        (None, None) => {
+            let data = optimize_gif(&original)?;
+            write_output(image_path, output_path, &original, &data, None, "gif", options)?
+        }
+    };
+    Ok(FileOutcome { placeholder, ..outcome })
+}
+
+/// GIF 无损优化，像素和播放效果保持不变
+///
+/// * 删除调色板中没用到的颜色，合并重复颜色，按使用次数重排，缩小 LZW 码长
+/// * 所有帧调色板相同时改用全局调色板
+/// * 裁掉不会改变画面的透明边框
+/// * 去掉注释等扩展块，只保留循环次数
+pub(crate) fn optimize_gif(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decode_options = DecodeOptions::new();
+    decode_options.set_color_output(ColorOutput::Indexed);
+    let mut decoder = decode_options.read_info(data).map_err(gif_error)?;
+    let (width, height) = (decoder.width(), decoder.height());
+    let global_palette = decoder.global_palette().map(<[u8]>::to_vec);
+
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().map_err(gif_error)? {
+        frames.push(frame.clone());
+    }
+    if frames.is_empty() {
+        return Err(Error::compression("GIF 中没有图像帧"));
+    }
+    let repeat = decoder.repeat();
+
+    // 使用全局调色板的帧共用一次压缩结果，不合并颜色以免不同帧的透明色互相影响
+    let global = match &global_palette {
+        Some(palette) => {
+            let mut counts = [0usize; 256];
+            for frame in frames.iter().filter(|f| f.palette.is_none()) {
+                count_indices(&frame.buffer, &mut counts);
+            }
+            Some(compact_palette(palette, &counts, None, false))
+        }
+        None => None,
+    };
+
+    let mut optimized = Vec::with_capacity(frames.len());
+    for frame in &frames {
+        let rebuilt = match (&frame.palette, &global) {
+            (Some(palette), _) => {
+                let mut counts = [0usize; 256];
+                count_indices(&frame.buffer, &mut counts);
+                let (palette, remap) = compact_palette(palette, &counts, frame.transparent, true);
+                rebuild_frame(frame, &remap, Some(palette))
+            }
+            (None, Some((_, remap))) => rebuild_frame(frame, remap, None),
+            (None, None) => return Err(Error::compression("GIF 帧缺少调色板")),
+        };
+        optimized.push(rebuilt);
+    }
+
+    let mut global_palette = global.map(|(palette, _)| palette).unwrap_or_default();
+    if global_palette.is_empty() && optimized.iter().all(|f| f.palette == optimized[0].palette) {
+        global_palette = optimized[0].palette.clone().unwrap_or_default();
+        for frame in &mut optimized {
+            frame.palette = None;
+        }
+    }
+
+    let mut output = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut output, width, height, &global_palette).map_err(gif_encode_error)?;
+        // 没有循环扩展（播放一次）时编码器不会写入
+        encoder.set_repeat(repeat).map_err(gif_encode_error)?;
+        for frame in &optimized {
+            encoder.write_frame(frame).map_err(gif_encode_error)?;
+        }
+    }
+    Ok(output)
+}
+
+fn count_indices(buffer: &[u8], counts: &mut [usize; 256]) {
+    for &index in buffer {
+        counts[index as usize] += 1;
+    }
+}
+
+/// 只保留用到的颜色并按使用次数从多到少排列，返回新调色板和旧索引到新索引的映射
+///
+/// `merge` 时合并 RGB 相同的不透明颜色，透明色始终单独占一个索引
+fn compact_palette(
+    palette: &[u8],
+    counts: &[usize; 256],
+    transparent: Option<u8>,
+    merge: bool,
+) -> (Vec<u8>, [u8; 256]) {
+    let color = |index: usize| -> [u8; 3] {
+        palette.get(index * 3..index * 3 + 3).map_or([0, 0, 0], |c| [c[0], c[1], c[2]])
+    };
+    let mut used: Vec<usize> = (0..256).filter(|&i| counts[i] > 0).collect();
+    used.sort_by_key(|&i| Reverse(counts[i]));
+
+    let mut colors: Vec<[u8; 3]> = Vec::new();
+    let mut new_transparent = None;
+    let mut remap = [0u8; 256];
+    for index in used {
+        let rgb = color(index);
+        let is_transparent = transparent == Some(index as u8);
+        let existing = if merge && !is_transparent {
+            colors.iter().enumerate().position(|(i, c)| *c == rgb && new_transparent != Some(i))
+        } else {
+            None
+        };
+        remap[index] = match existing {
+            Some(i) => i as u8,
+            None => {
+                colors.push(rgb);
+                (colors.len() - 1) as u8
+            }
+        };
+        if is_transparent {
+            new_transparent = Some(remap[index] as usize);
+        }
+    }
+    if colors.is_empty() {
+        colors.push([0, 0, 0]);
+    }
+    (colors.concat(), remap)
+}
+
+/// 按新索引重建帧，去掉没用到的透明色，并裁掉透明边框
+fn rebuild_frame(frame: &Frame<'_>, remap: &[u8; 256], palette: Option<Vec<u8>>) -> Frame<'static> {
+    let transparent = frame.transparent.filter(|t| frame.buffer.contains(t));
+    let mut rebuilt = Frame {
+        delay: frame.delay,
+        dispose: frame.dispose,
+        transparent: transparent.map(|t| remap[t as usize]),
+        needs_user_input: frame.needs_user_input,
+        top: frame.top,
+        left: frame.left,
+        width: frame.width,
+        height: frame.height,
+        interlaced: false,
+        palette,
+        buffer: Cow::Owned(frame.buffer.iter().map(|&i| remap[i as usize]).collect()),
+    };
+    // 恢复为背景色的帧会清除整个帧区域，裁剪会改变被清除的范围
+    if let (Some(transparent), false) = (transparent, frame.dispose == DisposalMethod::Background) {
+        trim_transparent(&mut rebuilt, remap[transparent as usize]);
+    }
+    rebuilt
+}
+
+/// 裁掉四周完全透明的行和列；整帧透明时保留 1 像素以保持帧的延迟
+fn trim_transparent(frame: &mut Frame<'static>, transparent: u8) {
+    let (width, height) = (frame.width as usize, frame.height as usize);
+    if width == 0 || height == 0 {
+        return;
+    }
+    let opaque = |x: usize, y: usize| frame.buffer[y * width + x] != transparent;
+    let rows: Vec<usize> = (0..height).filter(|&y| (0..width).any(|x| opaque(x, y))).collect();
+    let cols: Vec<usize> = (0..width).filter(|&x| (0..height).any(|y| opaque(x, y))).collect();
+    let (top, bottom, left, right) = match (rows.first(), rows.last(), cols.first(), cols.last()) {
+        (Some(&top), Some(&bottom), Some(&left), Some(&right)) => (top, bottom, left, right),
+        _ => (0, 0, 0, 0),
+    };
+    if (top, bottom, left, right) == (0, height - 1, 0, width - 1) {
+        return;
+    }
+
+    let mut buffer = Vec::with_capacity((bottom - top + 1) * (right - left + 1));
+    for y in top..=bottom {
+        buffer.extend_from_slice(&frame.buffer[y * width + left..=y * width + right]);
+    }
+    frame.top += top as u16;
+    frame.left += left as u16;
+    frame.width = (right - left + 1) as u16;
+    frame.height = (bottom - top + 1) as u16;
+    frame.buffer = Cow::Owned(buffer);
+}
+
+/// 把 GIF 动图编码为动态 WebP，返回编码结果和画布尺寸
+///
+/// 质量取自 `options.quality`，`--lossless` 时使用无损编码；循环次数和帧延迟与浏览器播放 GIF 的效果一致
+pub(crate) fn encode_animated_webp(data: &[u8], options: &CompressOptions) -> Result<(Vec<u8>, (u32, u32))> {
+    let decoder = GifDecoder::new(Cursor::new(data)).map_err(|e| Error::compression(format!("GIF 解码失败: {}", e)))?;
+    let (width, height) = decoder.dimensions();
+    let frames = decoder
+        .into_frames()
+        .collect_frames()
+        .map_err(|e| Error::compression(format!("GIF 解码失败: {}", e)))?;
+    if frames.is_empty() {
+        return Err(Error::compression("GIF 中没有图像帧"));
+    }
+
+    let mut config = WebPConfig::new().map_err(|_| Error::compression("WebP 编码参数初始化失败"))?;
+    config.lossless = i32::from(options.lossless);
+    config.alpha_compression = i32::from(!options.lossless);
+    config.quality = f32::from(options.quality.unwrap_or(WEBP_QUALITY));
+
+    let mut encoder = AnimEncoder::new(width, height, &config);
+    encoder.set_loop_count(webp_loop_count(gif_repeat(data)?));
+    let mut timestamp = 0;
+    for frame in &frames {
+        encoder.add_frame(AnimFrame::from_rgba(frame.buffer().as_raw(), width, height, timestamp));
+        timestamp += frame_delay_ms(frame) as i32;
+    }
+    let mut encoded = encoder
+        .try_encode()
+        .map_err(|e| Error::compression(format!("动态 WebP 编码失败: {:?}", e)))?
+        .to_vec();
+    set_last_frame_duration(&mut encoded, timestamp as u32);
+    Ok((encoded, (width, height)))
+}
+
+fn frame_delay_ms(frame: &image::Frame) -> u32 {
+    let (numer, denom) = frame.delay().numer_denom_ms();
+    match numer / denom.max(1) {
+        delay if delay <= MIN_FRAME_DELAY_MS => BROWSER_FRAME_DELAY_MS,
+        delay => delay,
+    }
+}
+
+/// 读取 GIF 的循环设置，循环扩展位于第一帧之前
+fn gif_repeat(data: &[u8]) -> Result<Repeat> {
+    let mut decoder = DecodeOptions::new().read_info(data).map_err(gif_error)?;
+    decoder.next_frame_info().map_err(gif_error)?;
+    Ok(decoder.repeat())
+}
+
+/// WebP 的循环次数是总播放次数（0 表示无限），GIF 的是首次播放之后的重复次数
+fn webp_loop_count(repeat: Repeat) -> i32 {
+    match repeat {
+        Repeat::Infinite => 0,
+        Repeat::Finite(count) => (i32::from(count) + 1).min(i32::from(u16::MAX)),
+    }
+}
+
+/// libwebp 把最后一帧的时长设为前面各帧的平均值，这里改回 GIF 中的实际时长
+///
+/// 相同的连续帧会被合并，因此用总时长减去前面各帧的时长计算
+fn set_last_frame_duration(webp: &mut [u8], total_ms: u32) {
+    let mut offset = 12;
+    let mut frames = Vec::new();
+    while offset + 8 <= webp.len() {
+        let size = u32::from_le_bytes([webp[offset + 4], webp[offset + 5], webp[offset + 6], webp[offset + 7]]) as usize;
+        if &webp[offset..offset + 4] == b"ANMF" && offset + 24 <= webp.len() {
+            frames.push(offset + 8 + 12);
+        }
+        offset += 8 + size + (size & 1);
+    }
+    let Some((&last, previous)) = frames.split_last() else {
+        return;
+    };
+    let read = |at: usize| u32::from_le_bytes([webp[at], webp[at + 1], webp[at + 2], 0]);
+    let elapsed: u32 = previous.iter().map(|&at| read(at)).sum();
+    let duration = total_ms.saturating_sub(elapsed).clamp(1, 0xFF_FFFF);
+    webp[last..last + 3].copy_from_slice(&duration.to_le_bytes()[..3]);
+}
+
+fn gif_error(e: ::gif::DecodingError) -> Error {
+    Error::compression(format!("GIF 解码失败: {}", e))
+}
+
+fn gif_encode_error(e: ::gif::EncodingError) -> Error {
+    Error::compression(format!("GIF 编码失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 两帧 16x16 GIF：全局调色板 256 色只用到 3 色，第二帧只有中间 4x4 不透明
+    fn sample_gif() -> Vec<u8> {
+        let mut palette = vec![0u8; 256 * 3];
+        palette[3..6].copy_from_slice(&[255, 0, 0]);
+        palette[600..603].copy_from_slice(&[0, 0, 255]);
+        let mut data = Vec::new();
+        {
+            let mut encoder = Encoder::new(&mut data, 16, 16, &palette).unwrap();
+            encoder.set_repeat(Repeat::Infinite).unwrap();
+            let first: Vec<u8> = (0..256).map(|i| if i % 3 == 0 { 1 } else { 200 }).collect();
+            encoder
+                .write_frame(&Frame { width: 16, height: 16, delay: 5, buffer: Cow::Owned(first), ..Frame::default() })
+                .unwrap();
+            let second: Vec<u8> = (0..256)
+                .map(|i: usize| if (6..10).contains(&(i % 16)) && (6..10).contains(&(i / 16)) { 1 } else { 0 })
+                .collect();
+            encoder
+                .write_frame(&Frame {
+                    width: 16,
+                    height: 16,
+                    delay: 50,
+                    transparent: Some(0),
+                    dispose: DisposalMethod::Keep,
+                    buffer: Cow::Owned(second),
+                    ..Frame::default()
+                })
+                .unwrap();
+        }
+        data
+    }
+
+    fn rendered_frames(data: &[u8]) -> Vec<(Vec<u8>, (u32, u32))> {
+        GifDecoder::new(Cursor::new(data))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap()
+            .into_iter()
+            .map(|frame| (frame.buffer().as_raw().clone(), frame.delay().numer_denom_ms()))
+            .collect()
+    }
+
+    #[test]
+    fn test_optimize_gif_is_lossless_and_smaller() {
+        let original = sample_gif();
+        let optimized = optimize_gif(&original).unwrap();
+        assert!(optimized.len() < original.len());
+        assert_eq!(rendered_frames(&optimized), rendered_frames(&original));
+        assert_eq!(gif_repeat(&optimized).unwrap(), Repeat::Infinite);
+
+        // 第二帧只保留了不透明的中间区域
+        let mut decode_options = DecodeOptions::new();
+        decode_options.set_color_output(ColorOutput::Indexed);
+        let mut decoder = decode_options.read_info(optimized.as_slice()).unwrap();
+        assert_eq!(decoder.global_palette().unwrap().len(), 3 * 4);
+        decoder.read_next_frame().unwrap();
+        let second = decoder.read_next_frame().unwrap().unwrap();
+        assert_eq!((second.left, second.top, second.width, second.height), (6, 6, 4, 4));
+    }
+
+    #[test]
+    fn test_encode_animated_webp_keeps_timing() {
+        let (webp, dimensions) = encode_animated_webp(&sample_gif(), &CompressOptions::default()).unwrap();
+        assert_eq!(dimensions, (16, 16));
+        assert_eq!(&webp[8..12], b"WEBP");
+
+        let decoded = webp::AnimDecoder::new(&webp).decode().unwrap();
+        let timestamps: Vec<i32> = decoded.into_iter().map(|frame| frame.get_time_ms()).collect();
+        // 时间戳为每帧的结束时间：50ms 后切换到第二帧，第二帧播放 500ms
+        assert_eq!(timestamps, vec![50, 550]);
+        assert_eq!(webp_loop_count(Repeat::Finite(0)), 1);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::{
+    ChromaSubsampling, CompressOptions, ImageType, MetadataOptions, PngOptions, ResizeOptions, SvgOptions, VideoFormat,
+};
+use maya_common::atomic::{write_atomic, AtomicWriteOptions};
+use maya_common::config::DATA_DIR;
+use maya_common::error::{Error, Result};
+use maya_common::hash_utils::sha256_hex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 缓存文件名
+pub const CACHE_FILE_NAME: &str = "optimize-cache.json";
+
+/// 优化缓存的存放位置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CacheLocation {
+    /// 不使用缓存
+    #[default]
+    Disabled,
+    /// 保存在被优化目录的 `.maya/optimize-cache.json`
+    Project,
+    /// 保存在指定目录中，多个项目共用一个缓存文件
+    Central(PathBuf),
+}
+
+/// 一个已优化文件的记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheEntry {
+    /// 优化后文件内容的 SHA-256
+    hash: String,
+    /// 优化时使用的参数指纹
+    settings: String,
+}
+
+/// 已优化图片的缓存：路径 + 内容哈希 + 参数都一致时跳过
+#[derive(Debug)]
+pub(crate) struct OptimizeCache {
+    path: PathBuf,
+    /// 项目缓存中的键相对于该目录，中央缓存使用绝对路径
+    base: Option<PathBuf>,
+    settings: String,
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+impl OptimizeCache {
+    /// 打开缓存，未启用时返回 `None`；缓存文件不存在或损坏时当作空缓存
+    ///
+    /// # 参数
+    /// * `root` - 被优化的目录
+    /// * `options` - 压缩选项，用于确定缓存位置和参数指纹
+    pub(crate) fn open(root: &Path, options: &CompressOptions) -> Option<Self> {
+        // 清单需要包含所有图片，生成响应式图片时每次都完整处理
+        if options.responsive.is_some() {
+            return None;
+        }
+        let (path, base) = match &options.cache {
+            CacheLocation::Disabled => return None,
+            CacheLocation::Project => (root.join(DATA_DIR).join(CACHE_FILE_NAME), Some(root.to_path_buf())),
+            CacheLocation::Central(dir) => (dir.join(CACHE_FILE_NAME), None),
+        };
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Some(Self { path, base, settings: settings_fingerprint(options), entries })
+    }
+
+    /// 文件内容和参数都与上次优化后一致
+    pub(crate) fn is_fresh(&self, file: &Path, hash: &str) -> bool {
+        self.entries
+            .get(&self.key(file))
+            .is_some_and(|entry| entry.hash == hash && entry.settings == self.settings)
+    }
+
+    /// 缓存中有该文件的记录，说明以前处理过它
+    pub(crate) fn contains(&self, file: &Path) -> bool {
+        self.entries.contains_key(&self.key(file))
+    }
+
+    /// 记录优化后的文件内容哈希
+    pub(crate) fn record(&mut self, file: &Path, hash: String) {
+        let key = self.key(file);
+        self.entries.insert(key, CacheEntry { hash, settings: self.settings.clone() });
+    }
+
+    /// 写回缓存文件
+    pub(crate) fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(&self.entries)
+            .map_err(|e| Error::other(format!("优化缓存序列化失败: {}", e)))?;
+        write_atomic(&self.path, content.as_bytes(), &AtomicWriteOptions::default())?;
+        Ok(())
+    }
+
+    fn key(&self, file: &Path) -> String {
+        let path = match &self.base {
+            Some(root) => file.strip_prefix(root).unwrap_or(file).to_path_buf(),
+            None => file.canonicalize().unwrap_or_else(|_| file.to_path_buf()),
+        };
+        path.to_string_lossy().replace('\\', "/")
+    }
+}
+
+/// 影响输出结果的参数，序列化后的哈希作为缓存指纹
+#[derive(Serialize)]
+struct CacheSettings<'a> {
+    /// 编码器随版本变化，升级后缓存自动失效
+    version: &'static str,
+    create_new_file: bool,
+    convert_to: &'a Option<ImageType>,
+    video: Option<VideoFormat>,
+    lossless: bool,
+    remove_original: bool,
+    quality: Option<u8>,
+    baseline: bool,
+    chroma: ChromaSubsampling,
+    png: &'a PngOptions,
+    svg: &'a SvgOptions,
+    resize: ResizeOptions,
+    metadata: MetadataOptions,
+    out_dir: &'a Option<PathBuf>,
+    target_ssim: Option<f64>,
+    placeholders: bool,
+}
+
+/// 影响输出结果的参数指纹，参数或编码器版本变化后缓存自动失效
+///
+/// 完整解构 `CompressOptions`，新增选项时编译器会要求在这里决定它是否影响输出
+fn settings_fingerprint(options: &CompressOptions) -> String {
+    let CompressOptions {
+        create_new_file,
+        convert_to,
+        video,
+        lossless,
+        remove_original,
+        quality,
+        baseline,
+        chroma,
+        png,
+        svg,
+        resize,
+        metadata,
+        out_dir,
+        target_ssim,
+        placeholders,
+        // 不影响输出内容
+        cache: _,
+        force: _,
+        preserve_mtime: _,
+        jobs: _,
+        // 生成响应式图片时不使用缓存
+        responsive: _,
+    } = options;
+    let settings = CacheSettings {
+        version: env!("CARGO_PKG_VERSION"),
+        create_new_file: *create_new_file,
+        convert_to,
+        video: *video,
+        lossless: *lossless,
+        remove_original: *remove_original,
+        quality: *quality,
+        baseline: *baseline,
+        chroma: *chroma,
+        png,
+        svg,
+        resize: *resize,
+        metadata: *metadata,
+        out_dir,
+        target_ssim: *target_ssim,
+        placeholders: *placeholders,
+    };
+    // 这些字段都能序列化为 JSON，不会失败
+    let serialized = serde_json::to_vec(&settings).unwrap_or_default();
+    sha256_hex(&serialized)[..16].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_cache_roundtrip_and_settings_change() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let file = root.join("img").join("a.png");
+        let options = CompressOptions { cache: CacheLocation::Project, ..Default::default() };
+
+        let mut cache = OptimizeCache::open(root, &options).unwrap();
+        assert!(!cache.is_fresh(&file, "abc"));
+        cache.record(&file, "abc".to_string());
+        cache.save().unwrap();
+
+        let saved = fs::read_to_string(root.join(DATA_DIR).join(CACHE_FILE_NAME)).unwrap();
+        assert!(saved.contains("img/a.png"));
+
+        let cache = OptimizeCache::open(root, &options).unwrap();
+        assert!(cache.is_fresh(&file, "abc"));
+        assert!(!cache.is_fresh(&file, "changed"));
+
+        let lossless = CompressOptions { lossless: true, ..options.clone() };
+        let cache = OptimizeCache::open(root, &lossless).unwrap();
+        assert!(!cache.is_fresh(&file, "abc"));
+        let placeholders = CompressOptions { placeholders: true, ..options.clone() };
+        assert!(!OptimizeCache::open(root, &placeholders).unwrap().is_fresh(&file, "abc"));
+        // 线程数不影响输出
+        let jobs = CompressOptions { jobs: 4, ..options };
+        assert!(OptimizeCache::open(root, &jobs).unwrap().is_fresh(&file, "abc"));
+
+        assert!(OptimizeCache::open(root, &CompressOptions::default()).is_none());
+    }
+}
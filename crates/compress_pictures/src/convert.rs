@@ -0,0 +1,241 @@
+use crate::metadata::{self, MetadataMode, MetadataOptions};
+use crate::quality::{self, Encoded};
+use crate::{jpeg, placeholder, png, resize, write_new_file, CompressOptions, FileOutcome, ImageType};
+use image::codecs::avif::AvifEncoder;
+use image::{DynamicImage, ImageEncoder, ImageFormat};
+use maya_common::error::{Error, Result};
+use std::fs;
+use std::path::Path;
+
+/// WebP 有损编码的默认质量
+pub(crate) const WEBP_QUALITY: u8 = 80;
+/// AVIF 编码的默认质量
+const AVIF_QUALITY: u8 = 70;
+/// AVIF 编码速度（1 最慢体积最小，10 最快）
+const AVIF_SPEED: u8 = 6;
+
+/// 把PNG/JPEG转换为WebP或AVIF，写入 `output_path`（默认与原图同目录同名）
+///
+/// 像素按 EXIF 方向摆正后再编码，元数据不会带到新文件中
+///
+/// 转换后体积没有变小时不写入文件，返回 0 压缩率；
+/// 使用输出目录时改为把原图复制过去，保持目录结构完整
+pub(crate) fn convert_image(
+    image_path: &Path,
+    output_path: &Path,
+    target: &ImageType,
+    options: &CompressOptions,
+) -> Result<FileOutcome> {
+    let original = fs::read(image_path)?;
+    let format = image::ImageFormat::from_path(image_path)
+        .map_err(|e| Error::compression(format!("无法识别图片格式: {}", e)))?;
+    let img = metadata::decode_oriented(&original, format)?;
+    let (img, resized) = resize::resize_image(img, &options.resize);
+    let placeholder = placeholder::for_image(&img, options)?;
+
+    let Encoded { data: encoded, quality, ssim } = encode_target(&img, target, options)?;
+    let outcome = write_converted(
+        image_path,
+        output_path,
+        &original,
+        target.label(),
+        target.extensions()[0],
+        &encoded,
+        options,
+    )?;
+    if outcome.kept_original {
+        return Ok(FileOutcome { placeholder, ..outcome });
+    }
+    Ok(FileOutcome {
+        // AVIF 只能编码不能读取图片头，直接使用编码时的尺寸
+        dimensions: Some((img.width(), img.height())),
+        resized,
+        quality,
+        ssim,
+        placeholder,
+        ..outcome
+    })
+}
+
+/// 写入转换结果；没有变小时保留原图，使用输出目录时把原图复制过去
+///
+/// # 参数
+/// * `original` - 原图内容
+/// * `target_label` - 目标格式名称，用于提示
+/// * `extension` - 目标格式的扩展名
+/// * `encoded` - 转换后的内容
+pub(crate) fn write_converted(
+    image_path: &Path,
+    output_path: &Path,
+    original: &[u8],
+    target_label: &str,
+    extension: &'static str,
+    encoded: &[u8],
+    options: &CompressOptions,
+) -> Result<FileOutcome> {
+    if encoded.len() >= original.len() {
+        let source_format = source_format(image_path);
+        if options.out_dir.is_some() {
+            let copy_path = output_path.with_extension(image_path.extension().unwrap_or_default());
+            println!(
+                "提示: 文件 {} 转换为 {} 后未变小，原样复制到 {}",
+                image_path.display(),
+                target_label,
+                copy_path.display()
+            );
+            write_new_file(&copy_path, original)?;
+            return Ok(FileOutcome::kept(original, source_format));
+        }
+        println!(
+            "提示: 文件 {} (原始大小: {} bytes) 转换为 {} 后大小为 {} bytes，未变小，跳过转换。",
+            image_path.display(),
+            original.len(),
+            target_label,
+            encoded.len()
+        );
+        return Ok(FileOutcome::kept(original, source_format));
+    }
+
+    write_new_file(output_path, encoded)?;
+    if options.remove_original {
+        fs::remove_file(image_path)?;
+    }
+    Ok(FileOutcome::written(original.len() as u64, encoded, extension, None))
+}
+
+/// 按目标格式编码，质量、目标 SSIM 和无损选项取自 `options`
+///
+/// AVIF 编码结果无法解码比较，总是使用固定质量
+pub(crate) fn encode_target(img: &DynamicImage, target: &ImageType, options: &CompressOptions) -> Result<Encoded> {
+    match target {
+        ImageType::Webp if options.lossless => Ok(Encoded::lossless(encode_webp(img, true, 0.0))),
+        ImageType::Webp => {
+            quality::encode_lossy(img, WEBP_QUALITY, options, |q| Ok(encode_webp(img, false, f32::from(q))))
+        }
+        ImageType::Avif => {
+            let quality = options.quality.unwrap_or(AVIF_QUALITY);
+            Ok(Encoded { data: encode_avif(img, quality)?, quality: Some(quality), ssim: None })
+        }
+        _ => Err(Error::compression(format!("不支持转换为 {}", target.label()))),
+    }
+}
+
+/// 编码为 PNG 或 JPEG，PNG 再经 oxipng 优化，JPEG 的质量取自 `options`
+pub(crate) fn encode_raster(img: &DynamicImage, format: ImageFormat, options: &CompressOptions) -> Result<Vec<u8>> {
+    if format == ImageFormat::Png {
+        let mut data = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut data), ImageFormat::Png)
+            .map_err(|e| Error::compression(format!("图片编码失败: {}", e)))?;
+        let strip = MetadataOptions { mode: MetadataMode::Strip, keep_gps: false };
+        png::optimize_png(&data, &data, &options.png, &strip)
+    } else {
+        quality::encode_lossy(img, jpeg::DEFAULT_JPEG_QUALITY, options, |q| {
+            jpeg::encode_jpeg(img, q, !options.baseline, options.chroma, &[])
+        })
+        .map(|encoded| encoded.data)
+    }
+}
+
+/// 原图格式，保留原图时记录在结果中
+fn source_format(image_path: &Path) -> &'static str {
+    match image::ImageFormat::from_path(image_path) {
+        Ok(image::ImageFormat::Png) => "png",
+        Ok(image::ImageFormat::Gif) => "gif",
+        _ => "jpeg",
+    }
+}
+
+/// WebP 编码，有透明通道时保留 alpha
+pub(crate) fn encode_webp(img: &DynamicImage, lossless: bool, quality: f32) -> Vec<u8> {
+    let (width, height) = (img.width(), img.height());
+    let memory = if img.color().has_alpha() {
+        let rgba = img.to_rgba8();
+        let encoder = webp::Encoder::from_rgba(rgba.as_raw(), width, height);
+        if lossless { encoder.encode_lossless() } else { encoder.encode(quality) }
+    } else {
+        let rgb = img.to_rgb8();
+        let encoder = webp::Encoder::from_rgb(rgb.as_raw(), width, height);
+        if lossless { encoder.encode_lossless() } else { encoder.encode(quality) }
+    };
+    memory.to_vec()
+}
+
+/// AVIF 编码
+fn encode_avif(img: &DynamicImage, quality: u8) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    let encoder = AvifEncoder::new_with_speed_quality(&mut buffer, AVIF_SPEED, quality);
+    let rgba = img.to_rgba8();
+    encoder
+        .write_image(rgba.as_raw(), img.width(), img.height(), image::ExtendedColorType::Rgba8)
+        .map_err(|e| Error::compression(format!("AVIF编码失败: {}", e)))?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+    use tempfile::tempdir;
+
+    /// 生成有渐变的图片，保证编码器有压缩空间
+    fn gradient_png(path: &Path) {
+        let img: ImageBuffer<Rgb<u8>, _> =
+            ImageBuffer::from_fn(64, 64, |x, y| Rgb([(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8]));
+        img.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_convert_to_webp_keeps_original() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("photo.png");
+        gradient_png(&source);
+
+        for lossless in [false, true] {
+            let options = CompressOptions { lossless, ..Default::default() };
+            let webp = temp_dir.path().join("photo.webp");
+            let ratio = convert_image(&source, &webp, &ImageType::Webp, &options).unwrap().ratio;
+            assert!(ratio > 0.0);
+            assert_eq!(image::open(&webp).unwrap().width(), 64);
+            assert!(source.exists());
+        }
+    }
+
+    #[test]
+    fn test_convert_to_avif_removes_original() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("photo.png");
+        gradient_png(&source);
+
+        let options = CompressOptions { remove_original: true, ..Default::default() };
+        let ratio = convert_image(&source, &temp_dir.path().join("photo.avif"), &ImageType::Avif, &options).unwrap().ratio;
+        assert!(ratio > 0.0);
+        assert!(temp_dir.path().join("photo.avif").exists());
+        assert!(!source.exists());
+    }
+
+    #[test]
+    fn test_convert_rejects_conflicting_targets() {
+        use crate::compress_images;
+
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let img: ImageBuffer<Rgb<u8>, _> = ImageBuffer::from_fn(64, 64, |x, y| Rgb([x as u8 * 4, y as u8 * 4, 128]));
+        img.save(root.join("logo.png")).unwrap();
+        img.save(root.join("logo.jpg")).unwrap();
+        let options = CompressOptions { convert_to: Some(ImageType::Webp), ..Default::default() };
+        let error = compress_images(root, ImageType::All, &options).unwrap_err();
+        assert!(error.to_string().contains("logo.webp"));
+        assert!(!root.join("logo.webp").exists());
+
+        // 已有的同名文件不会被覆盖，除非指定 force
+        fs::remove_file(root.join("logo.jpg")).unwrap();
+        fs::write(root.join("logo.webp"), b"hand made").unwrap();
+        let summary = compress_images(root, ImageType::Png, &options).unwrap();
+        assert_eq!(summary.failed(), 1);
+        assert_eq!(fs::read(root.join("logo.webp")).unwrap(), b"hand made");
+
+        let forced = CompressOptions { force: true, ..options };
+        assert_eq!(compress_images(root, ImageType::Png, &forced).unwrap().successful(), 1);
+        assert_ne!(fs::read(root.join("logo.webp")).unwrap(), b"hand made");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,248 @@
+use crate::metadata::JpegMarker;
+use image::DynamicImage;
+use maya_common::error::{Error, Result};
+use mozjpeg_sys as ffi;
+use serde::Serialize;
+use std::any::Any;
+use std::os::raw::{c_int, c_ulong};
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::{mem, ptr, slice};
+
+/// 有损编码的默认质量
+pub const DEFAULT_JPEG_QUALITY: u8 = 80;
+
+/// 色度抽样方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum ChromaSubsampling {
+    /// 4:2:0，体积最小，适合照片
+    #[default]
+    S420,
+    /// 4:2:2
+    S422,
+    /// 4:4:4，不抽样，适合有细小彩色文字或线条的图片
+    S444,
+}
+
+impl ChromaSubsampling {
+    /// 转换为 mozjpeg 使用的 (Cb, Cr) 色度像素尺寸
+    fn pixel_sizes(self) -> ((u8, u8), (u8, u8)) {
+        match self {
+            ChromaSubsampling::S420 => ((2, 2), (2, 2)),
+            ChromaSubsampling::S422 => ((2, 1), (2, 1)),
+            ChromaSubsampling::S444 => ((1, 1), (1, 1)),
+        }
+    }
+}
+
+impl FromStr for ChromaSubsampling {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.replace(':', "").as_str() {
+            "420" => Ok(ChromaSubsampling::S420),
+            "422" => Ok(ChromaSubsampling::S422),
+            "444" => Ok(ChromaSubsampling::S444),
+            _ => Err(format!("不支持的色度抽样: {}（可选 420、422、444）", s)),
+        }
+    }
+}
+
+/// 使用 mozjpeg 有损编码，始终启用哈夫曼表优化
+///
+/// # 参数
+/// * `img` - 解码后的图片
+/// * `quality` - 质量 1-100
+/// * `progressive` - 是否输出渐进式 JPEG
+/// * `chroma` - 色度抽样方式
+/// * `markers` - 要写入的 APPn/COM 标记段
+pub(crate) fn encode_jpeg(
+    img: &DynamicImage,
+    quality: u8,
+    progressive: bool,
+    chroma: ChromaSubsampling,
+    markers: &[JpegMarker],
+) -> Result<Vec<u8>> {
+    let grayscale = !img.color().has_color();
+    let (color_space, pixels) = if grayscale {
+        (mozjpeg::ColorSpace::JCS_GRAYSCALE, img.to_luma8().into_raw())
+    } else {
+        (mozjpeg::ColorSpace::JCS_RGB, img.to_rgb8().into_raw())
+    };
+
+    // mozjpeg 通过 unwind 报告 libjpeg 内部错误
+    let result = panic::catch_unwind(AssertUnwindSafe(|| -> std::io::Result<Vec<u8>> {
+        let mut compress = mozjpeg::Compress::new(color_space);
+        compress.set_size(img.width() as usize, img.height() as usize);
+        compress.set_quality(quality as f32);
+        compress.set_optimize_coding(true);
+        if progressive {
+            compress.set_progressive_mode();
+        } else {
+            compress.set_optimize_scans(false);
+        }
+        if !grayscale {
+            let (cb, cr) = chroma.pixel_sizes();
+            compress.set_chroma_sampling_pixel_sizes(cb, cr);
+        }
+
+        let mut started = compress.start_compress(Vec::new())?;
+        for marker in markers {
+            started.write_marker(mozjpeg::Marker::from(marker.code), &marker.data);
+        }
+        started.write_scanlines(&pixels)?;
+        started.finish()
+    }));
+
+    match result {
+        Ok(Ok(data)) => Ok(data),
+        Ok(Err(e)) => Err(Error::compression(format!("JPEG编码失败: {}", e))),
+        Err(payload) => Err(Error::compression(format!("JPEG编码失败: {}", panic_message(payload)))),
+    }
+}
+
+/// 无损优化 JPEG：直接转写 DCT 系数，不重新量化，画质不变
+///
+/// 相当于 `jpegtran -optimize [-progressive]`，原图的标记段由 `markers` 指定
+pub(crate) fn optimize_lossless(data: &[u8], progressive: bool, markers: &[JpegMarker]) -> Result<Vec<u8>> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+        let mut transcoder = Transcoder::new();
+        transcoder.run(data, progressive, markers)
+    }));
+    result.map_err(|payload| Error::compression(format!("JPEG无损优化失败: {}", panic_message(payload))))
+}
+
+/// libjpeg 系数转写所需的状态，放在同一个堆分配中，保证内部指针稳定
+struct Transcoder {
+    err: ffi::jpeg_error_mgr,
+    src: ffi::jpeg_decompress_struct,
+    dst: ffi::jpeg_compress_struct,
+    out_buffer: *mut u8,
+    out_size: c_ulong,
+}
+
+impl Transcoder {
+    unsafe fn new() -> Box<Self> {
+        let mut transcoder: Box<Self> = Box::new(mem::zeroed());
+        ffi::jpeg_std_error(&mut transcoder.err);
+        transcoder.err.error_exit = Some(unwind_error_exit);
+        transcoder.err.emit_message = Some(silence_message);
+
+        let err: *mut ffi::jpeg_error_mgr = &mut transcoder.err;
+        transcoder.src.common.err = err;
+        ffi::jpeg_create_decompress(&mut transcoder.src);
+        transcoder.dst.common.err = err;
+        ffi::jpeg_create_compress(&mut transcoder.dst);
+        transcoder
+    }
+
+    unsafe fn run(&mut self, data: &[u8], progressive: bool, markers: &[JpegMarker]) -> Vec<u8> {
+        ffi::jpeg_mem_src(&mut self.src, data.as_ptr(), data.len() as c_ulong);
+        ffi::jpeg_read_header(&mut self.src, 1);
+        let coefficients = ffi::jpeg_read_coefficients(&mut self.src);
+
+        ffi::jpeg_copy_critical_parameters(&self.src, &mut self.dst);
+        self.dst.optimize_coding = 1;
+        if progressive {
+            ffi::jpeg_simple_progression(&mut self.dst);
+        } else {
+            // mozjpeg 默认输出渐进式，基线模式需要关闭扫描优化并清空扫描脚本
+            ffi::jpeg_c_set_bool_param(&mut self.dst, ffi::J_BOOLEAN_PARAM::JBOOLEAN_OPTIMIZE_SCANS, 0);
+            self.dst.scan_info = ptr::null();
+            self.dst.num_scans = 0;
+        }
+
+        ffi::jpeg_mem_dest(&mut self.dst, &mut self.out_buffer, &mut self.out_size);
+        ffi::jpeg_write_coefficients(&mut self.dst, coefficients);
+        for marker in markers {
+            ffi::jpeg_write_marker(&mut self.dst, marker.code as c_int, marker.data.as_ptr(), marker.data.len() as _);
+        }
+        ffi::jpeg_finish_compress(&mut self.dst);
+        ffi::jpeg_finish_decompress(&mut self.src);
+
+        slice::from_raw_parts(self.out_buffer, self.out_size as usize).to_vec()
+    }
+}
+
+impl Drop for Transcoder {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::jpeg_destroy_compress(&mut self.dst);
+            ffi::jpeg_destroy_decompress(&mut self.src);
+            if !self.out_buffer.is_null() {
+                libc::free(self.out_buffer.cast());
+            }
+        }
+    }
+}
+
+/// libjpeg 的致命错误处理函数不能返回，这里通过 unwind 回到 `catch_unwind`
+extern "C-unwind" fn unwind_error_exit(cinfo: &mut ffi::jpeg_common_struct) {
+    let code = unsafe { cinfo.err.as_ref().map(|err| err.msg_code).unwrap_or_default() };
+    panic::resume_unwind(Box::new(format!("libjpeg 错误码 {}", code)));
+}
+
+extern "C-unwind" fn silence_message(_cinfo: &mut ffi::jpeg_common_struct, _level: c_int) {}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else {
+        "未知错误".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    fn sample_image() -> DynamicImage {
+        DynamicImage::ImageRgb8(ImageBuffer::from_fn(64, 48, |x, y| {
+            Rgb([(x * 4) as u8, (y * 5) as u8, ((x * y) % 256) as u8])
+        }))
+    }
+
+    /// 渐进式 JPEG 使用 SOF2 帧头
+    fn is_progressive(data: &[u8]) -> bool {
+        data.windows(2).any(|w| w == [0xFF, 0xC2])
+    }
+
+    #[test]
+    fn test_encode_jpeg_quality_and_progressive() {
+        let img = sample_image();
+        let high = encode_jpeg(&img, 95, true, ChromaSubsampling::S444, &[]).unwrap();
+        let low = encode_jpeg(&img, 30, true, ChromaSubsampling::S420, &[]).unwrap();
+        assert!(low.len() < high.len());
+        assert!(is_progressive(&high));
+
+        let baseline = encode_jpeg(&img, 80, false, ChromaSubsampling::default(), &[]).unwrap();
+        assert!(!is_progressive(&baseline));
+        assert_eq!(image::load_from_memory(&baseline).unwrap().width(), 64);
+    }
+
+    #[test]
+    fn test_optimize_lossless_keeps_pixels() {
+        let mut original = Vec::new();
+        sample_image()
+            .write_to(&mut std::io::Cursor::new(&mut original), image::ImageFormat::Jpeg)
+            .unwrap();
+
+        let optimized = optimize_lossless(&original, true, &[]).unwrap();
+        assert!(is_progressive(&optimized));
+        let before = image::load_from_memory(&original).unwrap().to_rgb8();
+        let after = image::load_from_memory(&optimized).unwrap().to_rgb8();
+        assert_eq!(before, after);
+
+        assert!(optimize_lossless(b"not a jpeg", false, &[]).is_err());
+    }
+
+    #[test]
+    fn test_chroma_from_str() {
+        assert_eq!("4:2:2".parse::<ChromaSubsampling>().unwrap(), ChromaSubsampling::S422);
+        assert_eq!("444".parse::<ChromaSubsampling>().unwrap(), ChromaSubsampling::S444);
+        assert!("411".parse::<ChromaSubsampling>().is_err());
+    }
+}
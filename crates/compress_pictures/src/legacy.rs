@@ -0,0 +1,235 @@
+use crate::{convert, write_new_file, CompressOptions, ImageType};
+use image::{DynamicImage, ImageFormat, ImageReader, RgbImage};
+use maya_common::error::{Error, Result};
+use maya_common::file_utils::find_files_by_extension;
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// 可以批量转换为现代格式的旧图片格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyFormat {
+    Bmp,
+    Tiff,
+    Tga,
+    /// PPM/PGM/PBM 等 Netpbm 格式
+    Pnm,
+}
+
+impl LegacyFormat {
+    /// 对应的文件扩展名
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            LegacyFormat::Bmp => &["bmp"],
+            LegacyFormat::Tiff => &["tif", "tiff"],
+            LegacyFormat::Tga => &["tga"],
+            LegacyFormat::Pnm => &["pnm", "ppm", "pgm", "pbm"],
+        }
+    }
+
+    /// 显示名称
+    pub fn label(self) -> &'static str {
+        match self {
+            LegacyFormat::Bmp => "BMP",
+            LegacyFormat::Tiff => "TIFF",
+            LegacyFormat::Tga => "TGA",
+            LegacyFormat::Pnm => "PNM",
+        }
+    }
+}
+
+impl FromStr for LegacyFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bmp" => Ok(LegacyFormat::Bmp),
+            "tif" | "tiff" => Ok(LegacyFormat::Tiff),
+            "tga" => Ok(LegacyFormat::Tga),
+            "pnm" | "ppm" | "pgm" | "pbm" => Ok(LegacyFormat::Pnm),
+            _ => Err(format!("不支持的源格式: {}（可选 bmp、tiff、tga、pnm）", s)),
+        }
+    }
+}
+
+/// 批量把目录中的旧格式图片转换为 PNG、JPEG、WebP 或 AVIF
+///
+/// 转换结果写在原图旁边，文件名相同、扩展名改为目标格式；原图保留，也不修改其他文件中对原图的引用。
+/// 目标文件已存在时不覆盖，计为失败；多个图片会转换为同一个文件（如 `a.tif` 和 `a.tiff`）时直接报错
+///
+/// # 参数
+/// * `path` - 搜索图片的目录路径
+/// * `source` - 源格式
+/// * `target` - 目标格式
+///
+/// # 返回
+/// * `Result<(u32, u32)>` - (成功转换的文件数量, 失败的文件数量)
+pub fn convert_legacy_images(path: &Path, source: LegacyFormat, target: &ImageType) -> Result<(u32, u32)> {
+    if !matches!(target, ImageType::Png | ImageType::Jpg | ImageType::Jpeg | ImageType::Webp | ImageType::Avif) {
+        return Err(Error::invalid_argument(format!(
+            "不支持转换为 {}（可选 png、jpg、jpeg、webp、avif）",
+            target.label()
+        )));
+    }
+
+    println!("开始扫描{}文件...", source.label());
+
+    let files = find_files_by_extension(path, source.extensions())?;
+    if files.is_empty() {
+        println!("未找到任何{}文件", source.label());
+        return Ok((0, 0));
+    }
+
+    println!("找到 {} 个{}文件", files.len(), source.label());
+    check_duplicate_targets(&files, target)?;
+
+    let options = CompressOptions::default();
+    let successful_conversions = files
+        .par_iter()
+        .filter(|file| match convert_single(file, target, &options) {
+            Ok(output) => {
+                println!("✅ 成功转换: {} -> {}", file.display(), output.display());
+                true
+            }
+            Err(e) => {
+                eprintln!("❌ 转换失败 {}: {}", file.display(), e);
+                false
+            }
+        })
+        .count() as u32;
+    let failed_conversions = files.len() as u32 - successful_conversions;
+
+    println!("\n--- 转换总结 ---");
+    println!("总共处理文件数量: {}", files.len());
+    println!("成功转换文件数量: {}", successful_conversions);
+    println!("失败转换文件数量: {}", failed_conversions);
+    println!("--------------------");
+
+    Ok((successful_conversions, failed_conversions))
+}
+
+/// 同一目录中文件名相同、扩展名不同的图片会写到同一个文件，转换前报错
+fn check_duplicate_targets(files: &[PathBuf], target: &ImageType) -> Result<()> {
+    let mut targets: BTreeMap<PathBuf, Vec<&Path>> = BTreeMap::new();
+    for file in files {
+        targets.entry(target_path(file, target)).or_default().push(file);
+    }
+    let conflicts: Vec<String> = targets
+        .iter()
+        .filter(|(_, sources)| sources.len() > 1)
+        .map(|(output, sources)| {
+            let sources: Vec<String> = sources.iter().map(|source| source.display().to_string()).collect();
+            format!("{} -> {}", sources.join(", "), output.display())
+        })
+        .collect();
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    Err(Error::invalid_argument(format!("多个图片会转换为同一个文件，请先重命名: {}", conflicts.join("; "))))
+}
+
+/// 转换结果的路径：原图旁边，扩展名改为目标格式
+fn target_path(image_path: &Path, target: &ImageType) -> PathBuf {
+    image_path.with_extension(target.extensions()[0])
+}
+
+/// 转换单个文件，返回输出路径
+fn convert_single(image_path: &Path, target: &ImageType, options: &CompressOptions) -> Result<PathBuf> {
+    let output_path = target_path(image_path, target);
+    if output_path.exists() {
+        return Err(Error::compression(format!("目标文件已存在: {}", output_path.display())));
+    }
+
+    let img = ImageReader::open(image_path)?
+        .with_guessed_format()?
+        .decode()
+        .map_err(|e| Error::compression(format!("图片解码失败: {}", e)))?;
+
+    let data = match target {
+        ImageType::Png => convert::encode_raster(&img, ImageFormat::Png, options)?,
+        // JPEG 没有透明通道，透明区域铺白底，避免变成黑色
+        ImageType::Jpg | ImageType::Jpeg => convert::encode_raster(&flatten(&img), ImageFormat::Jpeg, options)?,
+        _ => convert::encode_target(&img, target, options)?.data,
+    };
+
+    write_new_file(&output_path, &data)?;
+    Ok(output_path)
+}
+
+/// 把带透明通道的图片合成到白色背景上
+fn flatten(img: &DynamicImage) -> Cow<'_, DynamicImage> {
+    if !img.color().has_alpha() {
+        return Cow::Borrowed(img);
+    }
+    let rgba = img.to_rgba8();
+    let mut rgb = RgbImage::new(img.width(), img.height());
+    for (src, dst) in rgba.pixels().zip(rgb.pixels_mut()) {
+        let alpha = u32::from(src[3]);
+        for channel in 0..3 {
+            dst[channel] = ((u32::from(src[channel]) * alpha + 255 * (255 - alpha) + 127) / 255) as u8;
+        }
+    }
+    Cow::Owned(DynamicImage::ImageRgb8(rgb))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, Rgba, RgbaImage};
+
+    #[test]
+    fn test_convert_bmp_and_tiff() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        RgbImage::from_pixel(16, 8, Rgb([10, 200, 30])).save(dir.path().join("a.bmp")).unwrap();
+        RgbImage::from_pixel(16, 8, Rgb([10, 200, 30])).save(nested.join("b.bmp")).unwrap();
+        std::fs::write(dir.path().join("broken.bmp"), b"not a bmp").unwrap();
+
+        let (successful, failed) = convert_legacy_images(dir.path(), LegacyFormat::Bmp, &ImageType::Png).unwrap();
+        assert_eq!((successful, failed), (2, 1));
+        assert!(dir.path().join("a.bmp").exists());
+        let png = image::open(nested.join("b.png")).unwrap().to_rgb8();
+        assert_eq!(png.dimensions(), (16, 8));
+        assert_eq!(png.get_pixel(3, 3), &Rgb([10, 200, 30]));
+
+        // 透明像素转 JPEG 后为白色
+        let mut rgba = RgbaImage::from_pixel(16, 16, Rgba([0, 0, 0, 0]));
+        rgba.put_pixel(0, 0, Rgba([0, 0, 0, 255]));
+        rgba.save(dir.path().join("c.tiff")).unwrap();
+        let result = convert_legacy_images(dir.path(), "tif".parse().unwrap(), &ImageType::Jpg).unwrap();
+        assert_eq!(result, (1, 0));
+        let jpg = image::open(dir.path().join("c.jpg")).unwrap().to_rgb8();
+        assert!(jpg.get_pixel(12, 12).0.iter().all(|&c| c > 245));
+    }
+
+    #[test]
+    fn test_convert_keeps_existing_and_rejects_duplicate_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        RgbImage::from_pixel(8, 8, Rgb([10, 200, 30])).save(dir.path().join("logo.bmp")).unwrap();
+        RgbImage::from_pixel(8, 8, Rgb([10, 200, 30])).save(dir.path().join("icon.bmp")).unwrap();
+        std::fs::write(dir.path().join("logo.png"), b"hand made").unwrap();
+        let result = convert_legacy_images(dir.path(), LegacyFormat::Bmp, &ImageType::Png).unwrap();
+        assert_eq!(result, (1, 1));
+        assert_eq!(std::fs::read(dir.path().join("logo.png")).unwrap(), b"hand made");
+        assert!(dir.path().join("icon.png").exists());
+
+        // a.ppm 和 a.pgm 都会写到 a.webp，转换前报错
+        RgbImage::from_pixel(8, 8, Rgb([1, 2, 3])).save(dir.path().join("a.ppm")).unwrap();
+        image::GrayImage::from_pixel(8, 8, image::Luma([9])).save(dir.path().join("a.pgm")).unwrap();
+        let error = convert_legacy_images(dir.path(), LegacyFormat::Pnm, &ImageType::Webp).unwrap_err();
+        assert!(error.to_string().contains("a.webp"));
+        assert!(!dir.path().join("a.webp").exists());
+    }
+
+    #[test]
+    fn test_unsupported_formats() {
+        assert!("gif".parse::<LegacyFormat>().is_err());
+        assert_eq!("PPM".parse::<LegacyFormat>(), Ok(LegacyFormat::Pnm));
+        let dir = tempfile::tempdir().unwrap();
+        assert!(convert_legacy_images(dir.path(), LegacyFormat::Bmp, &ImageType::Gif).is_err());
+        assert_eq!(convert_legacy_images(dir.path(), LegacyFormat::Tga, &ImageType::Webp).unwrap(), (0, 0));
+    }
+}
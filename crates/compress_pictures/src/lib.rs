@@ -1,23 +1,221 @@
 use image::{self};
+use maya_common::atomic::{write_atomic, AtomicWriteOptions};
 use maya_common::error::{Error, Result};
 use maya_common::file_utils::find_files_by_extension;
-use oxipng::{optimize_from_memory, Options};
-use rayon::prelude::*;
+use serde::Serialize;
 use std::fs;
-use std::io::{BufReader, BufWriter};
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+mod animation;
+mod cache;
+mod convert;
+mod jpeg;
+mod legacy;
+mod metadata;
+mod pipeline;
+mod placeholder;
+mod png;
+mod quality;
+mod report;
+mod resize;
+mod responsive;
+mod svg;
+mod video;
+
+pub use cache::{CacheLocation, CACHE_FILE_NAME};
+pub use jpeg::{ChromaSubsampling, DEFAULT_JPEG_QUALITY};
+pub use legacy::{convert_legacy_images, LegacyFormat};
+pub use metadata::{MetadataMode, MetadataOptions};
+pub use pipeline::{compress_images, CompressSummary, FileResult, FileStatus};
+pub use placeholder::{Placeholder, PLACEHOLDER_MANIFEST};
+pub use png::{parse_quality_range, PngOptions, PngStrip, DEFAULT_PNG_LEVEL};
+pub use report::{write_report, ReportFormat, REPORT_FILE_STEM};
+pub use resize::{Fit, ResizeOptions, Resized};
+pub use responsive::{
+    parse_widths, ResponsiveImage, ResponsiveOptions, Variant, VariantSet, DEFAULT_SIZES, RESPONSIVE_MANIFEST,
+    RESPONSIVE_SNIPPETS,
+};
+pub use svg::{SvgOptions, DEFAULT_SVG_PRECISION};
+pub use video::VideoFormat;
+
 const STREAMING_THRESHOLD: u64 = 10 * 1024 * 1024; // 10 MB
 /// 压缩图片类型枚举
-#[derive(Debug, PartialEq)]
+///
+/// `Webp` 和 `Avif` 只作为转换的目标格式使用
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum ImageType {
     Png,
     Jpg,
     Jpeg,
+    Gif,
+    Svg,
+    Webp,
+    Avif,
     All,
 }
 
+impl ImageType {
+    /// 对应的文件扩展名，第一个为转换时使用的扩展名
+    pub fn extensions(&self) -> Vec<&'static str> {
+        match self {
+            ImageType::Png => vec!["png"],
+            ImageType::Jpg => vec!["jpg"],
+            ImageType::Jpeg => vec!["jpeg"],
+            ImageType::Gif => vec!["gif"],
+            ImageType::Svg => vec!["svg"],
+            ImageType::Webp => vec!["webp"],
+            ImageType::Avif => vec!["avif"],
+            ImageType::All => vec!["png", "jpg", "jpeg"],
+        }
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImageType::Png => "PNG",
+            ImageType::Jpg => "JPG",
+            ImageType::Jpeg => "JPEG",
+            ImageType::Gif => "GIF",
+            ImageType::Svg => "SVG",
+            ImageType::Webp => "WebP",
+            ImageType::Avif => "AVIF",
+            ImageType::All => "所有支持的",
+        }
+    }
+
+    /// 是否可以作为转换的目标格式
+    pub fn is_convert_target(&self) -> bool {
+        matches!(self, ImageType::Webp | ImageType::Avif)
+    }
+}
+
+/// 图片压缩选项
+#[derive(Debug, Clone, Default)]
+pub struct CompressOptions {
+    /// 是否创建新文件（添加_c后缀）而不是覆盖原文件
+    pub create_new_file: bool,
+    /// 转换的目标格式（WebP/AVIF），为 `None` 时保持原格式压缩
+    pub convert_to: Option<ImageType>,
+    /// 把 GIF 动图转换为视频，优先于 `convert_to`
+    pub video: Option<VideoFormat>,
+    /// WebP 使用无损编码；JPEG 只做无损优化，不重新量化
+    pub lossless: bool,
+    /// 转换成功后删除原图
+    pub remove_original: bool,
+    /// 有损编码质量 1-100，为 `None` 时使用各格式的默认值
+    pub quality: Option<u8>,
+    /// JPEG 输出基线格式，默认输出渐进式
+    pub baseline: bool,
+    /// JPEG 色度抽样方式
+    pub chroma: ChromaSubsampling,
+    /// PNG 量化和 oxipng 选项
+    pub png: PngOptions,
+    /// SVG 压缩选项
+    pub svg: SvgOptions,
+    /// 最大尺寸限制，超出时先缩小再编码
+    pub resize: ResizeOptions,
+    /// 元数据处理方式
+    pub metadata: MetadataOptions,
+    /// 优化缓存位置，内容和参数未变化的图片会被跳过
+    pub cache: CacheLocation,
+    /// 忽略缓存，重新处理所有图片；转换格式时允许覆盖原图旁边已有的同名文件
+    pub force: bool,
+    /// 覆写原图时保留原来的修改时间
+    pub preserve_mtime: bool,
+    /// 输出目录，按源目录结构保存优化后的图片，原图保持不变
+    pub out_dir: Option<PathBuf>,
+    /// 并行处理的线程数，0 表示使用全部 CPU 核心
+    pub jobs: usize,
+    /// 生成响应式图片，设置后原图保持不变
+    pub responsive: Option<ResponsiveOptions>,
+    /// 目标 SSIM，设置后为每张图片搜索满足目标的最低质量，代替固定的 `quality`
+    pub target_ssim: Option<f64>,
+    /// 生成懒加载占位数据（BlurHash、主色和内联预览图），写入占位数据清单
+    pub placeholders: bool,
+}
+
+impl CompressOptions {
+    /// 转换格式时输出文件使用的扩展名
+    fn target_extension(&self) -> Option<&'static str> {
+        match (self.video, &self.convert_to) {
+            (Some(video), _) => Some(video.extension()),
+            (None, Some(target)) => Some(target.extensions()[0]),
+            (None, None) => None,
+        }
+    }
+
+    /// 覆写原图时使用的原子写入选项
+    fn write_options(&self) -> AtomicWriteOptions {
+        AtomicWriteOptions { preserve_mtime: self.preserve_mtime, ..Default::default() }
+    }
+}
+
+/// 成功处理的单个图片的结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileOutcome {
+    /// 压缩率
+    pub ratio: f64,
+    /// 调整尺寸前后的大小，未调整时为 `None`
+    pub resized: Option<Resized>,
+    /// 原图字节数
+    pub original_size: u64,
+    /// 输出文件字节数，保留原图时等于原图大小
+    pub output_size: u64,
+    /// 输出格式（png/jpeg/webp/avif）
+    pub format: &'static str,
+    /// 输出图片的宽高
+    pub dimensions: Option<(u32, u32)>,
+    /// 压缩后没有变小，保留了原图内容
+    pub kept_original: bool,
+    /// 生成的响应式图片
+    pub responsive: Option<ResponsiveImage>,
+    /// 有损编码使用的质量
+    pub quality: Option<u8>,
+    /// 按目标 SSIM 编码时达到的 SSIM
+    pub ssim: Option<f64>,
+    /// 懒加载占位数据
+    pub placeholder: Option<Placeholder>,
+}
+
+impl FileOutcome {
+    /// 写出了新内容
+    fn written(original_size: u64, data: &[u8], format: &'static str, resized: Option<Resized>) -> Self {
+        Self {
+            ratio: 1.0 - data.len() as f64 / original_size as f64,
+            resized,
+            original_size,
+            output_size: data.len() as u64,
+            format,
+            dimensions: image_dimensions(data),
+            kept_original: false,
+            ..Default::default()
+        }
+    }
+
+    /// 体积没有变小，保留原图内容
+    fn kept(original: &[u8], format: &'static str) -> Self {
+        Self {
+            original_size: original.len() as u64,
+            output_size: original.len() as u64,
+            format,
+            dimensions: image_dimensions(original),
+            kept_original: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// 只读取图片头获取宽高
+fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
 impl FromStr for ImageType {
     type Err = String;
 
@@ -26,210 +224,101 @@ impl FromStr for ImageType {
             "png" => Ok(ImageType::Png),
             "jpg" => Ok(ImageType::Jpg),
             "jpeg" => Ok(ImageType::Jpeg),
+            "gif" => Ok(ImageType::Gif),
+            "svg" => Ok(ImageType::Svg),
+            "webp" => Ok(ImageType::Webp),
+            "avif" => Ok(ImageType::Avif),
             "all" => Ok(ImageType::All),
             _ => Err(format!("不支持的图片类型: {}", s)),
         }
     }
 }
 
-/// 压缩图片函数
+/// 查找要处理的图片
+///
+/// 跳过以前生成的 `_c` 文件（同目录下存在去掉后缀的原图）和位于输出目录中的文件
 ///
 /// # 参数
-/// * `path` - 目录路径
+/// * `root` - 源目录
 /// * `img_type` - 图片类型
-/// * `create_new_file` - 是否创建新文件（添加_c后缀）而不是覆盖原文件
-///
-/// # 返回
-/// * `Result<(u32, u32, f64)>` - (成功压缩的文件数量, 失败的文件数量, 平均压缩率)
-pub fn compress_images(
-    path: &Path,
-    img_type: ImageType,
-    create_new_file: bool,
-) -> Result<(u32, u32, f64)> {
-    println!(
-        "开始压缩 {} 图片...",
-        match img_type {
-            ImageType::Png => "PNG",
-            ImageType::Jpg => "JPG",
-            ImageType::Jpeg => "JPEG",
-            ImageType::All => "所有支持的",
-        }
-    );
-
-    let mut successful_compressions = 0;
-    let mut failed_compressions = 0;
-    let mut total_compression_ratio_sum = 0.0;
-    let mut processed_files_count = 0; // 用于计算平均压缩率的分母
-
-    // 根据图片类型获取扩展名列表
-    let extensions: Vec<&str> = match img_type {
-        ImageType::Png => vec!["png"],
-        ImageType::Jpg => vec!["jpg"],
-        ImageType::Jpeg => vec!["jpeg"],
-        ImageType::All => vec!["png", "jpg", "jpeg"],
+/// * `options` - 压缩选项
+pub fn find_images(root: &Path, img_type: &ImageType, options: &CompressOptions) -> Result<Vec<PathBuf>> {
+    let out_dir = match &options.out_dir {
+        Some(out_dir) => mirrored_dir(root, out_dir)?,
+        None => None,
     };
+    let files = find_files_by_extension(root, &img_type.extensions())?;
+    Ok(files
+        .into_iter()
+        .filter(|file| !is_generated_copy(file) && !responsive::is_generated_variant(file))
+        .filter(|file| !out_dir.as_ref().is_some_and(|dir| file.starts_with(dir)))
+        .collect())
+}
 
-    // 使用共享的文件遍历函数查找匹配的图片文件
-    let image_files = find_files_by_extension(path, &extensions)?;
-
-    for file_path in image_files {
-        processed_files_count += 1; // 标记为已处理，无论成功与否
-        match compress_image(&file_path, create_new_file) {
-            Ok(ratio) => {
-                successful_compressions += 1;
-                total_compression_ratio_sum += ratio;
-                println!(
-                    "成功压缩: {} (压缩率: {:.2}%)",
-                    file_path.display(),
-                    ratio * 100.0
-                );
-            }
-            Err(e) => {
-                failed_compressions += 1;
-                eprintln!("压缩失败 {}: {}", file_path.display(), e);
-            }
-        }
+/// 输出目录位于源目录内部时，返回它在源目录下的路径，用于在遍历时排除
+fn mirrored_dir(root: &Path, out_dir: &Path) -> Result<Option<PathBuf>> {
+    let (Ok(root_abs), Ok(out_abs)) = (root.canonicalize(), out_dir.canonicalize()) else {
+        return Ok(None);
+    };
+    if root_abs == out_abs {
+        return Err(Error::invalid_argument(format!("输出目录不能与源目录相同: {}", out_dir.display())));
     }
+    Ok(out_abs.strip_prefix(&root_abs).ok().map(|relative| root.join(relative)))
+}
 
-    let avg_compression_ratio = if successful_compressions > 0 {
-        // 平均压缩率只基于成功压缩的文件
-        total_compression_ratio_sum / successful_compressions as f64
-    } else {
-        0.0
+/// 是否为以前生成的 `<stem>_c.<ext>` 文件
+fn is_generated_copy(path: &Path) -> bool {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let Some(original_stem) = stem.strip_suffix("_c") else {
+        return false;
     };
-
-    println!(
-        "
---- 压缩总结 ---"
-    );
-    println!("总共处理图片数量: {}", processed_files_count);
-    println!("成功压缩文件数量: {}", successful_compressions);
-    println!("失败压缩文件数量: {}", failed_compressions);
-    if successful_compressions > 0 {
-        println!(
-            "成功文件的平均压缩率: {:.2}%",
-            avg_compression_ratio * 100.0
-        );
-    } else {
-        println!("没有文件被成功压缩。");
+    let mut original = PathBuf::from(original_stem);
+    if let Some(extension) = path.extension() {
+        original.set_extension(extension);
     }
-    println!("--------------------");
-
-    Ok((
-        successful_compressions,
-        failed_compressions,
-        avg_compression_ratio,
-    ))
+    path.with_file_name(original).is_file()
 }
 
-/// 并行压缩图片函数
-///
-/// # 参数
-/// * `path` - 目录路径
-/// * `img_type` - 图片类型
-/// * `create_new_file` - 是否创建新文件（添加_c后缀）而不是覆盖原文件
-///
-/// # 返回
-/// * `Result<(u32, u32, f64)>` - (成功压缩的文件数量, 失败的文件数量, 平均压缩率)
-pub fn compress_images_parallel(
-    path: &Path,
-    img_type: ImageType,
-    create_new_file: bool,
-) -> Result<(u32, u32, f64)> {
-    println!(
-        "开始并行压缩 {} 图片...",
-        match img_type {
-            ImageType::Png => "PNG",
-            ImageType::Jpg => "JPG",
-            ImageType::Jpeg => "JPEG",
-            ImageType::All => "所有支持的",
+/// 计算图片的输出路径：输出目录 > `_c` 新文件 > 覆写原图；转换格式时替换扩展名
+fn output_path(root: &Path, image_path: &Path, options: &CompressOptions) -> PathBuf {
+    let path = match &options.out_dir {
+        Some(out_dir) => {
+            let relative = image_path
+                .strip_prefix(root)
+                .unwrap_or_else(|_| Path::new(image_path.file_name().unwrap_or_default()));
+            out_dir.join(relative)
         }
-    );
-
-    // 根据图片类型获取扩展名列表
-    let extensions: Vec<&str> = match img_type {
-        ImageType::Png => vec!["png"],
-        ImageType::Jpg => vec!["jpg"],
-        ImageType::Jpeg => vec!["jpeg"],
-        ImageType::All => vec!["png", "jpg", "jpeg"],
-    };
-
-    // 使用共享的文件遍历函数查找匹配的图片文件
-    let image_files = find_files_by_extension(path, &extensions)?;
-
-    // 并行处理每个文件
-    let results: Vec<(PathBuf, Result<f64>)> = image_files
-        .par_iter()
-        .map(|file_path| {
-            let result = compress_image(file_path, create_new_file);
-            (file_path.clone(), result)
-        })
-        .collect();
-
-    let mut successful_compressions = 0;
-    let mut failed_compressions = 0;
-    let mut total_compression_ratio_sum = 0.0;
-
-    // 处理结果并输出信息
-    for (file_path, result) in results.iter() {
-        match result {
-            Ok(ratio) => {
-                successful_compressions += 1;
-                total_compression_ratio_sum += *ratio;
-                println!(
-                    "成功压缩: {} (压缩率: {:.2}%)",
-                    file_path.display(),
-                    *ratio * 100.0
-                );
-            }
-            Err(e) => {
-                failed_compressions += 1;
-                eprintln!("压缩失败 {}: {}", file_path.display(), e);
-            }
+        None if options.create_new_file && options.target_extension().is_none() && options.responsive.is_none() => {
+            create_output_path(image_path, "_c")
         }
-    }
-
-    let avg_compression_ratio = if successful_compressions > 0 {
-        // 平均压缩率只基于成功压缩的文件
-        total_compression_ratio_sum / successful_compressions as f64
-    } else {
-        0.0
+        None => image_path.to_path_buf(),
     };
-
-    println!(
-        "
---- 压缩总结 ---"
-    );
-    println!("总共处理图片数量: {}", results.len());
-    println!("成功压缩文件数量: {}", successful_compressions);
-    println!("失败压缩文件数量: {}", failed_compressions);
-    if successful_compressions > 0 {
-        println!(
-            "成功文件的平均压缩率: {:.2}%",
-            avg_compression_ratio * 100.0
-        );
-    } else {
-        println!("没有文件被成功压缩。");
+    match options.target_extension() {
+        Some(extension) if options.responsive.is_none() => path.with_extension(extension),
+        _ => path,
     }
-    println!("--------------------");
-
-    Ok((
-        successful_compressions,
-        failed_compressions,
-        avg_compression_ratio,
-    ))
 }
 
-/// 压缩单个图片
-fn compress_image(image_path: &Path, create_new_file: bool) -> Result<f64> {
+/// 压缩单个图片，指定了目标格式时转换格式
+///
+/// # 参数
+/// * `root` - 源目录，使用输出目录时据此计算相对路径
+/// * `image_path` - 图片路径
+/// * `options` - 压缩选项
+fn compress_image(root: &Path, image_path: &Path, options: &CompressOptions) -> Result<FileOutcome> {
     let original_size = fs::metadata(image_path)?.len() as f64;
+    let output = output_path(root, image_path, options);
 
     if let Some(extension) = image_path.extension() {
         let ext = extension.to_string_lossy().to_lowercase();
 
-        match ext.as_str() {
-            "png" => compress_png(image_path, create_new_file, original_size),
-            "jpg" | "jpeg" => compress_jpg(image_path, create_new_file, original_size),
+        match (ext.as_str(), &options.convert_to) {
+            ("png" | "jpg" | "jpeg", _) if options.responsive.is_some() => responsive::generate(image_path, &output, options),
+            ("png" | "jpg" | "jpeg", Some(target)) => convert::convert_image(image_path, &output, target, options),
+            ("png", None) => compress_png(image_path, &output, options, original_size),
+            ("jpg" | "jpeg", None) => compress_jpg(image_path, &output, options, original_size),
+            ("svg", None) => svg::compress_svg(image_path, &output, options),
+            ("gif", target) => animation::process_gif(image_path, &output, target.as_ref(), options),
             _ => Err(Error::compression(format!("不支持的图片格式: {}", ext))),
         }
     } else {
@@ -238,7 +327,9 @@ fn compress_image(image_path: &Path, create_new_file: bool) -> Result<f64> {
 }
 
 /// 压缩PNG图片
-fn compress_png(image_path: &Path, create_new_file: bool, original_size: f64) -> Result<f64> {
+///
+/// 设置了量化质量范围时先做有损调色板量化，再由 oxipng 无损优化
+fn compress_png(image_path: &Path, output_path: &Path, options: &CompressOptions, original_size: f64) -> Result<FileOutcome> {
     if original_size as u64 > STREAMING_THRESHOLD {
         println!("文件大小超过阈值，使用缓冲IO处理: {}", image_path.display());
     }
@@ -249,100 +340,120 @@ fn compress_png(image_path: &Path, create_new_file: bool, original_size: f64) ->
     use std::io::Read;
     reader.read_to_end(&mut input_data)?;
 
-    // 使用默认优化选项
-    let options = Options::default();
+    // 超出尺寸限制时先缩小，再以 PNG 编码交给 oxipng，元数据从原图写回
+    let mut resized = None;
+    let mut resized_data = Vec::new();
+    let placeholder = match resize::resize_encoded(&input_data, image::ImageFormat::Png, &options.resize)? {
+        Some((img, info)) => {
+            img.write_to(&mut std::io::Cursor::new(&mut resized_data), image::ImageFormat::Png)
+                .map_err(|e| Error::compression(format!("图片编码失败: {}", e)))?;
+            resized = Some(info);
+            placeholder::for_image(&img, options)?
+        }
+        None => placeholder::for_data(&input_data, image::ImageFormat::Png, options)?,
+    };
+    let source = if resized.is_some() { &resized_data } else { &input_data };
 
     // 优化PNG到内存
-    let output_data_in_memory = optimize_from_memory(&input_data, &options)
-        .map_err(|e| Error::compression(format!("PNG优化失败: {}", e)))?;
-    let compressed_size_in_memory = output_data_in_memory.len() as f64;
-
-    if !create_new_file {
-        // 覆写模式
-        if compressed_size_in_memory >= original_size {
-            println!(
-                "提示: 文件 {} (原始大小: {:.0} bytes) 压缩后大小为 {:.0} bytes，未变小或反而变大，跳过覆写。",
-                image_path.display(),
-                original_size,
-                compressed_size_in_memory
-            );
-            return Ok(0.0); // 返回0%压缩率，表示未进行有效压缩
-        }
-        // 体积变小，执行覆写，使用 BufWriter
-        let file = fs::File::create(image_path)?;
-        let mut writer = BufWriter::new(file);
-        use std::io::Write;
-        writer.write_all(&output_data_in_memory)?;
-        writer.flush()?;
-        let compression_ratio = 1.0 - (compressed_size_in_memory / original_size);
-        Ok(compression_ratio)
-    } else {
-        // 创建新文件模式
-        let output_path = create_output_path(image_path, "_c");
-        let file = fs::File::create(&output_path)?;
-        let mut writer = BufWriter::new(file);
-        use std::io::Write;
-        writer.write_all(&output_data_in_memory)?;
-        writer.flush()?;
-
-        // 对于新文件，我们仍然基于其在磁盘上的最终大小计算压缩率
-        let final_compressed_size_on_disk = fs::metadata(&output_path)?.len() as f64;
-        let compression_ratio = 1.0 - (final_compressed_size_on_disk / original_size);
-        Ok(compression_ratio)
-    }
+    let output_data_in_memory = png::optimize_png(source, &input_data, &options.png, &options.metadata)?;
+
+    let outcome = write_output(image_path, output_path, &input_data, &output_data_in_memory, resized, "png", options)?;
+    Ok(FileOutcome { placeholder, ..outcome })
 }
 
 /// 压缩JPG/JPEG图片
-fn compress_jpg(image_path: &Path, create_new_file: bool, original_size: f64) -> Result<f64> {
+///
+/// 使用 mozjpeg 重新编码（哈夫曼表优化，默认渐进式）；
+/// `lossless` 时只转写 DCT 系数，不损失画质
+///
+/// 超出尺寸限制的图片总是重新编码，`lossless` 对它们不生效
+fn compress_jpg(image_path: &Path, output_path: &Path, options: &CompressOptions, original_size: f64) -> Result<FileOutcome> {
     if original_size as u64 > STREAMING_THRESHOLD {
         println!("文件大小超过阈值，使用缓冲IO处理: {}", image_path.display());
     }
-    // 使用 BufReader 打开图片
+    // 使用 BufReader 读取文件
     let file = fs::File::open(image_path)?;
-    let reader = BufReader::new(file);
-    let img = image::load(reader, image::ImageFormat::Jpeg)
-        .map_err(|e| Error::compression(format!("无法打开图片: {}", e)))?;
-
-    // 尝试将图片编码到内存缓冲区
-    let mut buffer = Vec::new();
-    let mut cursor = std::io::Cursor::new(&mut buffer);
-    img.write_to(&mut cursor, image::ImageFormat::Jpeg)
-        .map_err(|e| Error::compression(format!("图片编码失败: {}", e)))?;
-    let compressed_size_in_memory = buffer.len() as f64;
-
-    if !create_new_file {
-        // 覆写模式
-        if compressed_size_in_memory >= original_size {
-            println!(
-                "提示: 文件 {} (原始大小: {:.0} bytes) 压缩后大小为 {:.0} bytes，未变小或反而变大，跳过覆写。",
-                image_path.display(),
-                original_size,
-                compressed_size_in_memory
-            );
-            return Ok(0.0); // 返回0%压缩率
+    let mut reader = BufReader::new(file);
+    let mut input_data = Vec::new();
+    use std::io::Read;
+    reader.read_to_end(&mut input_data)?;
+
+    let progressive = !options.baseline;
+    let mut resized = None;
+    // 重新编码时像素已按 EXIF 方向摆正，方向标签需要重置
+    let reencode = |img: &image::DynamicImage| {
+        let markers = metadata::jpeg_markers(&input_data, &options.metadata, true);
+        quality::encode_lossy(img, DEFAULT_JPEG_QUALITY, options, |q| {
+            jpeg::encode_jpeg(img, q, progressive, options.chroma, &markers)
+        })
+    };
+    let (encoded, placeholder) = match resize::resize_encoded(&input_data, image::ImageFormat::Jpeg, &options.resize)? {
+        Some((img, info)) => {
+            resized = Some(info);
+            (reencode(&img)?, placeholder::for_image(&img, options)?)
+        }
+        None if options.lossless => {
+            let markers = metadata::jpeg_markers(&input_data, &options.metadata, false);
+            let encoded = quality::Encoded::lossless(jpeg::optimize_lossless(&input_data, progressive, &markers)?);
+            (encoded, placeholder::for_data(&input_data, image::ImageFormat::Jpeg, options)?)
+        }
+        None => {
+            let img = metadata::decode_oriented(&input_data, image::ImageFormat::Jpeg)?;
+            (reencode(&img)?, placeholder::for_image(&img, options)?)
+        }
+    };
+    let outcome = write_output(image_path, output_path, &input_data, &encoded.data, resized, "jpeg", options)?;
+    Ok(FileOutcome { quality: encoded.quality, ssim: encoded.ssim, placeholder, ..outcome })
+}
+
+/// 写出压缩结果
+///
+/// 体积没有变小时：覆写模式保留原图，输出目录模式原样复制原图以保持目录结构完整，
+/// `_c` 新文件和调整过尺寸的图片总是写入
+fn write_output(
+    image_path: &Path,
+    output_path: &Path,
+    original: &[u8],
+    data: &[u8],
+    resized: Option<Resized>,
+    format: &'static str,
+    options: &CompressOptions,
+) -> Result<FileOutcome> {
+    let always_write = resized.is_some() || (options.create_new_file && options.out_dir.is_none());
+    if data.len() < original.len() || always_write {
+        if output_path == image_path {
+            // 原子地覆写，中途失败时原图不受影响
+            write_atomic(image_path, data, &options.write_options())?;
+        } else {
+            write_new_file(output_path, data)?;
         }
-        // 体积变小，执行覆写，使用 BufWriter
-        let file = fs::File::create(image_path)?;
-        let mut writer = BufWriter::new(file);
-        use std::io::Write;
-        writer.write_all(&buffer)?;
-        writer.flush()?;
-        let compression_ratio = 1.0 - (compressed_size_in_memory / original_size);
-        Ok(compression_ratio)
+        return Ok(FileOutcome::written(original.len() as u64, data, format, resized));
+    }
+
+    if options.out_dir.is_some() {
+        println!(
+            "提示: 文件 {} 压缩后未变小，原样复制到 {}",
+            image_path.display(),
+            output_path.display()
+        );
+        write_new_file(output_path, original)?;
     } else {
-        // 创建新文件模式
-        let output_path = create_output_path(image_path, "_c");
-        let file = fs::File::create(&output_path)?;
-        let mut writer = BufWriter::new(file);
-        use std::io::Write;
-        img.write_to(&mut writer, image::ImageFormat::Jpeg)
-            .map_err(|e| Error::compression(format!("图片保存失败: {}", e)))?;
-        writer.flush()?;
-
-        let final_compressed_size_on_disk = fs::metadata(&output_path)?.len() as f64;
-        let compression_ratio = 1.0 - (final_compressed_size_on_disk / original_size);
-        Ok(compression_ratio)
+        println!(
+            "提示: 文件 {} (原始大小: {} bytes) 压缩后大小为 {} bytes，未变小或反而变大，跳过覆写。",
+            image_path.display(),
+            original.len(),
+            data.len()
+        );
+    }
+    Ok(FileOutcome::kept(original, format))
+}
+
+/// 写入新文件，必要时创建输出目录中的上级目录
+fn write_new_file(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
     }
+    write_atomic(path, data, &AtomicWriteOptions::default())
 }
 
 /// 创建输出路径（添加后缀）
@@ -373,13 +484,17 @@ mod tests {
         assert_eq!(ImageType::from_str("JPG").unwrap(), ImageType::Jpg);
         assert_eq!(ImageType::from_str("jpeg").unwrap(), ImageType::Jpeg);
         assert_eq!(ImageType::from_str("JPEG").unwrap(), ImageType::Jpeg);
+        assert_eq!(ImageType::from_str("gif").unwrap(), ImageType::Gif);
+        assert_eq!(ImageType::from_str("SVG").unwrap(), ImageType::Svg);
         assert_eq!(ImageType::from_str("all").unwrap(), ImageType::All);
         assert_eq!(ImageType::from_str("ALL").unwrap(), ImageType::All);
+        assert_eq!(ImageType::from_str("WebP").unwrap(), ImageType::Webp);
+        assert!(ImageType::from_str("avif").unwrap().is_convert_target());
     }
 
     #[test]
     fn test_image_type_from_str_invalid() {
-        assert!(ImageType::from_str("gif").is_err());
+        assert!(ImageType::from_str("tiff").is_err());
         assert!(ImageType::from_str("bmp").is_err());
         assert!(ImageType::from_str("").is_err());
         assert!(ImageType::from_str("png ").is_err());
@@ -401,7 +516,7 @@ mod tests {
         let file_path = temp_dir.path().join("test.bmp");
         File::create(&file_path).unwrap();
 
-        let result = compress_image(&file_path, false);
+        let result = compress_image(temp_dir.path(), &file_path, &CompressOptions::default());
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.to_string().contains("不支持的图片格式"));
@@ -410,7 +525,7 @@ mod tests {
     #[test]
     fn test_compress_image_file_not_found() {
         let non_existent_path = std::path::Path::new("/non/existent/file.png");
-        let result = compress_image(non_existent_path, false);
+        let result = compress_image(Path::new("/non/existent"), non_existent_path, &CompressOptions::default());
         assert!(result.is_err());
         // 应该是Io错误，但我们的错误类型会包装它
         let err = result.unwrap_err();
@@ -430,15 +545,96 @@ mod tests {
         img.save(&file_path).unwrap();
 
         // 使用create_new_file=true进行压缩，这样不会修改原文件
-        let result = compress_image(&file_path, true);
+        let options = CompressOptions { create_new_file: true, ..Default::default() };
+        let result = compress_image(temp_dir.path(), &file_path, &options);
         // 压缩应该成功，但可能没有压缩率（因为图像很小）
         assert!(result.is_ok());
-        let compression_ratio = result.unwrap();
+        let compression_ratio = result.unwrap().ratio;
         // 压缩率应该在0.0到1.0之间（可能是0.0，因为图像太小无法压缩）
-        assert!(compression_ratio >= 0.0 && compression_ratio <= 1.0);
+        assert!((0.0..=1.0).contains(&compression_ratio));
 
         // 检查新文件是否被创建（带有_c后缀）
         let new_file_path = temp_dir.path().join("test_c.png");
         assert!(new_file_path.exists());
     }
+
+    #[test]
+    fn test_compress_jpg_resizes_to_max_width() {
+        use image::{ImageBuffer, Rgb};
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("photo.jpg");
+        let img: ImageBuffer<Rgb<u8>, _> =
+            ImageBuffer::from_fn(400, 300, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8]));
+        img.save(&file_path).unwrap();
+
+        let options = CompressOptions {
+            resize: ResizeOptions { max_width: Some(200), ..Default::default() },
+            ..Default::default()
+        };
+        let outcome = compress_image(temp_dir.path(), &file_path, &options).unwrap();
+        assert_eq!(outcome.resized, Some(Resized { from: (400, 300), to: (200, 150) }));
+        assert_eq!(image::image_dimensions(&file_path).unwrap(), (200, 150));
+    }
+
+    #[test]
+    fn test_compress_images_skips_cached_files() {
+        use image::{ImageBuffer, Rgb};
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let img: ImageBuffer<Rgb<u8>, _> =
+            ImageBuffer::from_fn(32, 32, |x, y| Rgb([(x * 8) as u8, (y * 8) as u8, 128]));
+        img.save(temp_dir.path().join("a.png")).unwrap();
+
+        let options = CompressOptions { cache: CacheLocation::Project, ..Default::default() };
+        assert_eq!(compress_images(temp_dir.path(), ImageType::Png, &options).unwrap().successful(), 1);
+        assert!(temp_dir.path().join(".maya").join(CACHE_FILE_NAME).exists());
+        // 第二次运行命中缓存
+        let cached = compress_images(temp_dir.path(), ImageType::Png, &options).unwrap();
+        assert_eq!((cached.successful(), cached.skipped()), (0, 1));
+
+        let forced = CompressOptions { force: true, jobs: 2, ..options.clone() };
+        assert_eq!(compress_images(temp_dir.path(), ImageType::Png, &forced).unwrap().successful(), 1);
+
+        // 生成的 _c 文件被删除后重新生成
+        let new_file = CompressOptions { create_new_file: true, ..options };
+        assert_eq!(compress_images(temp_dir.path(), ImageType::Png, &new_file).unwrap().successful(), 1);
+        assert_eq!(compress_images(temp_dir.path(), ImageType::Png, &new_file).unwrap().skipped(), 1);
+        fs::remove_file(temp_dir.path().join("a_c.png")).unwrap();
+        assert_eq!(compress_images(temp_dir.path(), ImageType::Png, &new_file).unwrap().successful(), 1);
+        assert!(temp_dir.path().join("a_c.png").exists());
+    }
+
+    #[test]
+    fn test_out_dir_mirrors_tree_and_skips_generated_copies() {
+        use image::{ImageBuffer, Rgb};
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("src");
+        fs::create_dir_all(source.join("icons")).unwrap();
+        let img: ImageBuffer<Rgb<u8>, _> =
+            ImageBuffer::from_fn(32, 32, |x, y| Rgb([(x * 8) as u8, (y * 8) as u8, 128]));
+        img.save(source.join("icons").join("a.png")).unwrap();
+        img.save(source.join("icons").join("a_c.png")).unwrap();
+        img.save(source.join("logo_c.png")).unwrap();
+
+        let out_dir = source.join("dist");
+        let options = CompressOptions { out_dir: Some(out_dir.clone()), ..Default::default() };
+        let original = fs::read(source.join("icons").join("a.png")).unwrap();
+        let summary = compress_images(&source, ImageType::Png, &options).unwrap();
+        assert_eq!((summary.successful(), summary.failed()), (2, 0));
+
+        // 原图不变，输出目录按源目录结构生成；没有原图对应的 logo_c.png 不是生成文件
+        assert_eq!(fs::read(source.join("icons").join("a.png")).unwrap(), original);
+        assert!(out_dir.join("icons").join("a.png").is_file());
+        assert!(out_dir.join("logo_c.png").is_file());
+        assert!(!out_dir.join("icons").join("a_c.png").exists());
+        assert!(!out_dir.join("dist").exists());
+
+        let same = CompressOptions { out_dir: Some(source.clone()), ..Default::default() };
+        assert!(compress_images(&source, ImageType::Png, &same).is_err());
+    }
 }
@@ -0,0 +1,633 @@
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use maya_common::error::{Error, Result};
+use serde::Serialize;
+use std::io::Cursor;
+use std::str::FromStr;
+
+/// JPEG APP0 标记码
+const APP0: u8 = 0xE0;
+/// JPEG APP1 标记码（EXIF、XMP）
+const APP1: u8 = 0xE1;
+/// JPEG APP2 标记码（ICC）
+const APP2: u8 = 0xE2;
+/// JPEG APP14 标记码（Adobe）
+const APP14: u8 = 0xEE;
+/// JPEG COM 注释标记码
+const COM: u8 = 0xFE;
+/// JPEG 扫描开始标记码，之后是压缩数据
+const SOS: u8 = 0xDA;
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// PNG 色彩相关的辅助块
+pub(crate) const PNG_COLOR_CHUNKS: [[u8; 4]; 4] = [*b"iCCP", *b"sRGB", *b"gAMA", *b"cHRM"];
+/// PNG 文本块，版权信息通常写在 tEXt/iTXt 的 `Copyright` 键中
+const PNG_TEXT_CHUNKS: [[u8; 4]; 3] = [*b"tEXt", *b"iTXt", *b"zTXt"];
+/// PNG 中按元数据选项处理的辅助块，其余辅助块（透明色、像素密度等）交给 oxipng
+pub(crate) const PNG_METADATA_CHUNKS: [[u8; 4]; 9] =
+    [*b"iCCP", *b"sRGB", *b"gAMA", *b"cHRM", *b"tEXt", *b"iTXt", *b"zTXt", *b"eXIf", *b"tIME"];
+
+/// EXIF 标签
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_ARTIST: u16 = 0x013B;
+const TAG_COPYRIGHT: u16 = 0x8298;
+const TAG_GPS_IFD: u16 = 0x8825;
+/// TIFF ASCII 类型
+const TYPE_ASCII: u16 = 2;
+/// TIFF SHORT 类型
+const TYPE_SHORT: u16 = 3;
+
+/// 元数据处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum MetadataMode {
+    /// 移除全部元数据（JPEG 无损优化和 PNG 保留方向标签）
+    Strip,
+    /// 保留元数据（默认移除 GPS 位置）
+    #[default]
+    Keep,
+    /// 只保留 ICC 色彩配置
+    KeepIcc,
+    /// 保留 ICC 色彩配置和 EXIF 中的版权、作者信息
+    KeepCopyright,
+}
+
+impl FromStr for MetadataMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "strip" => Ok(MetadataMode::Strip),
+            "keep" => Ok(MetadataMode::Keep),
+            "keep-icc" => Ok(MetadataMode::KeepIcc),
+            "keep-copyright" => Ok(MetadataMode::KeepCopyright),
+            _ => Err(format!(
+                "不支持的元数据处理方式: {}（可选 strip、keep、keep-icc、keep-copyright）",
+                s
+            )),
+        }
+    }
+}
+
+/// 元数据选项
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MetadataOptions {
+    pub mode: MetadataMode,
+    /// 保留 GPS 位置信息，默认出于隐私考虑移除
+    pub keep_gps: bool,
+}
+
+/// 需要写入输出文件的 JPEG 标记段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct JpegMarker {
+    pub code: u8,
+    pub data: Vec<u8>,
+}
+
+/// 解码图片并按 EXIF 方向旋转/翻转像素
+pub(crate) fn decode_oriented(data: &[u8], format: ImageFormat) -> Result<DynamicImage> {
+    let open_error = |e: image::ImageError| Error::compression(format!("无法打开图片: {}", e));
+    let mut decoder = ImageReader::with_format(Cursor::new(data), format)
+        .into_decoder()
+        .map_err(open_error)?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut img = DynamicImage::from_decoder(decoder).map_err(open_error)?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+/// 读取图片按 EXIF 方向摆正后的尺寸，不解码像素
+pub(crate) fn oriented_dimensions(data: &[u8], format: ImageFormat) -> Result<(u32, u32)> {
+    let open_error = |e: image::ImageError| Error::compression(format!("无法打开图片: {}", e));
+    let mut decoder = ImageReader::with_format(Cursor::new(data), format)
+        .into_decoder()
+        .map_err(open_error)?;
+    let (width, height) = decoder.dimensions();
+    match decoder.orientation().unwrap_or(Orientation::NoTransforms) {
+        Orientation::Rotate90
+        | Orientation::Rotate270
+        | Orientation::Rotate90FlipH
+        | Orientation::Rotate270FlipH => Ok((height, width)),
+        _ => Ok((width, height)),
+    }
+}
+
+/// 按元数据选项挑选原 JPEG 中要保留的 APPn/COM 标记段
+///
+/// JFIF 和 Adobe 标记由编码器自动写入，总是跳过
+///
+/// # 参数
+/// * `data` - 原 JPEG 数据
+/// * `options` - 元数据选项
+/// * `reoriented` - 像素已按 EXIF 方向摆正，需要把方向标签重置为 1；
+///   为 `false` 时像素保持原样，即使丢弃 EXIF 也要保留原来的方向标签，否则竖拍的照片会横过来
+pub(crate) fn jpeg_markers(data: &[u8], options: &MetadataOptions, reoriented: bool) -> Vec<JpegMarker> {
+    let strip_gps = !options.keep_gps;
+    let mut kept = Vec::new();
+    for marker in read_jpeg_markers(data) {
+        let is_icc = marker.code == APP2 && marker.data.starts_with(ICC_HEADER);
+        let is_exif = marker.code == APP1 && marker.data.starts_with(EXIF_HEADER);
+        let is_xmp = marker.code == APP1 && marker.data.starts_with(XMP_HEADER);
+        let is_jfif = marker.code == APP0 && marker.data.starts_with(b"JFIF\0");
+        let is_adobe = marker.code == APP14 && marker.data.starts_with(b"Adobe");
+        if is_jfif || is_adobe {
+            continue;
+        }
+
+        let orientation = if is_exif && !reoriented {
+            exif_orientation(&marker.data[EXIF_HEADER.len()..]).filter(|&o| o != 1)
+        } else {
+            None
+        };
+
+        let data = match options.mode {
+            MetadataMode::Strip | MetadataMode::KeepIcc if is_exif => orientation.map(|o| build_exif(Some(o), &[])),
+            MetadataMode::Strip => None,
+            MetadataMode::KeepIcc => is_icc.then_some(marker.data),
+            MetadataMode::KeepCopyright if is_icc => Some(marker.data),
+            MetadataMode::KeepCopyright if is_exif => copyright_exif(&marker.data[EXIF_HEADER.len()..], orientation),
+            MetadataMode::KeepCopyright => None,
+            MetadataMode::Keep if is_exif => sanitize_exif(&marker.data, strip_gps, reoriented),
+            // XMP 中也可能带有位置信息
+            MetadataMode::Keep if is_xmp && strip_gps && contains(&marker.data, b"exif:GPS") => None,
+            MetadataMode::Keep => Some(marker.data),
+        };
+        if let Some(data) = data {
+            kept.push(JpegMarker { code: marker.code, data });
+        }
+    }
+    kept
+}
+
+/// 需要写入输出文件的 PNG 辅助块
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PngChunk {
+    pub name: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+/// 按元数据选项挑选原 PNG 中要保留的元数据块，规则和 [`jpeg_markers`] 相同
+///
+/// PNG 解码时不会按 eXIf 方向旋转像素，方向标签总是原样保留；
+/// eXIf 块只包含 TIFF 数据，没有 JPEG APP1 段的 `Exif\0\0` 头
+pub(crate) fn png_chunks(data: &[u8], options: &MetadataOptions) -> Vec<PngChunk> {
+    let strip_gps = !options.keep_gps;
+    let without_header = |segment: Vec<u8>| segment[EXIF_HEADER.len()..].to_vec();
+    let mut kept = Vec::new();
+    for chunk in read_png_chunks(data) {
+        if !PNG_METADATA_CHUNKS.contains(&chunk.name) {
+            continue;
+        }
+        let is_color = PNG_COLOR_CHUNKS.contains(&chunk.name);
+        let is_text = PNG_TEXT_CHUNKS.contains(&chunk.name);
+        let is_exif = &chunk.name == b"eXIf";
+
+        let orientation = if is_exif { exif_orientation(&chunk.data).filter(|&o| o != 1) } else { None };
+
+        let data = match options.mode {
+            MetadataMode::Strip | MetadataMode::KeepIcc if is_exif => {
+                orientation.map(|o| without_header(build_exif(Some(o), &[])))
+            }
+            MetadataMode::Strip => None,
+            MetadataMode::KeepIcc => is_color.then_some(chunk.data),
+            MetadataMode::KeepCopyright if is_exif => copyright_exif(&chunk.data, orientation).map(without_header),
+            MetadataMode::KeepCopyright => (is_color || is_text).then_some(chunk.data),
+            MetadataMode::Keep if is_exif => {
+                sanitize_exif(&[EXIF_HEADER, &chunk.data].concat(), strip_gps, false).map(without_header)
+            }
+            // iTXt 中的 XMP 也可能带有位置信息
+            MetadataMode::Keep if is_text && strip_gps && contains(&chunk.data, b"exif:GPS") => None,
+            MetadataMode::Keep => Some(chunk.data),
+        };
+        if let Some(data) = data {
+            kept.push(PngChunk { name: chunk.name, data });
+        }
+    }
+    kept
+}
+
+/// 读取 PNG 中 IDAT 之前的辅助块，数据损坏时返回已读取的部分
+pub(crate) fn read_png_chunks(data: &[u8]) -> Vec<PngChunk> {
+    let mut chunks = Vec::new();
+    if !data.starts_with(PNG_SIGNATURE) {
+        return chunks;
+    }
+    let mut pos = PNG_SIGNATURE.len();
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let name: [u8; 4] = [data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]];
+        let Some(body) = data.get(pos + 8..(pos + 8).saturating_add(length)) else {
+            break;
+        };
+        if &name == b"IDAT" || &name == b"IEND" {
+            break;
+        }
+        // 首字母小写的是辅助块
+        if name[0].is_ascii_lowercase() {
+            chunks.push(PngChunk { name, data: body.to_vec() });
+        }
+        pos += 12 + length;
+    }
+    chunks
+}
+
+/// 读取 SOS 之前的 APPn/COM 标记段
+fn read_jpeg_markers(data: &[u8]) -> Vec<JpegMarker> {
+    let mut markers = Vec::new();
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return markers;
+    }
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            break;
+        }
+        let code = data[pos + 1];
+        // 填充字节
+        if code == 0xFF {
+            pos += 1;
+            continue;
+        }
+        if code == SOS {
+            break;
+        }
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        if length < 2 || pos + 2 + length > data.len() {
+            break;
+        }
+        if (APP0..=APP0 + 15).contains(&code) || code == COM {
+            markers.push(JpegMarker { code, data: data[pos + 4..pos + 2 + length].to_vec() });
+        }
+        pos += 2 + length;
+    }
+    markers
+}
+
+/// 处理 APP1 EXIF 段：移除 GPS，必要时把方向重置为 1
+///
+/// 直接在原数据上修改，其余标签保持不变；数据损坏且需要移除 GPS 时丢弃整个段
+fn sanitize_exif(segment: &[u8], strip_gps: bool, reset_orientation: bool) -> Option<Vec<u8>> {
+    let mut segment = segment.to_vec();
+    let tiff = &mut segment[EXIF_HEADER.len()..];
+    let Some(ifd0) = Tiff::new(tiff).and_then(|t| t.ifd_entries(t.first_ifd()?)) else {
+        return (!strip_gps).then_some(segment);
+    };
+    let little_endian = tiff.starts_with(b"II");
+
+    for entry in ifd0 {
+        match entry.tag {
+            TAG_ORIENTATION if reset_orientation => {
+                tiff.get_mut(entry.value_pos..entry.value_pos + 2)?.copy_from_slice(&encode_u16(1, little_endian));
+            }
+            TAG_GPS_IFD if strip_gps => {
+                let gps_offset = Tiff { data: tiff, little_endian }.read_u32(entry.value_pos)? as usize;
+                clear_ifd(tiff, little_endian, gps_offset)?;
+            }
+            _ => {}
+        }
+    }
+    Some(segment)
+}
+
+/// 清空一个 IFD：抹掉所有条目及其数据，条目数改为 0
+fn clear_ifd(tiff: &mut [u8], little_endian: bool, offset: usize) -> Option<()> {
+    let entries = Tiff { data: tiff, little_endian }.ifd_entries(offset)?;
+    for entry in &entries {
+        if let Some((start, len)) = entry.external_data {
+            tiff.get_mut(start..start + len)?.fill(0);
+        }
+    }
+    tiff.get_mut(offset + 2..offset + 2 + entries.len() * 12)?.fill(0);
+    tiff.get_mut(offset..offset + 2)?.copy_from_slice(&[0, 0]);
+    // 空 IFD 之后紧跟下一个 IFD 的偏移，写 0 表示没有
+    tiff.get_mut(offset + 2..offset + 6)?.copy_from_slice(&[0, 0, 0, 0]);
+    Some(())
+}
+
+/// 只保留版权和作者信息，生成新的 APP1 EXIF 段；`orientation` 不为 `None` 时一并写入方向
+fn copyright_exif(tiff: &[u8], orientation: Option<u16>) -> Option<Vec<u8>> {
+    let reader = Tiff::new(tiff)?;
+    let entries = reader.ifd_entries(reader.first_ifd()?)?;
+    let fields: Vec<(u16, &[u8])> = entries
+        .iter()
+        .filter(|e| matches!(e.tag, TAG_ARTIST | TAG_COPYRIGHT) && e.field_type == TYPE_ASCII)
+        .filter_map(|e| {
+            let (start, len) = e.external_data.unwrap_or((e.value_pos, e.count as usize));
+            Some((e.tag, tiff.get(start..start + len)?))
+        })
+        .collect();
+    if fields.is_empty() && orientation.is_none() {
+        return None;
+    }
+    Some(build_exif(orientation, &fields))
+}
+
+/// 读取 IFD0 中的方向标签
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+    let reader = Tiff::new(tiff)?;
+    let entries = reader.ifd_entries(reader.first_ifd()?)?;
+    let entry = entries.iter().find(|e| e.tag == TAG_ORIENTATION && e.field_type == TYPE_SHORT)?;
+    reader.read_u16(entry.value_pos)
+}
+
+/// 生成只含方向和 ASCII 字段的 APP1 EXIF 段，`fields` 需按标签升序排列
+fn build_exif(orientation: Option<u16>, fields: &[(u16, &[u8])]) -> Vec<u8> {
+    let count = fields.len() + usize::from(orientation.is_some());
+
+    // 小端 TIFF：头部 8 字节 + IFD0，字符串数据放在 IFD0 之后
+    let mut out = EXIF_HEADER.to_vec();
+    out.extend_from_slice(b"II*\0");
+    out.extend_from_slice(&8u32.to_le_bytes());
+    out.extend_from_slice(&(count as u16).to_le_bytes());
+    // 方向标签 0x0112 小于作者和版权，排在最前
+    if let Some(orientation) = orientation {
+        out.extend_from_slice(&TAG_ORIENTATION.to_le_bytes());
+        out.extend_from_slice(&TYPE_SHORT.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&orientation.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+    }
+    let mut data_offset = 8 + 2 + count * 12 + 4;
+    let mut data = Vec::new();
+    for (tag, value) in fields {
+        out.extend_from_slice(&tag.to_le_bytes());
+        out.extend_from_slice(&TYPE_ASCII.to_le_bytes());
+        out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        if value.len() <= 4 {
+            let mut inline = [0u8; 4];
+            inline[..value.len()].copy_from_slice(value);
+            out.extend_from_slice(&inline);
+        } else {
+            out.extend_from_slice(&(data_offset as u32).to_le_bytes());
+            data.extend_from_slice(value);
+            data_offset += value.len();
+        }
+    }
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&data);
+    out
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+fn encode_u16(value: u16, little_endian: bool) -> [u8; 2] {
+    if little_endian {
+        value.to_le_bytes()
+    } else {
+        value.to_be_bytes()
+    }
+}
+
+/// IFD 条目
+struct IfdEntry {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    /// 值（或值偏移）在 TIFF 数据中的位置
+    value_pos: usize,
+    /// 超过 4 字节的值存放在外部，记录 (起始位置, 长度)
+    external_data: Option<(usize, usize)>,
+}
+
+/// 只读的 TIFF 结构解析
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(..4)? {
+            b"II*\0" => true,
+            b"MM\0*" => false,
+            _ => return None,
+        };
+        Some(Self { data, little_endian })
+    }
+
+    fn first_ifd(&self) -> Option<usize> {
+        self.read_u32(4).map(|offset| offset as usize)
+    }
+
+    fn read_u16(&self, pos: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn read_u32(&self, pos: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    fn ifd_entries(&self, offset: usize) -> Option<Vec<IfdEntry>> {
+        let count = self.read_u16(offset)? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for i in 0..count {
+            let pos = offset + 2 + i * 12;
+            let field_type = self.read_u16(pos + 2)?;
+            let count = self.read_u32(pos + 4)?;
+            let size = type_size(field_type).saturating_mul(count as usize);
+            let external_data = if size > 4 {
+                let start = self.read_u32(pos + 8)? as usize;
+                // 越界的数据不处理，避免损坏的 EXIF 导致 panic
+                (start.checked_add(size)? <= self.data.len()).then_some((start, size))
+            } else {
+                None
+            };
+            entries.push(IfdEntry { tag: self.read_u16(pos)?, field_type, count, value_pos: pos + 8, external_data });
+        }
+        Some(entries)
+    }
+}
+
+/// TIFF 字段类型对应的字节数
+fn type_size(field_type: u16) -> usize {
+    match field_type {
+        1 | 2 | 6 | 7 => 1,
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造带方向、作者和 GPS 信息的小端 EXIF 段
+    fn sample_exif(orientation: u16) -> Vec<u8> {
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend_from_slice(&8u32.to_le_bytes());
+        // IFD0: 方向、作者、GPS 指针
+        tiff.extend_from_slice(&3u16.to_le_bytes());
+        tiff.extend_from_slice(&TAG_ORIENTATION.to_le_bytes());
+        tiff.extend_from_slice(&3u16.to_le_bytes());
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&[orientation as u8, 0, 0, 0]);
+        tiff.extend_from_slice(&TAG_ARTIST.to_le_bytes());
+        tiff.extend_from_slice(&TYPE_ASCII.to_le_bytes());
+        tiff.extend_from_slice(&6u32.to_le_bytes());
+        tiff.extend_from_slice(&68u32.to_le_bytes());
+        tiff.extend_from_slice(&TAG_GPS_IFD.to_le_bytes());
+        tiff.extend_from_slice(&4u16.to_le_bytes());
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&50u32.to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        // GPS IFD（偏移 50）：纬度 1 个 RATIONAL，数据在偏移 74
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&5u16.to_le_bytes());
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&74u32.to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(tiff.len(), 68);
+        tiff.extend_from_slice(b"Alice\0");
+        tiff.extend_from_slice(&[39, 0, 0, 0, 1, 0, 0, 0]);
+
+        let mut segment = EXIF_HEADER.to_vec();
+        segment.extend_from_slice(&tiff);
+        segment
+    }
+
+    #[test]
+    fn test_sanitize_exif_strips_gps_and_resets_orientation() {
+        let exif = sample_exif(6);
+        let cleaned = sanitize_exif(&exif, true, true).unwrap();
+        let tiff = &cleaned[EXIF_HEADER.len()..];
+        let reader = Tiff::new(tiff).unwrap();
+        let ifd0 = reader.ifd_entries(reader.first_ifd().unwrap()).unwrap();
+        assert_eq!(reader.read_u16(ifd0[0].value_pos), Some(1));
+        // GPS IFD 条目数为 0，纬度数据被抹掉
+        assert_eq!(&tiff[50..52], &[0, 0]);
+        assert_eq!(&tiff[74..82], &[0; 8]);
+        assert!(contains(tiff, b"Alice"));
+
+        let kept = sanitize_exif(&exif, false, false).unwrap();
+        assert_eq!(kept, exif);
+    }
+
+    #[test]
+    fn test_copyright_exif_keeps_artist_only() {
+        let exif = sample_exif(6);
+        let reduced = copyright_exif(&exif[EXIF_HEADER.len()..], None).unwrap();
+        let tiff = Tiff::new(&reduced[EXIF_HEADER.len()..]).unwrap();
+        let entries = tiff.ifd_entries(tiff.first_ifd().unwrap()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].tag, TAG_ARTIST);
+        assert!(contains(&reduced, b"Alice\0"));
+
+        let oriented = copyright_exif(&exif[EXIF_HEADER.len()..], Some(6)).unwrap();
+        assert_eq!(exif_orientation(&oriented[EXIF_HEADER.len()..]), Some(6));
+        assert!(contains(&oriented, b"Alice\0"));
+    }
+
+    #[test]
+    fn test_jpeg_markers_by_mode() {
+        let mut jpeg = vec![0xFF, 0xD8];
+        let mut push = |code: u8, data: &[u8]| {
+            jpeg.extend_from_slice(&[0xFF, code]);
+            jpeg.extend_from_slice(&((data.len() + 2) as u16).to_be_bytes());
+            jpeg.extend_from_slice(data);
+        };
+        push(APP0, b"JFIF\0\x01\x01");
+        push(APP1, &sample_exif(1));
+        push(APP2, b"ICC_PROFILE\0\x01\x01icc");
+        push(COM, b"comment");
+        jpeg.extend_from_slice(&[0xFF, SOS, 0, 2]);
+
+        let codes = |mode| {
+            let options = MetadataOptions { mode, keep_gps: false };
+            jpeg_markers(&jpeg, &options, false).iter().map(|m| m.code).collect::<Vec<_>>()
+        };
+        assert_eq!(codes(MetadataMode::Strip), Vec::<u8>::new());
+        assert_eq!(codes(MetadataMode::Keep), vec![APP1, APP2, COM]);
+        assert_eq!(codes(MetadataMode::KeepIcc), vec![APP2]);
+        assert_eq!(codes(MetadataMode::KeepCopyright), vec![APP1, APP2]);
+        assert!("keep-icc".parse::<MetadataMode>().is_ok());
+    }
+
+    #[test]
+    fn test_png_chunks_by_mode() {
+        let tiff = sample_exif(6)[EXIF_HEADER.len()..].to_vec();
+        let png = crate::png::tests::png_with_chunks(&[
+            (*b"iCCP", b"icc".to_vec()),
+            (*b"tEXt", b"Copyright\0Alice".to_vec()),
+            (*b"eXIf", tiff),
+            (*b"pHYs", vec![0; 9]),
+        ]);
+        let chunks = |mode| png_chunks(&png, &MetadataOptions { mode, keep_gps: false });
+        let names = |chunks: Vec<PngChunk>| chunks.iter().map(|c| c.name).collect::<Vec<_>>();
+
+        // 默认只移除 GPS，方向保留
+        let kept = chunks(MetadataMode::Keep);
+        assert_eq!(names(kept.clone()), vec![*b"iCCP", *b"tEXt", *b"eXIf"]);
+        assert_eq!(exif_orientation(&kept[2].data), Some(6));
+        assert!(contains(&kept[2].data, b"Alice"));
+        assert!(!contains(&kept[2].data, &[39, 0, 0, 0, 1, 0, 0, 0]));
+
+        // 去掉元数据时只保留方向
+        let stripped = chunks(MetadataMode::Strip);
+        assert_eq!(names(stripped.clone()), vec![*b"eXIf"]);
+        assert_eq!(exif_orientation(&stripped[0].data), Some(6));
+        assert!(!contains(&stripped[0].data, b"Alice"));
+        assert_eq!(names(chunks(MetadataMode::KeepIcc)), vec![*b"iCCP", *b"eXIf"]);
+        assert_eq!(names(chunks(MetadataMode::KeepCopyright)), vec![*b"iCCP", *b"tEXt", *b"eXIf"]);
+    }
+
+    #[test]
+    fn test_compress_jpg_applies_orientation_and_strips_gps() {
+        use crate::{compress_image, CompressOptions};
+        use tempfile::tempdir;
+
+        // 横向存储、方向为 6（顺时针旋转 90°）的照片
+        let img = DynamicImage::new_rgb8(80, 40);
+        let exif = JpegMarker { code: APP1, data: sample_exif(6) };
+        let data = crate::jpeg::encode_jpeg(&img, 95, false, Default::default(), &[exif]).unwrap();
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("portrait.jpg");
+        std::fs::write(&path, &data).unwrap();
+
+        let options = CompressOptions { create_new_file: true, ..Default::default() };
+        compress_image(temp_dir.path(), &path, &options).unwrap();
+        let output = std::fs::read(temp_dir.path().join("portrait_c.jpg")).unwrap();
+        assert_eq!(oriented_dimensions(&output, ImageFormat::Jpeg).unwrap(), (40, 80));
+        assert_eq!(image::load_from_memory(&output).unwrap().width(), 40);
+
+        let markers = read_jpeg_markers(&output);
+        let exif = markers.iter().find(|m| m.data.starts_with(EXIF_HEADER)).unwrap();
+        assert!(contains(&exif.data, b"Alice"));
+        assert!(!contains(&exif.data, &[39, 0, 0, 0, 1, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_lossless_strip_keeps_orientation() {
+        use crate::{compress_image, CompressOptions};
+        use tempfile::tempdir;
+
+        // 无损优化不旋转像素，去掉全部元数据时仍要保留方向 6
+        let img = DynamicImage::new_rgb8(80, 40);
+        let exif = JpegMarker { code: APP1, data: sample_exif(6) };
+        let data = crate::jpeg::encode_jpeg(&img, 95, false, Default::default(), &[exif]).unwrap();
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("portrait.jpg");
+        std::fs::write(&path, &data).unwrap();
+
+        let options = CompressOptions {
+            create_new_file: true,
+            lossless: true,
+            metadata: MetadataOptions { mode: MetadataMode::Strip, keep_gps: false },
+            ..Default::default()
+        };
+        compress_image(temp_dir.path(), &path, &options).unwrap();
+        let output = std::fs::read(temp_dir.path().join("portrait_c.jpg")).unwrap();
+        assert_eq!(oriented_dimensions(&output, ImageFormat::Jpeg).unwrap(), (40, 80));
+        assert!(!contains(&output, b"Alice"));
+        assert!(!contains(&output, &[39, 0, 0, 0, 1, 0, 0, 0]));
+    }
+}
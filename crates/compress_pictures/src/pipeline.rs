@@ -0,0 +1,357 @@
+use crate::{cache, compress_image, placeholder, responsive, find_images, output_path, CompressOptions, FileOutcome, ImageType, Resized};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use maya_common::error::{Error, Result};
+use maya_common::hash_utils::sha256_file;
+use maya_common::size_utils::{format_delta, format_size};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 单个图片的最终状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileStatus {
+    /// 处理完成
+    Compressed(FileOutcome),
+    /// 内容和参数与上次优化后一致，已跳过
+    Skipped,
+    /// 处理失败，附带错误信息
+    Failed(String),
+}
+
+/// 单个图片的处理结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileResult {
+    pub path: PathBuf,
+    pub status: FileStatus,
+}
+
+/// 一次压缩的全部结果，按文件遍历顺序排列
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompressSummary {
+    pub files: Vec<FileResult>,
+}
+
+impl CompressSummary {
+    /// 成功处理的文件数量
+    pub fn successful(&self) -> usize {
+        self.outcomes().count()
+    }
+
+    /// 处理失败的文件数量
+    pub fn failed(&self) -> usize {
+        self.files.iter().filter(|f| matches!(f.status, FileStatus::Failed(_))).count()
+    }
+
+    /// 命中缓存跳过的文件数量
+    pub fn skipped(&self) -> usize {
+        self.files.iter().filter(|f| f.status == FileStatus::Skipped).count()
+    }
+
+    /// 成功文件压缩前的总字节数
+    pub fn total_original(&self) -> u64 {
+        self.outcomes().map(|(_, outcome)| outcome.original_size).sum()
+    }
+
+    /// 成功文件压缩后的总字节数
+    pub fn total_output(&self) -> u64 {
+        self.outcomes().map(|(_, outcome)| outcome.output_size).sum()
+    }
+
+    /// 按字节加权的节省比例，避免少数大图被小图的压缩率平均掉
+    pub fn saved_ratio(&self) -> f64 {
+        match self.total_original() {
+            0 => 0.0,
+            total => 1.0 - self.total_output() as f64 / total as f64,
+        }
+    }
+
+    /// 成功处理的文件及其结果
+    pub fn outcomes(&self) -> impl Iterator<Item = (&Path, &FileOutcome)> {
+        self.files.iter().filter_map(|f| match &f.status {
+            FileStatus::Compressed(outcome) => Some((f.path.as_path(), outcome)),
+            _ => None,
+        })
+    }
+}
+
+/// 经过缓存检查的单个图片处理结果
+enum Processed {
+    /// 内容和参数与上次优化后一致，已跳过
+    Skipped,
+    /// 已处理，附带需要写入缓存的文件哈希
+    Done(Box<FileOutcome>, Option<String>),
+}
+
+/// 压缩目录中的图片
+///
+/// 只遍历一次目录，按 `options.jobs` 指定的线程数并行处理，并显示实时进度
+///
+/// # 参数
+/// * `path` - 目录路径
+/// * `img_type` - 图片类型
+/// * `options` - 压缩选项
+///
+/// # 返回
+/// * `Result<CompressSummary>` - 每个文件的处理结果
+pub fn compress_images(path: &Path, img_type: ImageType, options: &CompressOptions) -> Result<CompressSummary> {
+    if img_type == ImageType::Svg && (options.target_extension().is_some() || options.responsive.is_some()) {
+        return Err(Error::invalid_argument("SVG 只做压缩，不支持转换格式和响应式图片"));
+    }
+    let image_files = find_images(path, &img_type, options)?;
+    check_duplicate_outputs(path, &image_files, options)?;
+    let jobs = match options.jobs {
+        0 => rayon::current_num_threads(),
+        jobs => jobs,
+    }
+    .min(image_files.len())
+    .max(1);
+    println!("开始压缩 {} 图片，共 {} 个文件，使用 {} 个线程...", img_type.label(), image_files.len(), jobs);
+
+    let mut cache = cache::OptimizeCache::open(path, options);
+    let progress = Progress::new(image_files.len(), jobs);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .map_err(|e| Error::other(format!("创建线程池失败: {}", e)))?;
+
+    // 缓存在处理期间只读，结果汇总后再统一记录
+    let results: Vec<Result<Processed>> = pool.install(|| {
+        image_files
+            .par_iter()
+            .map(|file_path| {
+                progress.start(file_path);
+                let result = process_file(path, file_path, options, cache.as_ref());
+                progress.finish(file_path, &result);
+                result
+            })
+            .collect()
+    });
+    progress.clear();
+
+    let mut summary = CompressSummary::default();
+    for (file_path, result) in image_files.into_iter().zip(results) {
+        let status = match result {
+            Ok(Processed::Skipped) => FileStatus::Skipped,
+            Ok(Processed::Done(outcome, hash)) => {
+                if let (Some(cache), Some(hash)) = (cache.as_mut(), hash) {
+                    cache.record(&file_path, hash);
+                }
+                FileStatus::Compressed(*outcome)
+            }
+            Err(e) => FileStatus::Failed(e.to_string()),
+        };
+        summary.files.push(FileResult { path: file_path, status });
+    }
+
+    print_summary(&summary);
+    save_cache(cache.as_ref());
+    if options.responsive.is_some() {
+        let manifest = responsive::write_manifest(path, options, &summary)?;
+        println!("响应式图片清单已保存到: {}", manifest.display());
+    }
+    if options.placeholders {
+        let manifest = placeholder::write_manifest(path, options, &summary)?;
+        println!("占位数据清单已保存到: {}", manifest.display());
+    }
+    Ok(summary)
+}
+
+/// 转换格式时替换扩展名，`logo.png` 和 `logo.jpg` 会写到同一个文件；
+/// 生成响应式图片时，两者的 WebP/AVIF 尺寸文件也会重名，这些情况直接报错
+fn check_duplicate_outputs(root: &Path, files: &[PathBuf], options: &CompressOptions) -> Result<()> {
+    let target = |file: &Path| match &options.responsive {
+        Some(responsive) => {
+            let extension = responsive.formats.first()?.extensions()[0];
+            let output = output_path(root, file, options);
+            let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+            Some(output.with_file_name(format!("{}-<宽度>w.{}", stem, extension)))
+        }
+        None => options.target_extension().map(|_| output_path(root, file, options)),
+    };
+    let mut targets: BTreeMap<PathBuf, Vec<&Path>> = BTreeMap::new();
+    for file in files {
+        if let Some(output) = target(file) {
+            targets.entry(output).or_default().push(file);
+        }
+    }
+    let conflicts: Vec<String> = targets
+        .iter()
+        .filter(|(_, sources)| sources.len() > 1)
+        .map(|(target, sources)| {
+            let sources: Vec<String> = sources.iter().map(|source| source.display().to_string()).collect();
+            format!("{} -> {}", sources.join(", "), target.display())
+        })
+        .collect();
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    Err(Error::invalid_argument(format!("多个图片会转换为同一个文件，请先重命名: {}", conflicts.join("; "))))
+}
+
+/// 输出文件是否替换了原图的扩展名（转换格式且不生成响应式图片）
+fn replaces_extension(options: &CompressOptions) -> bool {
+    options.target_extension().is_some() && options.responsive.is_none()
+}
+
+/// 处理单个图片：启用缓存时先比较内容哈希，未变化的图片直接跳过
+///
+/// 输出写到其他文件时，输出文件被删除后会重新处理；
+/// 转换格式时不覆盖原图旁边已有的同名文件，除非缓存表明它由该原图生成或指定了 `force`
+fn process_file(
+    root: &Path,
+    image_path: &Path,
+    options: &CompressOptions,
+    cache: Option<&cache::OptimizeCache>,
+) -> Result<Processed> {
+    let output = output_path(root, image_path, options);
+    let generated = cache.is_some_and(|cache| cache.contains(image_path));
+    if replaces_extension(options) && options.out_dir.is_none() && !options.force && !generated && output.exists() {
+        return Err(Error::compression(format!("目标文件已存在: {}（使用 --force 覆盖）", output.display())));
+    }
+
+    let Some(cache) = cache else {
+        return compress_image(root, image_path, options).map(|outcome| Processed::Done(Box::new(outcome), None));
+    };
+    // 输出到其他文件（输出目录、_c 文件、转换格式）时，输出被删除后要重新生成
+    let output_missing = output != image_path && !output.exists();
+    if !options.force && !output_missing && cache.is_fresh(image_path, &sha256_file(image_path)?) {
+        return Ok(Processed::Skipped);
+    }
+
+    let outcome = compress_image(root, image_path, options)?;
+    // 覆写模式下内容已变化，记录处理后的哈希；原图被删除时不需要记录
+    let hash = if image_path.exists() { Some(sha256_file(image_path)?) } else { None };
+    Ok(Processed::Done(Box::new(outcome), hash))
+}
+
+/// 多行进度显示：一行总进度，每个线程一行当前处理的文件
+///
+/// 输出不是终端时 indicatif 会自动隐藏进度条
+struct Progress {
+    multi: MultiProgress,
+    overall: ProgressBar,
+    workers: Vec<ProgressBar>,
+}
+
+impl Progress {
+    fn new(total: usize, jobs: usize) -> Self {
+        let multi = MultiProgress::new();
+        let overall = multi.add(ProgressBar::new(total as u64));
+        overall.set_style(
+            ProgressStyle::default_bar()
+                .template("[{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+                .unwrap()
+                .progress_chars("█▉▊▋▌▍▎▏  "),
+        );
+        let workers = (0..jobs)
+            .map(|_| {
+                let worker = multi.add(ProgressBar::new_spinner());
+                worker.set_style(ProgressStyle::default_spinner().template("  {spinner:.green} {msg}").unwrap());
+                worker.enable_steady_tick(Duration::from_millis(120));
+                worker
+            })
+            .collect();
+        Self { multi, overall, workers }
+    }
+
+    /// 当前线程对应的进度行
+    fn worker(&self) -> Option<&ProgressBar> {
+        rayon::current_thread_index().and_then(|index| self.workers.get(index))
+    }
+
+    fn start(&self, file_path: &Path) {
+        if let Some(worker) = self.worker() {
+            worker.set_message(file_path.display().to_string());
+        }
+    }
+
+    fn finish(&self, file_path: &Path, result: &Result<Processed>) {
+        match result {
+            Ok(Processed::Skipped) => {}
+            Ok(Processed::Done(outcome, _)) => self.println(format!(
+                "成功压缩: {} (压缩率: {:.2}%)",
+                file_path.display(),
+                outcome.ratio * 100.0
+            )),
+            Err(e) => self.println(format!("压缩失败 {}: {}", file_path.display(), e)),
+        }
+        if let Some(worker) = self.worker() {
+            worker.set_message("等待中");
+        }
+        self.overall.inc(1);
+    }
+
+    /// 在进度条上方输出一行；进度条隐藏时直接输出到标准输出
+    fn println(&self, line: String) {
+        if self.multi.is_hidden() {
+            println!("{}", line);
+        } else {
+            let _ = self.multi.println(line);
+        }
+    }
+
+    fn clear(&self) {
+        for worker in &self.workers {
+            worker.finish_and_clear();
+        }
+        self.overall.finish_and_clear();
+    }
+}
+
+/// 打印压缩总结
+fn print_summary(summary: &CompressSummary) {
+    let successful = summary.successful();
+    println!("\n--- 压缩总结 ---");
+    println!("总共处理图片数量: {}", summary.files.len());
+    println!("成功压缩文件数量: {}", successful);
+    println!("失败压缩文件数量: {}", summary.failed());
+    if successful > 0 {
+        let (before, after) = (summary.total_original(), summary.total_output());
+        println!("压缩前总大小: {}", format_size(before));
+        println!("压缩后总大小: {}", format_size(after));
+        println!(
+            "体积变化: {} (按字节加权节省 {:.2}%)",
+            format_delta(after as i64 - before as i64),
+            summary.saved_ratio() * 100.0
+        );
+    } else {
+        println!("没有文件被成功压缩。");
+    }
+    print_resized(summary);
+    if summary.skipped() > 0 {
+        println!("跳过已优化且未变化的文件数量: {} (使用 --force 重新处理)", summary.skipped());
+    }
+    println!("--------------------");
+}
+
+/// 在总结中列出调整过尺寸的图片
+fn print_resized(summary: &CompressSummary) {
+    let resized_files: Vec<(&Path, Resized)> = summary
+        .outcomes()
+        .filter_map(|(path, outcome)| outcome.resized.map(|resized| (path, resized)))
+        .collect();
+    if resized_files.is_empty() {
+        return;
+    }
+    println!("调整尺寸的图片数量: {}", resized_files.len());
+    for (file_path, resized) in resized_files {
+        println!(
+            "  {}: {}x{} -> {}x{}",
+            file_path.display(),
+            resized.from.0,
+            resized.from.1,
+            resized.to.0,
+            resized.to.1
+        );
+    }
+}
+
+/// 保存缓存，失败时只提示不影响压缩结果
+fn save_cache(cache: Option<&cache::OptimizeCache>) {
+    if let Some(cache) = cache {
+        if let Err(e) = cache.save() {
+            eprintln!("保存优化缓存失败: {}", e);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{convert, metadata, CompressOptions, CompressSummary, FileStatus};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use maya_common::atomic::{write_atomic, AtomicWriteOptions};
+use maya_common::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 占位数据清单的文件名
+pub const PLACEHOLDER_MANIFEST: &str = "placeholders.json";
+/// 计算 BlurHash 和主色前把图片缩小到的边长
+const SAMPLE_SIZE: u32 = 32;
+/// 内联预览图长边的像素数
+const PREVIEW_SIZE: u32 = 16;
+/// 内联预览图的 WebP 质量
+const PREVIEW_QUALITY: f32 = 40.0;
+/// BlurHash 长边方向的分量数
+const BLURHASH_COMPONENTS: u32 = 4;
+
+/// 懒加载使用的低质量占位数据
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Placeholder {
+    /// 图片宽度，用于预留宽高比
+    pub width: u32,
+    /// 图片高度
+    pub height: u32,
+    pub blurhash: String,
+    /// 主色 `#rrggbb`，完全透明的图片为 `transparent`
+    pub color: String,
+    /// 内联的小尺寸 WebP，`data:image/webp;base64,...`
+    pub preview: String,
+}
+
+/// 启用占位数据时，根据已经解码的图片生成
+pub(crate) fn for_image(img: &DynamicImage, options: &CompressOptions) -> Result<Option<Placeholder>> {
+    if !options.placeholders {
+        return Ok(None);
+    }
+    let (width, height) = img.dimensions();
+    let sample = shrink(img, SAMPLE_SIZE).to_rgba8();
+    let (components_x, components_y) = blurhash_components(width, height);
+    let blurhash = blurhash::encode(components_x, components_y, sample.width(), sample.height(), sample.as_raw())
+        .map_err(|e| Error::compression(format!("BlurHash 计算失败: {}", e)))?;
+
+    let preview = convert::encode_webp(&shrink(img, PREVIEW_SIZE), false, PREVIEW_QUALITY);
+    Ok(Some(Placeholder {
+        width,
+        height,
+        blurhash,
+        color: dominant_color(sample.as_raw()),
+        preview: format!("data:image/webp;base64,{}", STANDARD.encode(preview)),
+    }))
+}
+
+/// 处理过程中没有解码图片时，启用占位数据才解码
+pub(crate) fn for_data(data: &[u8], format: ImageFormat, options: &CompressOptions) -> Result<Option<Placeholder>> {
+    if !options.placeholders {
+        return Ok(None);
+    }
+    for_image(&metadata::decode_oriented(data, format)?, options)
+}
+
+/// 缩小到长边不超过 `size`，不放大
+fn shrink(img: &DynamicImage, size: u32) -> DynamicImage {
+    if img.width() <= size && img.height() <= size {
+        return img.clone();
+    }
+    img.resize(size, size, FilterType::Triangle)
+}
+
+/// 长边使用 4 个分量，短边按宽高比减少
+fn blurhash_components(width: u32, height: u32) -> (u32, u32) {
+    let short = |long: u32, short: u32| {
+        ((BLURHASH_COMPONENTS as f64 * short as f64 / long.max(1) as f64).round() as u32).clamp(1, BLURHASH_COMPONENTS)
+    };
+    if width >= height {
+        (BLURHASH_COMPONENTS, short(width, height))
+    } else {
+        (short(height, width), BLURHASH_COMPONENTS)
+    }
+}
+
+/// 主色：把不透明像素按每通道 16 级分桶，取像素最多的桶的平均颜色
+fn dominant_color(rgba: &[u8]) -> String {
+    let mut buckets: HashMap<(u8, u8, u8), (u32, [u32; 3])> = HashMap::new();
+    for pixel in rgba.chunks_exact(4).filter(|pixel| pixel[3] >= 128) {
+        let (count, sum) = buckets.entry((pixel[0] >> 4, pixel[1] >> 4, pixel[2] >> 4)).or_default();
+        *count += 1;
+        for channel in 0..3 {
+            sum[channel] += u32::from(pixel[channel]);
+        }
+    }
+    // 数量相同时取较小的桶，保证结果稳定
+    let dominant = buckets.into_iter().max_by_key(|&(key, (count, _))| (count, Reverse(key)));
+    let Some((_, (count, sum))) = dominant else {
+        return "transparent".to_string();
+    };
+    format!("#{:02x}{:02x}{:02x}", sum[0] / count, sum[1] / count, sum[2] / count)
+}
+
+/// 写出占位数据清单，以相对于源目录的原图路径为键
+///
+/// 文件保存在输出目录中，没有设置输出目录时保存在源目录中；
+/// 命中缓存跳过的图片沿用上次清单中的数据
+pub(crate) fn write_manifest(root: &Path, options: &CompressOptions, summary: &CompressSummary) -> Result<PathBuf> {
+    let base = options.out_dir.as_deref().unwrap_or(root);
+    let manifest_path = base.join(PLACEHOLDER_MANIFEST);
+    let mut previous: BTreeMap<String, Placeholder> = fs::read(&manifest_path)
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default();
+
+    let mut manifest = BTreeMap::new();
+    for file in &summary.files {
+        let key = file.path.strip_prefix(root).unwrap_or(&file.path).to_string_lossy().replace('\\', "/");
+        let placeholder = match &file.status {
+            FileStatus::Compressed(outcome) => outcome.placeholder.clone(),
+            FileStatus::Skipped => previous.remove(&key),
+            FileStatus::Failed(_) => None,
+        };
+        if let Some(placeholder) = placeholder {
+            manifest.insert(key, placeholder);
+        }
+    }
+
+    fs::create_dir_all(base)?;
+    let content = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| Error::other(format!("占位数据清单序列化失败: {}", e)))?;
+    write_atomic(&manifest_path, content.as_bytes(), &AtomicWriteOptions::default())?;
+    Ok(manifest_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compress_images, ImageType};
+    use image::{ImageBuffer, Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_blurhash_components_and_dominant_color() {
+        assert_eq!(blurhash_components(1920, 1080), (4, 2));
+        assert_eq!(blurhash_components(100, 400), (1, 4));
+        assert_eq!(blurhash_components(50, 50), (4, 4));
+
+        let mut rgba = Vec::new();
+        for i in 0..10 {
+            let pixel: [u8; 4] = if i < 6 { [200, 30, 40 + i, 255] } else { [0, 0, 255, 255] };
+            rgba.extend_from_slice(&pixel);
+        }
+        rgba.extend_from_slice(&[0, 255, 0, 0]);
+        assert_eq!(dominant_color(&rgba), "#c81e2a");
+        assert_eq!(dominant_color(&[0, 0, 0, 0]), "transparent");
+    }
+
+    #[test]
+    fn test_placeholder_manifest() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("img")).unwrap();
+        let img: ImageBuffer<Rgb<u8>, _> =
+            ImageBuffer::from_fn(300, 200, |x, y| if x < 200 { Rgb([20, 120, 220]) } else { Rgb([(y % 256) as u8, 0, 0]) });
+        img.save(root.join("img").join("hero.jpg")).unwrap();
+        RgbImage::from_pixel(40, 40, Rgb([20, 120, 220])).save(root.join("logo.png")).unwrap();
+
+        let options = CompressOptions { placeholders: true, ..Default::default() };
+        let summary = compress_images(root, ImageType::All, &options).unwrap();
+        assert_eq!(summary.successful(), 2);
+
+        let manifest: BTreeMap<String, Placeholder> =
+            serde_json::from_str(&fs::read_to_string(root.join(PLACEHOLDER_MANIFEST)).unwrap()).unwrap();
+        assert_eq!(manifest.keys().collect::<Vec<_>>(), vec!["img/hero.jpg", "logo.png"]);
+        let hero = &manifest["img/hero.jpg"];
+        assert_eq!((hero.width, hero.height), (300, 200));
+        assert_eq!(hero.blurhash.len(), 6 + 2 * (4 * 3 - 1));
+        assert_eq!(manifest["logo.png"].color, "#1478dc");
+
+        let preview = STANDARD.decode(hero.preview.strip_prefix("data:image/webp;base64,").unwrap()).unwrap();
+        let decoded = image::load_from_memory_with_format(&preview, ImageFormat::WebP).unwrap();
+        assert_eq!(decoded.dimensions(), (16, 11));
+    }
+}
@@ -0,0 +1,306 @@
+use crate::metadata::{self, MetadataOptions, PngChunk};
+use image::codecs::png::PngDecoder;
+use image::DynamicImage;
+use maya_common::error::{Error, Result};
+use oxipng::{optimize_from_memory, BitDepth, ColorType, Deflaters, IndexSet, Options, RawImage, StripChunks};
+use serde::Serialize;
+use std::io::Cursor;
+use std::num::NonZeroU8;
+use std::str::FromStr;
+
+/// oxipng 默认预设等级
+pub const DEFAULT_PNG_LEVEL: u8 = 2;
+/// Zopfli 压缩迭代次数
+const ZOPFLI_ITERATIONS: u8 = 15;
+/// imagequant 速度（1 最慢质量最好，10 最快）
+const QUANTIZE_SPEED: i32 = 4;
+
+
+/// PNG 元数据清理方式
+///
+/// 设置为 `None` 以外的值时优先于 `--metadata`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum PngStrip {
+    /// 保留所有元数据
+    #[default]
+    None,
+    /// 移除不影响显示的元数据（保留色彩空间等信息）
+    Safe,
+    /// 移除所有非关键块
+    All,
+}
+
+impl FromStr for PngStrip {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(PngStrip::None),
+            "safe" => Ok(PngStrip::Safe),
+            "all" => Ok(PngStrip::All),
+            _ => Err(format!("不支持的元数据清理方式: {}（可选 none、safe、all）", s)),
+        }
+    }
+}
+
+/// PNG 压缩选项
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PngOptions {
+    /// 有损调色板量化的质量范围 (最低, 目标)，为 `None` 时只做无损优化
+    pub quantize: Option<(u8, u8)>,
+    /// 量化时的抖动强度 0.0-1.0
+    pub dithering: f32,
+    /// oxipng 预设等级 0-6，越高越慢
+    pub level: u8,
+    /// 使用 Zopfli 压缩（体积更小，速度慢很多）
+    pub zopfli: bool,
+    /// 元数据清理方式
+    pub strip: PngStrip,
+    /// 修改完全透明像素的颜色以提高压缩率
+    pub optimize_alpha: bool,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self {
+            quantize: None,
+            dithering: 1.0,
+            level: DEFAULT_PNG_LEVEL,
+            zopfli: false,
+            strip: PngStrip::None,
+            optimize_alpha: false,
+        }
+    }
+}
+
+/// 解析量化质量范围，例如 `65-80`；只写一个数字时表示目标质量，最低质量为 0
+pub fn parse_quality_range(s: &str) -> std::result::Result<(u8, u8), String> {
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<u8>()
+            .ok()
+            .filter(|q| *q <= 100)
+            .ok_or_else(|| format!("无效的质量范围: {}（示例: 65-80）", s))
+    };
+    let (min, target) = match s.split_once('-') {
+        Some((min, target)) => (parse(min)?, parse(target)?),
+        None => (0, parse(s)?),
+    };
+    if min > target {
+        return Err(format!("无效的质量范围: {}，最低质量不能大于目标质量", s));
+    }
+    Ok((min, target))
+}
+
+/// 压缩 PNG 数据：可选先做调色板量化，再交给 oxipng 无损优化
+///
+/// 元数据块由 oxipng 移除后，按元数据选项从 `original` 中挑选，写回到 IHDR 之后；
+/// `data` 是缩放后重新编码的数据时，`original` 传入缩放前的原图
+pub(crate) fn optimize_png(
+    data: &[u8],
+    original: &[u8],
+    options: &PngOptions,
+    metadata: &MetadataOptions,
+) -> Result<Vec<u8>> {
+    let oxipng_options = oxipng_options(options);
+    let png_error = |e: oxipng::PngError| Error::compression(format!("PNG优化失败: {}", e));
+
+    let mut optimized = None;
+    if let Some(range) = options.quantize {
+        match quantize(data, range, options.dithering)? {
+            Some(raw) => optimized = Some(raw.create_optimized_png(&oxipng_options).map_err(png_error)?),
+            None => println!("提示: 量化后达不到最低质量 {}，改为无损优化", range.0),
+        }
+    }
+    let optimized = match optimized {
+        Some(optimized) => optimized,
+        None => optimize_from_memory(data, &oxipng_options).map_err(png_error)?,
+    };
+
+    let chunks = match options.strip {
+        PngStrip::None => metadata::png_chunks(original, metadata),
+        // oxipng 的 safe 模式本身保留色彩块，量化或缩放后的图片没有，需要从原图补上
+        PngStrip::Safe => metadata::read_png_chunks(original)
+            .into_iter()
+            .filter(|chunk| metadata::PNG_COLOR_CHUNKS.contains(&chunk.name))
+            .collect(),
+        PngStrip::All => Vec::new(),
+    };
+    Ok(insert_chunks(&optimized, chunks))
+}
+
+/// 把辅助块插入到 IHDR 之后；输出中已有色彩块时不再插入色彩块，避免 iCCP 和 sRGB 同时出现
+fn insert_chunks(png: &[u8], mut chunks: Vec<PngChunk>) -> Vec<u8> {
+    // 8 字节签名 + IHDR（4 字节长度、4 字节类型、13 字节数据、4 字节 CRC）
+    const IHDR_END: usize = 8 + 25;
+    let existing = metadata::read_png_chunks(png);
+    if existing.iter().any(|chunk| metadata::PNG_COLOR_CHUNKS.contains(&chunk.name)) {
+        chunks.retain(|chunk| !metadata::PNG_COLOR_CHUNKS.contains(&chunk.name));
+    }
+    if chunks.is_empty() || png.len() < IHDR_END {
+        return png.to_vec();
+    }
+
+    let mut output = Vec::with_capacity(png.len() + chunks.iter().map(|c| c.data.len() + 12).sum::<usize>());
+    output.extend_from_slice(&png[..IHDR_END]);
+    for chunk in chunks {
+        output.extend_from_slice(&(chunk.data.len() as u32).to_be_bytes());
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&chunk.name);
+        hasher.update(&chunk.data);
+        output.extend_from_slice(&chunk.name);
+        output.extend_from_slice(&chunk.data);
+        output.extend_from_slice(&hasher.finalize().to_be_bytes());
+    }
+    output.extend_from_slice(&png[IHDR_END..]);
+    output
+}
+
+fn oxipng_options(options: &PngOptions) -> Options {
+    let mut oxipng_options = Options::from_preset(options.level);
+    if options.zopfli {
+        oxipng_options.deflate = Deflaters::Zopfli {
+            iterations: NonZeroU8::new(ZOPFLI_ITERATIONS).expect("迭代次数不为 0"),
+        };
+    }
+    oxipng_options.strip = match options.strip {
+        // 元数据块由 optimize_png 按选项写回
+        PngStrip::None => StripChunks::Strip(metadata::PNG_METADATA_CHUNKS.into_iter().collect::<IndexSet<_>>()),
+        PngStrip::Safe => StripChunks::Safe,
+        PngStrip::All => StripChunks::All,
+    };
+    oxipng_options.optimize_alpha = options.optimize_alpha;
+    oxipng_options
+}
+
+/// 使用 imagequant 把图片量化为最多 256 色的调色板图
+///
+/// # 返回
+/// * `Result<Option<RawImage>>` - 达不到最低质量时返回 `None`
+fn quantize(data: &[u8], (min, target): (u8, u8), dithering: f32) -> Result<Option<RawImage>> {
+    let decoder =
+        PngDecoder::new(Cursor::new(data)).map_err(|e| Error::compression(format!("无法打开图片: {}", e)))?;
+    let img = DynamicImage::from_decoder(decoder).map_err(|e| Error::compression(format!("无法打开图片: {}", e)))?;
+    let (width, height) = (img.width(), img.height());
+    let pixels: Vec<imagequant::RGBA> = img
+        .to_rgba8()
+        .pixels()
+        .map(|p| imagequant::RGBA::new(p[0], p[1], p[2], p[3]))
+        .collect();
+
+    let quantize_error = |e: imagequant::Error| Error::compression(format!("PNG量化失败: {}", e));
+    let mut attributes = imagequant::new();
+    attributes.set_speed(QUANTIZE_SPEED).map_err(quantize_error)?;
+    attributes.set_quality(min, target).map_err(quantize_error)?;
+    let mut image = attributes
+        .new_image(pixels, width as usize, height as usize, 0.0)
+        .map_err(quantize_error)?;
+    let mut result = match attributes.quantize(&mut image) {
+        Ok(result) => result,
+        Err(imagequant::Error::QualityTooLow) => return Ok(None),
+        Err(e) => return Err(quantize_error(e)),
+    };
+    result.set_dithering_level(dithering.clamp(0.0, 1.0)).map_err(quantize_error)?;
+    let (palette, indices) = result.remapped(&mut image).map_err(quantize_error)?;
+
+    let raw = RawImage::new(width, height, ColorType::Indexed { palette }, BitDepth::Eight, indices)
+        .map_err(|e| Error::compression(format!("PNG编码失败: {}", e)))?;
+    Ok(Some(raw))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgba};
+
+    /// 在 IHDR 之后插入指定的辅助块
+    pub(crate) fn png_with_chunks(chunks: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        DynamicImage::new_rgb8(8, 4).write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png).unwrap();
+        let chunks = chunks.iter().map(|(name, data)| PngChunk { name: *name, data: data.clone() }).collect();
+        insert_chunks(&data, chunks)
+    }
+
+    /// 带噪点的彩色图，无损压缩空间有限，量化为调色板后体积明显变小
+    fn noisy_png() -> Vec<u8> {
+        let img: ImageBuffer<Rgba<u8>, _> = ImageBuffer::from_fn(96, 96, |x, y| {
+            let noise = (x.wrapping_mul(7919) ^ y.wrapping_mul(104729)).wrapping_mul(2654435761) >> 24;
+            Rgba([(x * 2) as u8, (y * 2) as u8, noise as u8, 255])
+        });
+        let mut data = Vec::new();
+        DynamicImage::ImageRgba8(img)
+            .write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_quantize_produces_palette_png() {
+        let data = noisy_png();
+        let lossless = optimize_png(&data, &data, &PngOptions::default(), &MetadataOptions::default()).unwrap();
+        let options = PngOptions { quantize: Some((0, 80)), dithering: 0.5, ..Default::default() };
+        let quantized = optimize_png(&data, &data, &options, &MetadataOptions::default()).unwrap();
+        assert!(quantized.len() < lossless.len());
+
+        let decoded = image::load_from_memory(&quantized).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (96, 96));
+    }
+
+    #[test]
+    fn test_quantize_falls_back_when_quality_too_low() {
+        let data = noisy_png();
+        let options = PngOptions { quantize: Some((100, 100)), ..Default::default() };
+        let result = optimize_png(&data, &data, &options, &MetadataOptions::default()).unwrap();
+        // 回退到无损优化，像素保持不变
+        let before = image::load_from_memory(&data).unwrap().to_rgba8();
+        let after = image::load_from_memory(&result).unwrap().to_rgba8();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_default_keeps_exif_orientation() {
+        // 只有方向 6 的 eXIf 块，默认模式下不应因为移除 GPS 而整块丢失
+        let tiff = b"II*\0\x08\0\0\0\x01\0\x12\x01\x03\0\x01\0\0\0\x06\0\0\0\0\0\0\0".to_vec();
+        let data = png_with_chunks(&[(*b"eXIf", tiff.clone())]);
+        let output = optimize_png(&data, &data, &PngOptions::default(), &MetadataOptions::default()).unwrap();
+        let chunks = metadata::read_png_chunks(&output);
+        assert_eq!(chunks, vec![PngChunk { name: *b"eXIf", data: tiff }]);
+        assert!(image::load_from_memory(&output).is_ok());
+    }
+
+    #[test]
+    fn test_resize_and_quantize_keep_metadata() {
+        use crate::{compress_image, CompressOptions, ResizeOptions};
+
+        let mut data = noisy_png();
+        let text = (*b"tEXt", b"Copyright\0Alice".to_vec());
+        let gamma = (*b"gAMA", 45455u32.to_be_bytes().to_vec());
+        data = insert_chunks(&data, [text.clone(), gamma.clone()].map(|(name, data)| PngChunk { name, data }).to_vec());
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("photo.png");
+        std::fs::write(&path, &data).unwrap();
+
+        for quantize in [None, Some((0, 80))] {
+            let options = CompressOptions {
+                create_new_file: true,
+                resize: ResizeOptions { max_width: Some(48), ..Default::default() },
+                png: PngOptions { quantize, ..Default::default() },
+                ..Default::default()
+            };
+            compress_image(temp_dir.path(), &path, &options).unwrap();
+            let output = std::fs::read(temp_dir.path().join("photo_c.png")).unwrap();
+            assert_eq!(image::load_from_memory(&output).unwrap().width(), 48);
+            let names: Vec<[u8; 4]> = metadata::read_png_chunks(&output).iter().map(|chunk| chunk.name).collect();
+            assert!(names.contains(&text.0) && names.contains(&gamma.0), "{:?}", quantize);
+        }
+    }
+
+    #[test]
+    fn test_parse_quality_range() {
+        assert_eq!(parse_quality_range("65-80").unwrap(), (65, 80));
+        assert_eq!(parse_quality_range("90").unwrap(), (0, 90));
+        assert!(parse_quality_range("80-65").is_err());
+        assert!(parse_quality_range("0-101").is_err());
+        assert_eq!("SAFE".parse::<PngStrip>().unwrap(), PngStrip::Safe);
+    }
+}
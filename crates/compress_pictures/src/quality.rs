@@ -0,0 +1,187 @@
+use crate::CompressOptions;
+use image::{DynamicImage, GrayImage};
+use maya_common::error::{Error, Result};
+
+/// SSIM 计算窗口边长
+const WINDOW: u32 = 8;
+/// 窗口移动步长，窗口之间有一半重叠
+const STRIDE: u32 = 4;
+const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// 有损编码的结果
+#[derive(Debug)]
+pub(crate) struct Encoded {
+    pub data: Vec<u8>,
+    /// 使用的编码质量，无损编码时为 `None`
+    pub quality: Option<u8>,
+    /// 按目标 SSIM 搜索时达到的 SSIM
+    pub ssim: Option<f64>,
+}
+
+impl Encoded {
+    /// 无损编码，没有质量参数
+    pub(crate) fn lossless(data: Vec<u8>) -> Self {
+        Self { data, quality: None, ssim: None }
+    }
+}
+
+/// 以固定质量编码；设置了 `target_ssim` 时改为按目标 SSIM 搜索质量
+///
+/// # 参数
+/// * `img` - 要编码的图片
+/// * `default_quality` - 没有指定质量时使用的默认值
+/// * `options` - 压缩选项
+/// * `encode` - 按给定质量编码的函数
+pub(crate) fn encode_lossy<E>(img: &DynamicImage, default_quality: u8, options: &CompressOptions, encode: E) -> Result<Encoded>
+where
+    E: Fn(u8) -> Result<Vec<u8>>,
+{
+    match options.target_ssim {
+        Some(target) => search_quality(img, target, encode),
+        None => {
+            let quality = options.quality.unwrap_or(default_quality);
+            Ok(Encoded { data: encode(quality)?, quality: Some(quality), ssim: None })
+        }
+    }
+}
+
+/// 二分查找满足目标 SSIM 的最低质量
+///
+/// 每一步都解码编码结果并与原图比较亮度通道的 SSIM；相同输入总是得到相同结果。
+/// 最高质量也达不到目标时使用 100
+fn search_quality<E>(img: &DynamicImage, target: f64, encode: E) -> Result<Encoded>
+where
+    E: Fn(u8) -> Result<Vec<u8>>,
+{
+    let reference = img.to_luma8();
+    let measure = |data: &[u8]| -> Result<f64> {
+        let decoded = image::load_from_memory(data)
+            .map_err(|e| Error::compression(format!("解码编码结果失败: {}", e)))?;
+        Ok(ssim(&reference, &decoded.to_luma8()))
+    };
+
+    let (mut low, mut high) = (1u8, 100u8);
+    let mut best: Option<Encoded> = None;
+    while low <= high {
+        let quality = low + (high - low) / 2;
+        let data = encode(quality)?;
+        let score = measure(&data)?;
+        if score >= target {
+            best = Some(Encoded { data, quality: Some(quality), ssim: Some(score) });
+            if quality == 1 {
+                break;
+            }
+            high = quality - 1;
+        } else {
+            low = quality + 1;
+        }
+    }
+
+    match best {
+        Some(best) => Ok(best),
+        None => {
+            let data = encode(100)?;
+            let score = measure(&data)?;
+            Ok(Encoded { data, quality: Some(100), ssim: Some(score) })
+        }
+    }
+}
+
+/// 计算两张灰度图的平均 SSIM，尺寸不同时返回 0
+///
+/// 使用 8x8 窗口、步长 4 的均匀加权，图片小于窗口时整张图作为一个窗口
+pub(crate) fn ssim(a: &GrayImage, b: &GrayImage) -> f64 {
+    if a.dimensions() != b.dimensions() || a.width() == 0 || a.height() == 0 {
+        return 0.0;
+    }
+    let (width, height) = a.dimensions();
+    let window_w = WINDOW.min(width);
+    let window_h = WINDOW.min(height);
+
+    let mut total = 0.0;
+    let mut count = 0u32;
+    let mut y = 0;
+    loop {
+        let mut x = 0;
+        loop {
+            total += window_ssim(a, b, x, y, window_w, window_h);
+            count += 1;
+            if x + window_w >= width {
+                break;
+            }
+            x = (x + STRIDE).min(width - window_w);
+        }
+        if y + window_h >= height {
+            break;
+        }
+        y = (y + STRIDE).min(height - window_h);
+    }
+    total / count as f64
+}
+
+fn window_ssim(a: &GrayImage, b: &GrayImage, x0: u32, y0: u32, w: u32, h: u32) -> f64 {
+    let n = (w * h) as f64;
+    let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for y in y0..y0 + h {
+        for x in x0..x0 + w {
+            let pa = a.get_pixel(x, y)[0] as f64;
+            let pb = b.get_pixel(x, y)[0] as f64;
+            sum_a += pa;
+            sum_b += pb;
+            sum_aa += pa * pa;
+            sum_bb += pb * pb;
+            sum_ab += pa * pb;
+        }
+    }
+    let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+    let var_a = sum_aa / n - mean_a * mean_a;
+    let var_b = sum_bb / n - mean_b * mean_b;
+    let cov = sum_ab / n - mean_a * mean_b;
+    ((2.0 * mean_a * mean_b + C1) * (2.0 * cov + C2))
+        / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jpeg::{encode_jpeg, ChromaSubsampling};
+    use image::{ImageBuffer, Luma, Rgb};
+
+    fn sample_image() -> DynamicImage {
+        DynamicImage::ImageRgb8(ImageBuffer::from_fn(96, 64, |x, y| {
+            Rgb([(x * 2) as u8, (y * 3) as u8, (((x * 7) ^ (y * 5)) % 256) as u8])
+        }))
+    }
+
+    #[test]
+    fn test_ssim_identical_and_degraded() {
+        let a: GrayImage = ImageBuffer::from_fn(20, 12, |x, y| Luma([(x * 10 + y) as u8]));
+        assert!((ssim(&a, &a) - 1.0).abs() < 1e-9);
+
+        let noisy: GrayImage = ImageBuffer::from_fn(20, 12, |x, y| {
+            Luma([(a.get_pixel(x, y)[0] as i32 + if (x + y) % 2 == 0 { 40 } else { -40 }).clamp(0, 255) as u8])
+        });
+        assert!(ssim(&a, &noisy) < 0.9);
+        assert_eq!(ssim(&a, &GrayImage::new(4, 4)), 0.0);
+    }
+
+    #[test]
+    fn test_search_quality_is_deterministic_and_meets_target() {
+        let img = sample_image();
+        let encode = |q| encode_jpeg(&img, q, true, ChromaSubsampling::S444, &[]);
+        let options = |target| CompressOptions { target_ssim: Some(target), ..Default::default() };
+
+        let first = encode_lossy(&img, 80, &options(0.95), encode).unwrap();
+        let second = encode_lossy(&img, 80, &options(0.95), encode).unwrap();
+        assert_eq!(first.quality, second.quality);
+        assert_eq!(first.data, second.data);
+        assert!(first.ssim.unwrap() >= 0.95);
+
+        let strict = encode_lossy(&img, 80, &options(0.99), encode).unwrap();
+        assert!(strict.quality.unwrap() >= first.quality.unwrap());
+
+        let fixed = encode_lossy(&img, 80, &CompressOptions::default(), encode).unwrap();
+        assert_eq!((fixed.quality, fixed.ssim), (Some(80), None));
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{CompressSummary, FileStatus};
+use maya_common::atomic::{write_atomic, AtomicWriteOptions};
+use maya_common::error::{Error, Result};
+use maya_common::size_utils::format_size;
+use serde::Serialize;
+use std::path::Path;
+use std::str::FromStr;
+
+/// 报告文件名（不含扩展名），默认保存在被优化目录的 `.maya` 中
+pub const REPORT_FILE_STEM: &str = "optimize-report";
+
+/// 报告导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Html,
+}
+
+impl ReportFormat {
+    /// 默认报告文件名，例如 `optimize-report.json`
+    pub fn file_name(self) -> String {
+        let extension = match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Html => "html",
+        };
+        format!("{}.{}", REPORT_FILE_STEM, extension)
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            "html" => Ok(ReportFormat::Html),
+            _ => Err(format!("不支持的报告格式: {}（可选 json、csv、html）", s)),
+        }
+    }
+}
+
+/// 报告中的一行
+#[derive(Debug, Serialize)]
+struct ReportRow {
+    /// 相对于被优化目录的路径
+    path: String,
+    format: Option<&'static str>,
+    width: Option<u32>,
+    height: Option<u32>,
+    before: Option<u64>,
+    after: Option<u64>,
+    ratio: Option<f64>,
+    /// 有损编码使用的质量
+    quality: Option<u8>,
+    /// 按目标 SSIM 编码时达到的 SSIM
+    ssim: Option<f64>,
+    /// compressed/kept/cached/failed
+    status: &'static str,
+    /// 跳过或失败的原因
+    reason: Option<String>,
+}
+
+/// 导出的完整报告
+#[derive(Debug, Serialize)]
+struct Report {
+    total_before: u64,
+    total_after: u64,
+    saved_bytes: i64,
+    /// 按字节加权的节省比例
+    saved_ratio: f64,
+    files: Vec<ReportRow>,
+}
+
+impl Report {
+    fn new(summary: &CompressSummary, root: &Path) -> Self {
+        let files = summary
+            .files
+            .iter()
+            .map(|file| {
+                let path = file.path.strip_prefix(root).unwrap_or(&file.path).to_string_lossy().replace('\\', "/");
+                let mut row = ReportRow {
+                    path,
+                    format: None,
+                    width: None,
+                    height: None,
+                    before: None,
+                    after: None,
+                    ratio: None,
+                    quality: None,
+                    ssim: None,
+                    status: "cached",
+                    reason: None,
+                };
+                match &file.status {
+                    FileStatus::Compressed(outcome) => {
+                        row.format = Some(outcome.format);
+                        row.width = outcome.dimensions.map(|d| d.0);
+                        row.height = outcome.dimensions.map(|d| d.1);
+                        row.before = Some(outcome.original_size);
+                        row.after = Some(outcome.output_size);
+                        row.ratio = Some(outcome.ratio);
+                        row.quality = outcome.quality;
+                        row.ssim = outcome.ssim;
+                        if outcome.kept_original {
+                            row.status = "kept";
+                            row.reason = Some("压缩后未变小，保留原图".to_string());
+                        } else {
+                            row.status = "compressed";
+                        }
+                    }
+                    FileStatus::Skipped => row.reason = Some("内容和参数未变化，命中缓存".to_string()),
+                    FileStatus::Failed(e) => {
+                        row.status = "failed";
+                        row.reason = Some(e.clone());
+                    }
+                }
+                row
+            })
+            .collect();
+
+        Self {
+            total_before: summary.total_original(),
+            total_after: summary.total_output(),
+            saved_bytes: summary.total_original() as i64 - summary.total_output() as i64,
+            saved_ratio: summary.saved_ratio(),
+            files,
+        }
+    }
+
+    fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::other(format!("报告序列化失败: {}", e)))
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("path,format,width,height,before,after,ratio,quality,ssim,status,reason\n");
+        for row in &self.files {
+            let fields = [
+                csv_field(&row.path),
+                row.format.unwrap_or_default().to_string(),
+                optional(row.width),
+                optional(row.height),
+                optional(row.before),
+                optional(row.after),
+                row.ratio.map(|r| format!("{:.4}", r)).unwrap_or_default(),
+                optional(row.quality),
+                row.ssim.map(|s| format!("{:.4}", s)).unwrap_or_default(),
+                row.status.to_string(),
+                csv_field(row.reason.as_deref().unwrap_or_default()),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    fn to_html(&self) -> String {
+        let mut rows = String::new();
+        for row in &self.files {
+            let dimensions = match (row.width, row.height) {
+                (Some(width), Some(height)) => format!("{}x{}", width, height),
+                _ => String::new(),
+            };
+            rows.push_str(&format!(
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                row.status,
+                html_escape(&row.path),
+                row.format.unwrap_or_default(),
+                dimensions,
+                row.before.map(format_size).unwrap_or_default(),
+                row.after.map(format_size).unwrap_or_default(),
+                row.ratio.map(|r| format!("{:.2}%", r * 100.0)).unwrap_or_default(),
+                optional(row.quality),
+                row.ssim.map(|s| format!("{:.4}", s)).unwrap_or_default(),
+                html_escape(row.reason.as_deref().unwrap_or_default()),
+            ));
+        }
+
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+<meta charset="utf-8">
+<title>图片优化报告</title>
+<style>
+body {{ font-family: sans-serif; margin: 24px; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #ddd; padding: 4px 8px; text-align: left; }}
+tr.kept, tr.cached {{ color: #888; }}
+tr.failed {{ color: #c00; }}
+</style>
+</head>
+<body>
+<h1>图片优化报告</h1>
+<p>压缩前 {} → 压缩后 {}，节省 {} ({:.2}%)</p>
+<table>
+<tr><th>文件</th><th>格式</th><th>尺寸</th><th>压缩前</th><th>压缩后</th><th>压缩率</th><th>质量</th><th>SSIM</th><th>说明</th></tr>
+{}</table>
+</body>
+</html>
+"#,
+            format_size(self.total_before),
+            format_size(self.total_after),
+            format_size(self.saved_bytes.max(0) as u64),
+            self.saved_ratio * 100.0,
+            rows
+        )
+    }
+}
+
+/// 导出压缩报告
+///
+/// # 参数
+/// * `summary` - 压缩结果
+/// * `root` - 被优化的目录，报告中的路径相对于该目录
+/// * `format` - 报告格式
+/// * `path` - 报告文件路径
+pub fn write_report(summary: &CompressSummary, root: &Path, format: ReportFormat, path: &Path) -> Result<()> {
+    let report = Report::new(summary, root);
+    let content = match format {
+        ReportFormat::Json => report.to_json()?,
+        ReportFormat::Csv => report.to_csv(),
+        ReportFormat::Html => report.to_html(),
+    };
+    write_atomic(path, content.as_bytes(), &AtomicWriteOptions::default())
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// 含逗号、引号或换行的字段需要用引号包裹
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileOutcome, FileResult};
+    use std::path::PathBuf;
+
+    fn sample_summary() -> CompressSummary {
+        let big = FileOutcome {
+            ratio: 0.5,
+            original_size: 9000,
+            output_size: 4500,
+            format: "jpeg",
+            dimensions: Some((1920, 1080)),
+            quality: Some(72),
+            ssim: Some(0.95),
+            ..Default::default()
+        };
+        let small = FileOutcome {
+            original_size: 1000,
+            output_size: 1000,
+            format: "png",
+            kept_original: true,
+            ..Default::default()
+        };
+        CompressSummary {
+            files: vec![
+                FileResult { path: PathBuf::from("/site/img/big.jpg"), status: FileStatus::Compressed(big) },
+                FileResult { path: PathBuf::from("/site/a,b.png"), status: FileStatus::Compressed(small) },
+                FileResult { path: PathBuf::from("/site/c.png"), status: FileStatus::Skipped },
+                FileResult { path: PathBuf::from("/site/<d>.png"), status: FileStatus::Failed("解码失败".to_string()) },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_report_totals_are_weighted() {
+        let report = Report::new(&sample_summary(), Path::new("/site"));
+        assert_eq!((report.total_before, report.total_after, report.saved_bytes), (10000, 5500, 4500));
+        // 加权节省 45%，而不是两个文件压缩率的平均值 25%
+        assert!((report.saved_ratio - 0.45).abs() < 1e-9);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["files"][0]["path"], "img/big.jpg");
+        assert_eq!(json["files"][0]["width"], 1920);
+        assert_eq!(json["files"][0]["quality"], 72);
+        assert_eq!(json["files"][1]["status"], "kept");
+        assert_eq!(json["files"][2]["status"], "cached");
+    }
+
+    #[test]
+    fn test_csv_and_html_escape_fields() {
+        let report = Report::new(&sample_summary(), Path::new("/site"));
+        let csv = report.to_csv();
+        assert!(csv.contains("img/big.jpg,jpeg,1920,1080,9000,4500,0.5000,72,0.9500,compressed,\n"));
+        assert!(csv.contains("\"a,b.png\",png"));
+
+        let html = report.to_html();
+        assert!(html.contains("&lt;d&gt;.png"));
+        assert!(html.contains("45.00%"));
+        assert!("xml".parse::<ReportFormat>().is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use image::imageops::FilterType;
+use crate::metadata;
+use image::{DynamicImage, ImageFormat};
+use maya_common::error::Result;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// 超出尺寸限制时的缩放方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum Fit {
+    /// 等比缩小到完全放进限制框内
+    #[default]
+    Contain,
+    /// 等比缩小到铺满限制框，再居中裁掉多余部分
+    Cover,
+}
+
+impl FromStr for Fit {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "contain" => Ok(Fit::Contain),
+            "cover" => Ok(Fit::Cover),
+            _ => Err(format!("不支持的缩放方式: {}（可选 contain、cover）", s)),
+        }
+    }
+}
+
+/// 尺寸限制
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ResizeOptions {
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    pub fit: Fit,
+}
+
+impl ResizeOptions {
+    /// 是否设置了尺寸限制
+    pub fn is_enabled(&self) -> bool {
+        self.max_width.is_some() || self.max_height.is_some()
+    }
+}
+
+/// 一张图片调整前后的尺寸
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resized {
+    pub from: (u32, u32),
+    pub to: (u32, u32),
+}
+
+/// 计算缩放后的尺寸和最终裁剪尺寸，不需要调整时返回 `None`
+///
+/// # 返回
+/// * `Option<((u32, u32), (u32, u32))>` - (缩放尺寸, 裁剪后尺寸)
+fn plan(width: u32, height: u32, options: &ResizeOptions) -> Option<((u32, u32), (u32, u32))> {
+    let box_width = options.max_width.unwrap_or(u32::MAX).max(1);
+    let box_height = options.max_height.unwrap_or(u32::MAX).max(1);
+    if width <= box_width && height <= box_height {
+        return None;
+    }
+
+    let scale_x = box_width as f64 / width as f64;
+    let scale_y = box_height as f64 / height as f64;
+    // 只限制了一个方向时，cover 和 contain 结果相同
+    let scale = match options.fit {
+        Fit::Cover if options.max_width.is_some() && options.max_height.is_some() => scale_x.max(scale_y),
+        _ => scale_x.min(scale_y),
+    }
+    .min(1.0); // 不放大
+
+    let scaled = (
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    );
+    let cropped = (scaled.0.min(box_width), scaled.1.min(box_height));
+    Some((scaled, cropped))
+}
+
+/// 按尺寸限制缩小图片，使用 Lanczos3 滤波保证缩小后的清晰度
+///
+/// 图片没有超出限制时原样返回，`Resized` 为 `None`
+pub(crate) fn resize_image(img: DynamicImage, options: &ResizeOptions) -> (DynamicImage, Option<Resized>) {
+    let from = (img.width(), img.height());
+    let Some((scaled, cropped)) = plan(from.0, from.1, options) else {
+        return (img, None);
+    };
+
+    let mut resized = if scaled == from {
+        img
+    } else {
+        img.resize_exact(scaled.0, scaled.1, FilterType::Lanczos3)
+    };
+    if cropped != scaled {
+        let x = (scaled.0 - cropped.0) / 2;
+        let y = (scaled.1 - cropped.1) / 2;
+        resized = resized.crop_imm(x, y, cropped.0, cropped.1);
+    }
+    (resized, Some(Resized { from, to: cropped }))
+}
+
+/// 先只读取图片头判断是否超出限制，超出时才解码、按 EXIF 方向摆正并缩小
+///
+/// # 返回
+/// * `Result<Option<(DynamicImage, Resized)>>` - 不需要调整时返回 `None`
+pub(crate) fn resize_encoded(
+    data: &[u8],
+    format: ImageFormat,
+    options: &ResizeOptions,
+) -> Result<Option<(DynamicImage, Resized)>> {
+    if !options.is_enabled() {
+        return Ok(None);
+    }
+    let (width, height) = metadata::oriented_dimensions(data, format)?;
+    if plan(width, height, options).is_none() {
+        return Ok(None);
+    }
+
+    let img = metadata::decode_oriented(data, format)?;
+    match resize_image(img, options) {
+        (img, Some(resized)) => Ok(Some((img, resized))),
+        (_, None) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(max_width: Option<u32>, max_height: Option<u32>, fit: Fit) -> ResizeOptions {
+        ResizeOptions { max_width, max_height, fit }
+    }
+
+    #[test]
+    fn test_plan_contain() {
+        let contain = options(Some(1920), Some(1920), Fit::Contain);
+        assert_eq!(plan(6000, 4000, &contain), Some(((1920, 1280), (1920, 1280))));
+        assert_eq!(plan(4000, 6000, &contain), Some(((1280, 1920), (1280, 1920))));
+        assert_eq!(plan(1920, 1080, &contain), None);
+        // 只限制高度
+        assert_eq!(plan(3000, 2000, &options(None, Some(1000), Fit::Contain)), Some(((1500, 1000), (1500, 1000))));
+    }
+
+    #[test]
+    fn test_plan_cover_never_upscales() {
+        let cover = options(Some(800), Some(800), Fit::Cover);
+        assert_eq!(plan(1600, 1000, &cover), Some(((1280, 800), (800, 800))));
+        // 高度不足时不放大，只裁掉超出的宽度
+        assert_eq!(plan(1200, 600, &cover), Some(((1200, 600), (800, 600))));
+    }
+
+    #[test]
+    fn test_resize_image() {
+        let img = DynamicImage::new_rgb8(300, 200);
+        let (resized, info) = resize_image(img, &options(Some(150), None, Fit::Contain));
+        assert_eq!((resized.width(), resized.height()), (150, 100));
+        assert_eq!(info, Some(Resized { from: (300, 200), to: (150, 100) }));
+
+        let (small, info) = resize_image(DynamicImage::new_rgb8(10, 10), &options(Some(150), None, Fit::Cover));
+        assert_eq!(small.width(), 10);
+        assert!(info.is_none());
+        assert!("COVER".parse::<Fit>().is_ok());
+    }
+}
@@ -0,0 +1,387 @@
+use crate::metadata;
+use crate::{convert, placeholder, resize, write_new_file, CompressOptions, CompressSummary, FileOutcome, ImageType};
+use image::{DynamicImage, ImageFormat};
+use maya_common::atomic::{write_atomic, AtomicWriteOptions};
+use maya_common::error::{Error, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 响应式图片清单文件名
+pub const RESPONSIVE_MANIFEST: &str = "responsive-manifest.json";
+/// `<picture>` 代码片段文件名
+pub const RESPONSIVE_SNIPPETS: &str = "responsive-snippets.html";
+/// 默认的 `sizes` 属性
+pub const DEFAULT_SIZES: &str = "100vw";
+
+/// 响应式图片选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponsiveOptions {
+    /// 要生成的宽度，超过原图宽度的会被忽略
+    pub widths: Vec<u32>,
+    /// 额外生成的现代格式（WebP/AVIF），按 `<source>` 顺序排列；原格式总是作为兜底生成
+    pub formats: Vec<ImageType>,
+    /// `<picture>` 中使用的 `sizes` 属性
+    pub sizes: String,
+}
+
+impl Default for ResponsiveOptions {
+    fn default() -> Self {
+        Self {
+            widths: Vec::new(),
+            formats: vec![ImageType::Webp],
+            sizes: DEFAULT_SIZES.to_string(),
+        }
+    }
+}
+
+/// 解析宽度列表，例如 `320,640,1280`
+pub fn parse_widths(s: &str) -> std::result::Result<Vec<u32>, String> {
+    let mut widths = s
+        .split(',')
+        .map(|w| match w.trim().parse::<u32>() {
+            Ok(width) if width > 0 => Ok(width),
+            _ => Err(format!("无效的宽度: {}（示例: 320,640,1280）", w.trim())),
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    widths.sort_unstable();
+    widths.dedup();
+    Ok(widths)
+}
+
+/// 一个尺寸的图片文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub size: u64,
+}
+
+/// 同一格式的全部尺寸，按宽度从小到大排列
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantSet {
+    /// 扩展名，例如 `avif`、`jpg`
+    pub extension: &'static str,
+    /// MIME 类型，例如 `image/avif`
+    pub mime: &'static str,
+    pub variants: Vec<Variant>,
+}
+
+/// 一张原图生成的响应式图片
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponsiveImage {
+    /// 原图（按 EXIF 方向摆正后）的尺寸
+    pub width: u32,
+    pub height: u32,
+    /// 现代格式在前，原格式的兜底图片在最后
+    pub sets: Vec<VariantSet>,
+}
+
+impl ResponsiveImage {
+    /// 兜底格式中最大的图片，用作 `<img src>`
+    fn fallback(&self) -> Option<&Variant> {
+        self.sets.last().and_then(|set| set.variants.last())
+    }
+}
+
+/// 为一张图片生成各个宽度和格式的文件，写在 `output_path` 所在目录，命名为 `<stem>-<宽度>w.<扩展名>`
+///
+/// 原图保持不变，生成的图片不带元数据
+pub(crate) fn generate(image_path: &Path, output_path: &Path, options: &CompressOptions) -> Result<FileOutcome> {
+    let responsive = options.responsive.as_ref().ok_or_else(|| Error::other("未启用响应式图片"))?;
+    let original = fs::read(image_path)?;
+    let format = ImageFormat::from_path(image_path)
+        .map_err(|e| Error::compression(format!("无法识别图片格式: {}", e)))?;
+    let img = metadata::decode_oriented(&original, format)?;
+    let (width, height) = (img.width(), img.height());
+    let placeholder = placeholder::for_image(&img, options)?;
+
+    let mut widths: Vec<u32> = responsive.widths.iter().copied().filter(|&w| w <= width).collect();
+    if widths.is_empty() {
+        // 原图比所有宽度都小时只生成原尺寸，不放大
+        widths.push(width);
+    }
+    let resized: Vec<DynamicImage> = widths
+        .iter()
+        .map(|&w| {
+            let limit = resize::ResizeOptions { max_width: Some(w), ..Default::default() };
+            resize::resize_image(img.clone(), &limit).0
+        })
+        .collect();
+
+    let fallback_extension = if format == ImageFormat::Png { "png" } else { source_extension(image_path) };
+    let mut sets = Vec::new();
+    for target in &responsive.formats {
+        let extension = target.extensions()[0];
+        let variants = write_variants(output_path, extension, &resized, |img| {
+            convert::encode_target(img, target, options).map(|encoded| encoded.data)
+        })?;
+        sets.push(VariantSet { extension, mime: mime(extension), variants });
+    }
+    let variants =
+        write_variants(output_path, fallback_extension, &resized, |img| convert::encode_raster(img, format, options))?;
+    sets.push(VariantSet { extension: fallback_extension, mime: mime(fallback_extension), variants });
+
+    let image = ResponsiveImage { width, height, sets };
+    let fallback = image.fallback().cloned().ok_or_else(|| Error::compression("没有生成任何图片"))?;
+    Ok(FileOutcome {
+        ratio: 1.0 - fallback.size as f64 / original.len() as f64,
+        original_size: original.len() as u64,
+        output_size: fallback.size,
+        format: if format == ImageFormat::Png { "png" } else { "jpeg" },
+        dimensions: Some((fallback.width, fallback.height)),
+        responsive: Some(image),
+        placeholder,
+        ..Default::default()
+    })
+}
+
+/// 编码并写出同一格式的全部尺寸
+fn write_variants<F>(output_path: &Path, extension: &str, images: &[DynamicImage], encode: F) -> Result<Vec<Variant>>
+where
+    F: Fn(&DynamicImage) -> Result<Vec<u8>>,
+{
+    let stem = output_path.file_stem().unwrap_or_default().to_string_lossy();
+    images
+        .iter()
+        .map(|img| {
+            let data = encode(img)?;
+            let path = output_path.with_file_name(format!("{}-{}w.{}", stem, img.width(), extension));
+            write_new_file(&path, &data)?;
+            Ok(Variant { path, width: img.width(), height: img.height(), size: data.len() as u64 })
+        })
+        .collect()
+}
+
+/// 原图的扩展名（保持 jpg/jpeg 的写法）
+fn source_extension(path: &Path) -> &'static str {
+    match path.extension().map(|e| e.to_string_lossy().to_lowercase()).as_deref() {
+        Some("jpeg") => "jpeg",
+        _ => "jpg",
+    }
+}
+
+fn mime(extension: &str) -> &'static str {
+    match extension {
+        "avif" => "image/avif",
+        "webp" => "image/webp",
+        "png" => "image/png",
+        _ => "image/jpeg",
+    }
+}
+
+/// 是否为生成的 `<stem>-<宽度>w.<扩展名>` 文件（同目录下存在对应的原图）
+pub(crate) fn is_generated_variant(path: &Path) -> bool {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let Some((base, width)) = stem.rsplit_once('-') else {
+        return false;
+    };
+    let Some(digits) = width.strip_suffix('w') else {
+        return false;
+    };
+    if base.is_empty() || digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    ImageType::All
+        .extensions()
+        .iter()
+        .any(|extension| path.with_file_name(format!("{}.{}", base, extension)).is_file())
+}
+
+/// 清单中的一个文件
+#[derive(Debug, Serialize)]
+struct ManifestFile {
+    path: String,
+    width: u32,
+    height: u32,
+    size: u64,
+}
+
+/// 清单中的一种格式
+#[derive(Debug, Serialize)]
+struct ManifestSource {
+    #[serde(rename = "type")]
+    mime: &'static str,
+    srcset: Vec<ManifestFile>,
+}
+
+/// 清单中的一张原图
+#[derive(Debug, Serialize)]
+struct ManifestEntry {
+    width: u32,
+    height: u32,
+    sources: Vec<ManifestSource>,
+    /// `<img src>` 使用的兜底图片
+    fallback: String,
+    html: String,
+}
+
+/// 写出 JSON 清单和 `<picture>` 代码片段，清单以相对于源目录的原图路径为键
+///
+/// 文件保存在输出目录中，没有设置输出目录时保存在源目录中；
+/// 清单和片段中的图片路径相对于保存目录
+pub(crate) fn write_manifest(root: &Path, options: &CompressOptions, summary: &CompressSummary) -> Result<PathBuf> {
+    let sizes = options.responsive.as_ref().map_or(DEFAULT_SIZES, |r| r.sizes.as_str());
+    let base = options.out_dir.as_deref().unwrap_or(root);
+    let relative = |path: &Path| path.strip_prefix(base).unwrap_or(path).to_string_lossy().replace('\\', "/");
+
+    let mut manifest = BTreeMap::new();
+    let mut snippets = String::new();
+    for (source, outcome) in summary.outcomes() {
+        let Some(image) = &outcome.responsive else {
+            continue;
+        };
+        let key = source.strip_prefix(root).unwrap_or(source).to_string_lossy().replace('\\', "/");
+        let sources: Vec<ManifestSource> = image
+            .sets
+            .iter()
+            .map(|set| ManifestSource {
+                mime: set.mime,
+                srcset: set
+                    .variants
+                    .iter()
+                    .map(|v| ManifestFile { path: relative(&v.path), width: v.width, height: v.height, size: v.size })
+                    .collect(),
+            })
+            .collect();
+        let Some(fallback) = image.fallback() else {
+            continue;
+        };
+        let html = picture_html(&sources, fallback, &relative(&fallback.path), sizes);
+        snippets.push_str(&format!("<!-- {} -->\n{}\n", key, html));
+        manifest.insert(
+            key,
+            ManifestEntry { width: image.width, height: image.height, sources, fallback: relative(&fallback.path), html },
+        );
+    }
+
+    fs::create_dir_all(base)?;
+    let content = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| Error::other(format!("响应式图片清单序列化失败: {}", e)))?;
+    let manifest_path = base.join(RESPONSIVE_MANIFEST);
+    write_atomic(&manifest_path, content.as_bytes(), &AtomicWriteOptions::default())?;
+    write_atomic(&base.join(RESPONSIVE_SNIPPETS), snippets.as_bytes(), &AtomicWriteOptions::default())?;
+    Ok(manifest_path)
+}
+
+/// 生成 `<picture>` 代码片段，兜底格式放在 `<img>` 上
+fn picture_html(sources: &[ManifestSource], fallback: &Variant, fallback_path: &str, sizes: &str) -> String {
+    let srcset = |source: &ManifestSource| {
+        source
+            .srcset
+            .iter()
+            .map(|file| format!("{} {}w", html_attr(&file.path), file.width))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut html = String::from("<picture>\n");
+    let (img_source, modern) = sources.split_last().expect("至少包含兜底格式");
+    for source in modern {
+        html.push_str(&format!(
+            "  <source type=\"{}\" srcset=\"{}\" sizes=\"{}\">\n",
+            source.mime,
+            srcset(source),
+            html_attr(sizes)
+        ));
+    }
+    html.push_str(&format!(
+        "  <img src=\"{}\" srcset=\"{}\" sizes=\"{}\" width=\"{}\" height=\"{}\" alt=\"\" loading=\"lazy\" decoding=\"async\">\n",
+        html_attr(fallback_path),
+        srcset(img_source),
+        html_attr(sizes),
+        fallback.width,
+        fallback.height
+    ));
+    html.push_str("</picture>");
+    html
+}
+
+fn html_attr(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compress_images;
+    use image::{ImageBuffer, Rgb};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_widths() {
+        assert_eq!(parse_widths("1280, 320,640,320").unwrap(), vec![320, 640, 1280]);
+        assert!(parse_widths("320,abc").is_err());
+        assert!(parse_widths("0").is_err());
+    }
+
+    #[test]
+    fn test_generate_variants_manifest_and_snippets() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("src");
+        fs::create_dir_all(source.join("img")).unwrap();
+        let img: ImageBuffer<Rgb<u8>, _> =
+            ImageBuffer::from_fn(800, 400, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8]));
+        img.save(source.join("img").join("hero.jpg")).unwrap();
+
+        let out_dir = temp_dir.path().join("dist");
+        let options = CompressOptions {
+            out_dir: Some(out_dir.clone()),
+            responsive: Some(ResponsiveOptions { widths: vec![320, 640, 1920], ..Default::default() }),
+            ..Default::default()
+        };
+        let summary = compress_images(&source, ImageType::All, &options).unwrap();
+        assert_eq!(summary.successful(), 1);
+
+        // 1920 超过原图宽度，不会放大
+        for name in ["hero-320w.webp", "hero-640w.webp", "hero-320w.jpg", "hero-640w.jpg"] {
+            assert!(out_dir.join("img").join(name).is_file(), "{}", name);
+        }
+        assert!(!out_dir.join("img").join("hero-1920w.jpg").exists());
+        assert_eq!(image::image_dimensions(out_dir.join("img").join("hero-320w.jpg")).unwrap(), (320, 160));
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(out_dir.join(RESPONSIVE_MANIFEST)).unwrap()).unwrap();
+        let entry = &manifest["img/hero.jpg"];
+        assert_eq!(entry["width"], 800);
+        assert_eq!(entry["sources"][0]["type"], "image/webp");
+        assert_eq!(entry["fallback"], "img/hero-640w.jpg");
+        let html = entry["html"].as_str().unwrap();
+        assert!(html.contains("<source type=\"image/webp\" srcset=\"img/hero-320w.webp 320w, img/hero-640w.webp 640w\""));
+        assert!(html.contains("<img src=\"img/hero-640w.jpg\""));
+        assert!(fs::read_to_string(out_dir.join(RESPONSIVE_SNIPPETS)).unwrap().contains("<!-- img/hero.jpg -->"));
+
+        // 生成在原图旁边的文件不会在下一次运行时被当作原图
+        fs::copy(out_dir.join("img").join("hero-320w.jpg"), source.join("img").join("hero-320w.jpg")).unwrap();
+        assert!(is_generated_variant(&source.join("img").join("hero-320w.jpg")));
+        assert!(!is_generated_variant(Path::new("icon-32w.png")));
+    }
+
+    #[test]
+    fn test_rejects_colliding_stems() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let img: ImageBuffer<Rgb<u8>, _> = ImageBuffer::from_fn(400, 200, |x, y| Rgb([x as u8, y as u8, 64]));
+        img.save(root.join("hero.jpg")).unwrap();
+        img.save(root.join("hero.png")).unwrap();
+
+        // 两张图片的 WebP 尺寸文件都会命名为 hero-320w.webp
+        let options = CompressOptions {
+            responsive: Some(ResponsiveOptions { widths: vec![320], ..Default::default() }),
+            ..Default::default()
+        };
+        let error = compress_images(root, ImageType::All, &options).unwrap_err();
+        assert!(error.to_string().contains("hero.jpg"));
+        assert!(!root.join("hero-320w.webp").exists());
+
+        // 只生成原格式时文件名不会冲突
+        let options = CompressOptions {
+            responsive: Some(ResponsiveOptions { widths: vec![320], formats: Vec::new(), ..Default::default() }),
+            ..Default::default()
+        };
+        assert_eq!(compress_images(root, ImageType::All, &options).unwrap().successful(), 2);
+        assert!(root.join("hero-320w.jpg").is_file() && root.join("hero-320w.png").is_file());
+    }
+}
\ No newline at end of file
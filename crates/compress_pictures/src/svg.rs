@@ -0,0 +1,933 @@
+use crate::{write_output, CompressOptions, FileOutcome};
+use maya_common::error::{Error, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// 路径坐标默认保留的小数位数
+pub const DEFAULT_SVG_PRECISION: u8 = 3;
+
+/// 编辑器写入的命名空间，对应的元素和属性在浏览器中没有作用
+const EDITOR_NAMESPACES: &[&str] = &[
+    "http://inkscape.sourceforge.net/DTD/sodipodi-0.dtd",
+    "http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd",
+    "http://www.inkscape.org/namespaces/inkscape",
+    "http://www.bohemiancoding.com/sketch/ns",
+    "http://www.figma.com/figma/ns",
+    "http://www.serif.com/",
+    "http://www.vector.evaxdesign.sk",
+    "http://schemas.microsoft.com/visio/2003/SVGExtensions/",
+    "http://taptrix.com/vectorillustrator/svg_extensions",
+    "http://ns.adobe.com/AdobeIllustrator/10.0/",
+    "http://ns.adobe.com/AdobeSVGViewerExtensions/3.0/",
+    "http://ns.adobe.com/Extensibility/1.0/",
+    "http://ns.adobe.com/Flows/1.0/",
+    "http://ns.adobe.com/GenericCustomNamespace/1.0/",
+    "http://ns.adobe.com/Graphs/1.0/",
+    "http://ns.adobe.com/ImageReplacement/1.0/",
+    "http://ns.adobe.com/SaveForWeb/1.0/",
+    "http://ns.adobe.com/Variables/1.0/",
+    "http://ns.adobe.com/XPath/1.0/",
+    "http://purl.org/dc/elements/1.1/",
+    "http://creativecommons.org/ns#",
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#",
+];
+
+/// 可以从只有一个子元素的 `<g>` 移到子元素上的继承属性
+const INHERITED_ATTRIBUTES: &[&str] = &[
+    "clip-rule",
+    "color",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-weight",
+    "stroke",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-opacity",
+    "stroke-width",
+    "visibility",
+];
+
+/// 文本内容中的空白有意义，不能删除
+const TEXT_ELEMENTS: &[&str] = &["text", "tspan", "textPath", "title", "desc", "style", "script"];
+
+/// 使用 ID 做跳转或动画的元素，存在时保留所有 ID
+const ID_DEPENDENT_ELEMENTS: &[&str] = &["script", "animate", "animateMotion", "animateTransform", "set"];
+
+/// SVG 压缩选项
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SvgOptions {
+    /// 路径坐标保留的小数位数
+    pub precision: u8,
+    /// 保留和 width/height 相同的 viewBox，便于用 CSS 缩放
+    pub keep_viewbox: bool,
+    /// 保留所有 ID，即使文件内没有引用（可能被外部 CSS、脚本或 `sprite.svg#id` 使用）
+    pub keep_ids: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self { precision: DEFAULT_SVG_PRECISION, keep_viewbox: false, keep_ids: false }
+    }
+}
+
+/// 解析后的节点；文本保持转义后的原样
+#[derive(Debug, Clone)]
+enum Node {
+    Element(Element),
+    Text(String),
+    CData(String),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Element {
+    name: String,
+    /// 属性值为反转义后的内容
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    fn remove_attribute(&mut self, name: &str) -> Option<String> {
+        let index = self.attributes.iter().position(|(key, _)| key == name)?;
+        Some(self.attributes.remove(index).1)
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    fn elements_mut(&mut self) -> impl Iterator<Item = &mut Element> {
+        self.children.iter_mut().filter_map(|child| match child {
+            Node::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /// 深度优先遍历自身和所有子元素
+    fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a Element)) {
+        visit(self);
+        for child in self.elements() {
+            child.walk(visit);
+        }
+    }
+
+    fn walk_mut(&mut self, visit: &mut impl FnMut(&mut Element)) {
+        visit(self);
+        for child in self.elements_mut() {
+            child.walk_mut(visit);
+        }
+    }
+}
+
+/// 压缩单个 SVG 文件，没有变小时保留原文件
+pub(crate) fn compress_svg(image_path: &Path, output_path: &Path, options: &CompressOptions) -> Result<FileOutcome> {
+    let input = fs::read(image_path)?;
+    let data = minify_svg(&input, &options.svg)?;
+    write_output(image_path, output_path, &input, &data, None, "svg", options)
+}
+
+/// 压缩 SVG 内容
+///
+/// * 删除 XML 声明、DOCTYPE、注释、`<metadata>` 和编辑器命名空间中的元素和属性
+/// * 删除标签之间的空白，文本元素中的空白保持不变
+/// * 展开没有属性的 `<g>`，把只有一个子元素的 `<g>` 的继承属性移到子元素上，删除空的分组
+///   （`<style>` 中有依赖元素层级的选择器时不处理分组）
+/// * 按精度四舍五入路径坐标并去掉多余的分隔符
+/// * 压缩 `<style>` 和 `style` 属性中的 CSS
+/// * 删除文件内没有引用的 ID 和与 width/height 相同的 viewBox（可以通过选项保留）
+pub(crate) fn minify_svg(data: &[u8], options: &SvgOptions) -> Result<Vec<u8>> {
+    let mut root = parse(data)?;
+    remove_editor_data(&mut root);
+    remove_whitespace(&mut root, false);
+    if !has_structural_selectors(&root) {
+        collapse_groups(&mut root);
+    }
+    if !options.keep_ids {
+        remove_unreferenced_ids(&mut root);
+    }
+    root.walk_mut(&mut |element| minify_element(element, options.precision));
+    if !options.keep_viewbox {
+        remove_redundant_viewbox(&mut root);
+    }
+    remove_unused_namespaces(&mut root);
+
+    let mut output = String::with_capacity(data.len());
+    write_element(&root, &mut output);
+    Ok(output.into_bytes())
+}
+
+fn parse(data: &[u8]) -> Result<Element> {
+    let text = std::str::from_utf8(data).map_err(|_| Error::compression("SVG 不是 UTF-8 编码"))?;
+    let mut reader = Reader::from_str(text.trim_start_matches('\u{feff}'));
+    // 栈底是文档本身，只用来收集根元素
+    let mut stack = vec![Element::default()];
+
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(start) => stack.push(element(&start)?),
+            Event::Empty(start) => push_node(&mut stack, Node::Element(element(&start)?)),
+            Event::End(_) => {
+                let element = stack.pop().filter(|_| !stack.is_empty()).ok_or_else(|| Error::compression("SVG 标签不匹配"))?;
+                push_node(&mut stack, Node::Element(element));
+            }
+            Event::Text(text) => push_text(&mut stack, &text.decode().map_err(xml_error)?),
+            Event::GeneralRef(reference) => {
+                push_text(&mut stack, &format!("&{};", reference.decode().map_err(xml_error)?))
+            }
+            Event::CData(cdata) => push_node(&mut stack, Node::CData(cdata.decode().map_err(xml_error)?.into_owned())),
+            Event::DocType(doctype) => {
+                // 自定义实体在删除 DOCTYPE 后无法解析
+                if doctype.decode().map_err(xml_error)?.contains("<!ENTITY") {
+                    return Err(Error::compression("SVG 中定义了实体，不做压缩"));
+                }
+            }
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) => {}
+            Event::Eof => break,
+        }
+    }
+
+    let document = match stack.pop() {
+        Some(document) if stack.is_empty() => document,
+        _ => return Err(Error::compression("SVG 标签没有闭合")),
+    };
+    document
+        .children
+        .into_iter()
+        .find_map(|node| match node {
+            Node::Element(element) if local_name(&element.name) == "svg" => Some(element),
+            _ => None,
+        })
+        .ok_or_else(|| Error::compression("没有找到 <svg> 根元素"))
+}
+
+fn element(start: &BytesStart<'_>) -> Result<Element> {
+    let mut attributes = Vec::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(|e| Error::compression(format!("SVG 属性解析失败: {}", e)))?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+        let value = attribute.unescape_value().map_err(xml_error)?.into_owned();
+        attributes.push((key, value));
+    }
+    Ok(Element { name: String::from_utf8_lossy(start.name().as_ref()).into_owned(), attributes, children: Vec::new() })
+}
+
+fn push_node(stack: &mut [Element], node: Node) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(node);
+    }
+}
+
+/// 实体引用单独出现，和相邻文本合并为一个节点
+fn push_text(stack: &mut [Element], text: &str) {
+    let Some(parent) = stack.last_mut() else {
+        return;
+    };
+    match parent.children.last_mut() {
+        Some(Node::Text(previous)) => previous.push_str(text),
+        _ => parent.children.push(Node::Text(text.to_string())),
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn prefix(name: &str) -> Option<&str> {
+    name.split_once(':').map(|(prefix, _)| prefix)
+}
+
+/// 删除 `<metadata>` 和编辑器命名空间中的元素、属性及命名空间声明
+fn remove_editor_data(root: &mut Element) {
+    let mut prefixes = HashSet::new();
+    root.walk(&mut |element| {
+        for (key, value) in &element.attributes {
+            if let Some(declared) = key.strip_prefix("xmlns:") {
+                if EDITOR_NAMESPACES.contains(&value.as_str()) {
+                    prefixes.insert(declared.to_string());
+                }
+            }
+        }
+    });
+    let is_editor = |name: &str| prefix(name).is_some_and(|p| prefixes.contains(p));
+
+    root.walk_mut(&mut |element| {
+        element.attributes.retain(|(key, _)| {
+            let declared = key.strip_prefix("xmlns:").is_some_and(|p| prefixes.contains(p));
+            !declared && !is_editor(key)
+        });
+        element.children.retain(|child| match child {
+            Node::Element(child) => child.name != "metadata" && !is_editor(&child.name),
+            _ => true,
+        });
+    });
+}
+
+/// 删除标签之间只有空白的文本，文本元素和 `xml:space="preserve"` 中的内容保持不变
+fn remove_whitespace(element: &mut Element, preserve: bool) {
+    let preserve = match element.attribute("xml:space") {
+        Some(space) => space == "preserve",
+        None => preserve || TEXT_ELEMENTS.contains(&local_name(&element.name)),
+    };
+    if !preserve {
+        element.children.retain(|child| !matches!(child, Node::Text(text) if text.trim().is_empty()));
+    }
+    for child in element.elements_mut() {
+        remove_whitespace(child, preserve);
+    }
+}
+
+/// 展开和合并分组
+///
+/// `<switch>` 只渲染第一个满足条件的直接子元素，其中的分组不做处理
+fn collapse_groups(element: &mut Element) {
+    for child in element.elements_mut() {
+        collapse_groups(child);
+    }
+    if element.name == "switch" {
+        return;
+    }
+
+    let mut children = Vec::with_capacity(element.children.len());
+    for child in std::mem::take(&mut element.children) {
+        match child {
+            Node::Element(group) if group.name == "g" => {
+                if group.attributes.is_empty() {
+                    children.extend(group.children);
+                } else if group.children.is_empty() && group.attribute("id").is_none() {
+                    // 空分组不会渲染任何内容
+                } else {
+                    children.push(Node::Element(merge_single_child(&group).unwrap_or(group)));
+                }
+            }
+            child => children.push(child),
+        }
+    }
+    element.children = children;
+}
+
+/// `<style>` 中是否有展开分组后匹配结果可能改变的选择器：
+/// 子元素、相邻兄弟等组合符，`*`、`g` 类型选择器和 `:nth-child` 等结构伪类
+fn has_structural_selectors(root: &Element) -> bool {
+    let mut found = false;
+    root.walk(&mut |element| {
+        if element.name != "style" {
+            return;
+        }
+        for child in &element.children {
+            if let Node::Text(css) | Node::CData(css) = child {
+                found |= css_selectors(&minify_css(css)).any(is_structural_selector);
+            }
+        }
+    });
+    found
+}
+
+/// 规则中 `{` 前面的选择器，跳过 `@media` 等 at 规则本身
+fn css_selectors(css: &str) -> impl Iterator<Item = &str> {
+    css.split('}')
+        .flat_map(|block| {
+            let mut parts: Vec<&str> = block.split('{').collect();
+            parts.pop();
+            parts
+        })
+        .filter(|prelude| !prelude.starts_with('@'))
+        .flat_map(|prelude| prelude.split(','))
+}
+
+fn is_structural_selector(selector: &str) -> bool {
+    const PSEUDO_CLASSES: &[&str] = &[":nth-", ":first-", ":last-", ":only-", ":empty", ":has("];
+    if selector.contains(['>', '+', '~', '*']) || PSEUDO_CLASSES.iter().any(|pseudo| selector.contains(pseudo)) {
+        return true;
+    }
+    selector.split_whitespace().any(|compound| {
+        let end = compound.find(['.', '#', ':', '[']).unwrap_or(compound.len());
+        compound[..end].eq_ignore_ascii_case("g")
+    })
+}
+
+/// 分组只有一个子元素且只有可继承的属性时，把属性移到子元素上并去掉分组
+fn merge_single_child(group: &Element) -> Option<Element> {
+    let [Node::Element(child)] = group.children.as_slice() else {
+        return None;
+    };
+    let movable = group.attributes.iter().all(|(key, _)| {
+        (INHERITED_ATTRIBUTES.contains(&key.as_str()) && child.attribute(key).is_none()) || key == "transform"
+    });
+    if !movable {
+        return None;
+    }
+
+    let mut child = child.clone();
+    for (key, value) in &group.attributes {
+        if key == "transform" {
+            // 分组的变换在外层，先于子元素自身的变换
+            let combined = match child.remove_attribute("transform") {
+                Some(own) => format!("{} {}", value, own),
+                None => value.clone(),
+            };
+            child.attributes.push((key.clone(), combined));
+        } else {
+            child.attributes.push((key.clone(), value.clone()));
+        }
+    }
+    Some(child)
+}
+
+/// 删除文件内没有引用的 ID，以及 `<defs>` 中带 ID 但没有被引用的定义
+///
+/// `<symbol>` 和 `<view>` 的 ID 通常从外部引用（`sprite.svg#icon`），总是保留；
+/// 文件中有脚本或动画时所有 ID 都可能被用到，全部保留
+fn remove_unreferenced_ids(root: &mut Element) {
+    let mut referenced = HashSet::new();
+    let mut dependent = false;
+    root.walk(&mut |element| {
+        dependent |= ID_DEPENDENT_ELEMENTS.contains(&local_name(&element.name));
+        for (key, value) in &element.attributes {
+            collect_url_references(value, &mut referenced);
+            match key.as_str() {
+                "href" | "xlink:href" => {
+                    if let Some(id) = value.strip_prefix('#') {
+                        referenced.insert(id.to_string());
+                    }
+                }
+                "aria-labelledby" | "aria-describedby" => {
+                    referenced.extend(value.split_whitespace().map(str::to_string));
+                }
+                _ => {}
+            }
+        }
+        if element.name == "style" {
+            for child in &element.children {
+                if let Node::Text(css) | Node::CData(css) = child {
+                    collect_css_references(css, &mut referenced);
+                }
+            }
+        }
+    });
+    if dependent {
+        return;
+    }
+
+    root.walk_mut(&mut |element| {
+        for defs in element.elements_mut().filter(|child| child.name == "defs") {
+            // 定义中的分组可能包着被引用的渐变等元素，只要有一个后代被引用就保留
+            defs.children.retain(|child| match child {
+                Node::Element(child) => {
+                    let mut used = child.attribute("id").is_none();
+                    child.walk(&mut |element| {
+                        used |= element.attribute("id").is_some_and(|id| referenced.contains(id));
+                    });
+                    used
+                }
+                _ => true,
+            });
+        }
+        element.children.retain(
+            |child| !matches!(child, Node::Element(defs) if defs.name == "defs" && defs.children.is_empty()),
+        );
+        if matches!(element.name.as_str(), "symbol" | "view") {
+            return;
+        }
+        if element.attribute("id").is_some_and(|id| !referenced.contains(id)) {
+            element.remove_attribute("id");
+        }
+    });
+}
+
+/// 收集 `url(#id)` 中的 ID
+fn collect_url_references(value: &str, referenced: &mut HashSet<String>) {
+    let mut rest = value;
+    while let Some(start) = rest.find("url(") {
+        rest = &rest[start + 4..];
+        let end = rest.find(')').unwrap_or(rest.len());
+        let id = rest[..end].trim().trim_matches(|c| c == '"' || c == '\'');
+        if let Some(id) = id.strip_prefix('#') {
+            referenced.insert(id.to_string());
+        }
+        rest = &rest[end..];
+    }
+}
+
+/// CSS 中的 `#name` 可能是 ID 选择器，全部视为引用（颜色值只会多保留几个 ID）
+fn collect_css_references(css: &str, referenced: &mut HashSet<String>) {
+    collect_url_references(css, referenced);
+    for (index, _) in css.match_indices('#') {
+        let id: String = css[index + 1..]
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        if !id.is_empty() {
+            referenced.insert(id);
+        }
+    }
+}
+
+/// 压缩单个元素的路径、坐标列表和样式
+fn minify_element(element: &mut Element, precision: u8) {
+    let name = local_name(&element.name).to_string();
+    for (key, value) in &mut element.attributes {
+        let minified = match (name.as_str(), key.as_str()) {
+            ("path", "d") => minify_path(value, precision),
+            ("polygon" | "polyline", "points") => minify_points(value, precision),
+            (_, "style") => Some(minify_css(value).trim_end_matches(';').to_string()),
+            _ => None,
+        };
+        if let Some(minified) = minified {
+            *value = minified;
+        }
+    }
+    if name == "style" {
+        for child in &mut element.children {
+            if let Node::Text(css) | Node::CData(css) = child {
+                *css = minify_css(css);
+            }
+        }
+    }
+}
+
+/// 根元素的 viewBox 与 width/height 完全一致时可以省略
+fn remove_redundant_viewbox(root: &mut Element) {
+    let (Some(view_box), Some(width), Some(height)) =
+        (root.attribute("viewBox"), root.attribute("width"), root.attribute("height"))
+    else {
+        return;
+    };
+    let numbers: Vec<&str> = view_box.split(|c: char| c == ',' || c.is_whitespace()).filter(|s| !s.is_empty()).collect();
+    let parse = |value: &str| value.trim_end_matches("px").parse::<f64>().ok();
+    let redundant = match numbers.as_slice() {
+        [x, y, w, h] => {
+            parse(x) == Some(0.0) && parse(y) == Some(0.0) && parse(w) == parse(width) && parse(h) == parse(height)
+        }
+        _ => false,
+    } && parse(width).is_some();
+    if redundant {
+        root.remove_attribute("viewBox");
+    }
+}
+
+/// 删除没有元素或属性使用的命名空间前缀声明
+fn remove_unused_namespaces(root: &mut Element) {
+    let mut used = HashSet::new();
+    root.walk(&mut |element| {
+        used.extend(prefix(&element.name).map(str::to_string));
+        for (key, _) in &element.attributes {
+            if let Some(prefix) = prefix(key).filter(|p| *p != "xmlns") {
+                used.insert(prefix.to_string());
+            }
+        }
+    });
+    root.walk_mut(&mut |element| {
+        element
+            .attributes
+            .retain(|(key, _)| key.strip_prefix("xmlns:").is_none_or(|prefix| used.contains(prefix)));
+    });
+}
+
+/// 路径命令的参数个数
+fn command_arity(command: char) -> Option<usize> {
+    match command.to_ascii_lowercase() {
+        'z' => Some(0),
+        'h' | 'v' => Some(1),
+        'm' | 'l' | 't' => Some(2),
+        's' | 'q' => Some(4),
+        'c' => Some(6),
+        'a' => Some(7),
+        _ => None,
+    }
+}
+
+/// 路径数据中的一个记号
+enum PathToken {
+    Command(char),
+    Number(String),
+    /// 圆弧的 large-arc/sweep 标志，只有一个字符
+    Flag(char),
+}
+
+/// 四舍五入路径坐标并去掉多余分隔符，命令和参数的顺序保持不变；无法解析时返回 `None`
+fn minify_path(d: &str, precision: u8) -> Option<String> {
+    let chars: Vec<char> = d.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    let mut command = None;
+    let mut argument = 0;
+    loop {
+        while index < chars.len() && (chars[index].is_whitespace() || chars[index] == ',') {
+            index += 1;
+        }
+        if index >= chars.len() {
+            break;
+        }
+        let c = chars[index];
+        if let Some(arity) = command_arity(c) {
+            tokens.push(PathToken::Command(c));
+            command = Some((c, arity));
+            argument = 0;
+            index += 1;
+            continue;
+        }
+        let (current, arity) = command?;
+        if arity == 0 {
+            return None;
+        }
+        if current.eq_ignore_ascii_case(&'a') && matches!(argument % arity, 3 | 4) {
+            if c != '0' && c != '1' {
+                return None;
+            }
+            tokens.push(PathToken::Flag(c));
+            index += 1;
+        } else {
+            let (value, end) = read_number(&chars, index)?;
+            tokens.push(PathToken::Number(format_number(value, precision)));
+            index = end;
+        }
+        argument += 1;
+    }
+
+    let mut output = String::with_capacity(d.len());
+    let mut previous: Option<&PathToken> = None;
+    for token in &tokens {
+        match token {
+            PathToken::Command(c) => output.push(*c),
+            PathToken::Flag(c) => {
+                if !matches!(previous, Some(PathToken::Command(_)) | None) {
+                    output.push(' ');
+                }
+                output.push(*c);
+            }
+            PathToken::Number(number) => {
+                let needs_separator = match previous {
+                    Some(PathToken::Number(before)) => needs_separator(before, number),
+                    Some(PathToken::Flag(_)) => !number.starts_with('-'),
+                    _ => false,
+                };
+                if needs_separator {
+                    output.push(' ');
+                }
+                output.push_str(number);
+            }
+        }
+        previous = Some(token);
+    }
+    Some(output)
+}
+
+/// 压缩 `points` 坐标列表；无法解析时返回 `None`
+fn minify_points(points: &str, precision: u8) -> Option<String> {
+    let chars: Vec<char> = points.chars().collect();
+    let mut numbers: Vec<String> = Vec::new();
+    let mut index = 0;
+    loop {
+        while index < chars.len() && (chars[index].is_whitespace() || chars[index] == ',') {
+            index += 1;
+        }
+        if index >= chars.len() {
+            break;
+        }
+        let (value, end) = read_number(&chars, index)?;
+        numbers.push(format_number(value, precision));
+        index = end;
+    }
+
+    let mut output = String::with_capacity(points.len());
+    for (i, number) in numbers.iter().enumerate() {
+        if i > 0 && needs_separator(&numbers[i - 1], number) {
+            output.push(' ');
+        }
+        output.push_str(number);
+    }
+    Some(output)
+}
+
+/// 读取一个数字（可带符号、小数和指数），返回数值和结束位置
+fn read_number(chars: &[char], start: usize) -> Option<(f64, usize)> {
+    let mut end = start;
+    if matches!(chars.get(end), Some('+' | '-')) {
+        end += 1;
+    }
+    let digits = |end: &mut usize| {
+        let begin = *end;
+        while chars.get(*end).is_some_and(|c| c.is_ascii_digit()) {
+            *end += 1;
+        }
+        *end > begin
+    };
+    let mut has_digits = digits(&mut end);
+    if chars.get(end) == Some(&'.') {
+        end += 1;
+        has_digits |= digits(&mut end);
+    }
+    if !has_digits {
+        return None;
+    }
+    if matches!(chars.get(end), Some('e' | 'E')) {
+        let mut exponent = end + 1;
+        if matches!(chars.get(exponent), Some('+' | '-')) {
+            exponent += 1;
+        }
+        if digits(&mut exponent) {
+            end = exponent;
+        }
+    }
+    let text: String = chars[start..end].iter().collect();
+    text.parse().ok().map(|value| (value, end))
+}
+
+/// 按精度四舍五入并使用最短写法，例如 `0.500` -> `.5`、`-0.25` -> `-.25`
+fn format_number(value: f64, precision: u8) -> String {
+    let mut text = format!("{:.*}", precision as usize, value);
+    if text.contains('.') {
+        text = text.trim_end_matches('0').trim_end_matches('.').to_string();
+    }
+    if text == "-0" {
+        return "0".to_string();
+    }
+    if let Some(rest) = text.strip_prefix("0.") {
+        format!(".{}", rest)
+    } else if let Some(rest) = text.strip_prefix("-0.") {
+        format!("-.{}", rest)
+    } else {
+        text
+    }
+}
+
+/// 后一个数字以负号开头，或前一个数字已有小数点且后一个以小数点开头时不需要分隔符
+fn needs_separator(before: &str, number: &str) -> bool {
+    !(number.starts_with('-') || (number.starts_with('.') && before.contains('.')))
+}
+
+/// 压缩 CSS：删除注释和多余空白，以及 `}` 前的分号
+///
+/// 选择器中 `:` 前的空格有意义（后代伪类），保持不变；字符串原样保留
+fn minify_css(css: &str) -> String {
+    const NO_SPACE_AFTER: &[char] = &['{', '}', ';', ',', ':', '>', '('];
+    const NO_SPACE_BEFORE: &[char] = &['{', '}', ';', ',', '>', ')', '!'];
+
+    let chars: Vec<char> = css.chars().collect();
+    let mut output = String::with_capacity(css.len());
+    let mut pending_space = false;
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        if c == '/' && chars.get(index + 1) == Some(&'*') {
+            index += 2;
+            while index < chars.len() && !(chars[index] == '*' && chars.get(index + 1) == Some(&'/')) {
+                index += 1;
+            }
+            index += 2;
+            pending_space = true;
+            continue;
+        }
+        if c.is_whitespace() {
+            pending_space = true;
+            index += 1;
+            continue;
+        }
+
+        let needless_space = output.is_empty()
+            || output.ends_with(NO_SPACE_AFTER)
+            || NO_SPACE_BEFORE.contains(&c)
+            || (c == ':' && is_declaration(&chars[index..]));
+        if pending_space && !needless_space {
+            output.push(' ');
+        }
+        pending_space = false;
+
+        if c == '"' || c == '\'' {
+            output.push(c);
+            index += 1;
+            while index < chars.len() {
+                output.push(chars[index]);
+                if chars[index] == '\\' && index + 1 < chars.len() {
+                    output.push(chars[index + 1]);
+                    index += 2;
+                    continue;
+                }
+                index += 1;
+                if chars[index - 1] == c {
+                    break;
+                }
+            }
+            continue;
+        }
+        if c == '}' && output.ends_with(';') {
+            output.pop();
+        }
+        output.push(c);
+        index += 1;
+    }
+    output
+}
+
+/// `:` 之后先遇到 `;` 或 `}` 而不是 `{` 时位于声明中，否则位于选择器中
+fn is_declaration(rest: &[char]) -> bool {
+    rest.iter().find(|c| matches!(c, '{' | ';' | '}')).is_none_or(|c| *c != '{')
+}
+
+fn write_element(element: &Element, output: &mut String) {
+    output.push('<');
+    output.push_str(&element.name);
+    for (key, value) in &element.attributes {
+        output.push(' ');
+        output.push_str(key);
+        output.push_str("=\"");
+        output.push_str(&escape_attribute(value));
+        output.push('"');
+    }
+    if element.children.is_empty() {
+        output.push_str("/>");
+        return;
+    }
+    output.push('>');
+    for child in &element.children {
+        match child {
+            Node::Element(child) => write_element(child, output),
+            Node::Text(text) => output.push_str(text),
+            Node::CData(text) => {
+                output.push_str("<![CDATA[");
+                output.push_str(text);
+                output.push_str("]]>");
+            }
+        }
+    }
+    output.push_str("</");
+    output.push_str(&element.name);
+    output.push('>');
+}
+
+fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('"', "&quot;")
+}
+
+fn xml_error(e: impl std::fmt::Display) -> Error {
+    Error::compression(format!("SVG 解析失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ICON: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Generator: Sketch -->
+<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"
+     xmlns:sketch="http://www.bohemiancoding.com/sketch/ns" width="24" height="24" viewBox="0 0 24 24">
+  <metadata><rdf:RDF/></metadata>
+  <style>
+    /* 图标颜色 */
+    .a ,  .b  {  fill : red ;  stroke: url( #grad ) ; }
+  </style>
+  <defs>
+    <linearGradient id="grad"><stop offset="0"/></linearGradient>
+    <linearGradient id="unused"><stop offset="1"/></linearGradient>
+  </defs>
+  <g>
+    <g sketch:type="MSLayerGroup" fill="#000">
+      <path id="shape" d="M 10.00000 20.123456 L -0.5000 0.25 A 5 5 0 0 1 15.5 15.5 Z" style="opacity: 0.5;"/>
+    </g>
+  </g>
+  <g transform="translate(1 1)"></g>
+  <text x="1" y="2">  hello  world  </text>
+</svg>"##;
+
+    fn minify(svg: &str, options: &SvgOptions) -> String {
+        String::from_utf8(minify_svg(svg.as_bytes(), options).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_minify_svg_with_safe_defaults() {
+        let output = minify(ICON, &SvgOptions::default());
+        assert_eq!(
+            output,
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24">"#,
+                r#"<style>.a,.b{fill:red;stroke:url(#grad)}</style>"#,
+                r#"<defs><linearGradient id="grad"><stop offset="0"/></linearGradient></defs>"#,
+                r##"<path d="M10 20.123L-.5.25A5 5 0 0 1 15.5 15.5Z" style="opacity:0.5" fill="#000"/>"##,
+                r#"<text x="1" y="2">  hello  world  </text></svg>"#,
+            )
+        );
+        assert!(output.len() < ICON.len());
+    }
+
+    #[test]
+    fn test_minify_svg_keeps_viewbox_and_ids_when_asked() {
+        let options = SvgOptions { precision: 1, keep_viewbox: true, keep_ids: true };
+        let output = minify(ICON, &options);
+        assert!(output.contains(r#"viewBox="0 0 24 24""#));
+        assert!(output.contains(r#"id="unused""#));
+        assert!(output.contains(r#"<path id="shape" d="M10 20.1L-.5.2A5 5 0 0 1 15.5 15.5Z""#));
+
+        // 动画可能通过 begin="id.end" 引用 ID，此时全部保留
+        let animated = r#"<svg xmlns="http://www.w3.org/2000/svg"><rect id="box"/><animate begin="box.click"/></svg>"#;
+        assert!(minify(animated, &SvgOptions::default()).contains(r#"id="box""#));
+        // sprite 中的 symbol 从外部引用
+        let sprite = r#"<svg xmlns="http://www.w3.org/2000/svg"><symbol id="icon"><path d="M0 0"/></symbol></svg>"#;
+        assert!(minify(sprite, &SvgOptions::default()).contains(r#"<symbol id="icon">"#));
+    }
+
+    #[test]
+    fn test_minify_svg_keeps_referenced_defs_and_structure() {
+        // 分组本身没有被引用，但其中的渐变被引用
+        let nested = concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><defs><g id="palette">"#,
+            r#"<linearGradient id="grad"><stop offset="0"/></linearGradient></g>"#,
+            r#"<g id="unused"><circle r="1"/></g></defs><rect fill="url(#grad)"/></svg>"#,
+        );
+        assert_eq!(
+            minify(nested, &SvgOptions::default()),
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg"><defs><g>"#,
+                r#"<linearGradient id="grad"><stop offset="0"/></linearGradient></g></defs>"#,
+                r#"<rect fill="url(#grad)"/></svg>"#,
+            )
+        );
+
+        // 样式依赖元素层级时不展开分组
+        let structural = r#"<svg xmlns="http://www.w3.org/2000/svg"><style>g > rect{fill:red} rect{fill:blue}</style><g><rect/></g></svg>"#;
+        assert!(minify(structural, &SvgOptions::default()).contains("<g><rect/></g>"));
+        assert!(is_structural_selector("svg g.icon"));
+        assert!(is_structural_selector("path:first-child"));
+        assert!(!is_structural_selector(".a .b"));
+        assert!(!is_structural_selector("#grad"));
+    }
+
+    #[test]
+    fn test_svg_rejects_conversion() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("icon.svg"), ICON).unwrap();
+        let options = CompressOptions { convert_to: Some(crate::ImageType::Webp), ..Default::default() };
+        assert!(crate::compress_images(temp_dir.path(), crate::ImageType::Svg, &options).is_err());
+        assert!(!temp_dir.path().join("icon.webp").exists());
+    }
+
+    #[test]
+    fn test_minify_path_and_numbers() {
+        assert_eq!(minify_path("M 0.5,0.5 l 1e1 -2.0004 h .25 .25", 3).unwrap(), "M.5.5l10-2h.25.25");
+        // 圆弧标志不能和后面的数字合并解析
+        assert_eq!(minify_path("a1 1 0 00.5.5", 3).unwrap(), "a1 1 0 0 0 .5.5");
+        assert!(minify_path("M 0 0 L x", 3).is_none());
+        assert_eq!(minify_points("0,0 10.0001,-5 3.5 ,4", 2).unwrap(), "0 0 10-5 3.5 4");
+        assert_eq!(format_number(-0.0001, 3), "0");
+        assert_eq!(minify_css("a :hover { color : blue ; }"), "a :hover{color:blue}");
+        assert!(parse(b"<svg><g></svg>").is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use ffmpeg_sidecar::command::FfmpegCommand;
+use ffmpeg_sidecar::event::{FfmpegEvent, LogLevel};
+use maya_common::error::{Error, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 临时文件序号，避免并行转换时文件名冲突
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// GIF 动图转换的视频格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum VideoFormat {
+    /// H.264 编码的 MP4
+    Mp4,
+    /// VP9 编码的 WebM
+    Webm,
+}
+
+impl VideoFormat {
+    /// 文件扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::Webm => "webm",
+        }
+    }
+
+    /// 显示名称
+    pub fn label(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "MP4",
+            VideoFormat::Webm => "WebM",
+        }
+    }
+
+    /// 传给 ffmpeg 的编码参数
+    ///
+    /// 视频没有透明通道和调色板，统一输出 yuv420p；H.264 要求宽高为偶数，奇数边裁掉 1 像素
+    fn encoder_args(self) -> &'static [&'static str] {
+        match self {
+            VideoFormat::Mp4 => &[
+                "-c:v", "libx264", "-preset", "slow", "-crf", "23", "-pix_fmt", "yuv420p",
+                "-vf", "crop=trunc(iw/2)*2:trunc(ih/2)*2", "-movflags", "+faststart",
+            ],
+            VideoFormat::Webm => &["-c:v", "libvpx-vp9", "-crf", "35", "-b:v", "0", "-pix_fmt", "yuv420p"],
+        }
+    }
+}
+
+impl FromStr for VideoFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mp4" => Ok(VideoFormat::Mp4),
+            "webm" => Ok(VideoFormat::Webm),
+            _ => Err(format!("不支持的视频格式: {}（可选 mp4、webm）", s)),
+        }
+    }
+}
+
+/// 用 FFmpeg 把 GIF 动图转码为视频，返回视频内容
+///
+/// FFmpeg 先输出到系统临时目录，由调用方决定是否写入目标位置
+pub(crate) fn encode_video(image_path: &Path, format: VideoFormat) -> Result<Vec<u8>> {
+    let temp_path = temp_output(format);
+    let result = run_ffmpeg(image_path, &temp_path, format).and_then(|_| Ok(fs::read(&temp_path)?));
+    let _ = fs::remove_file(&temp_path);
+    result
+}
+
+fn run_ffmpeg(input: &Path, output: &Path, format: VideoFormat) -> Result<()> {
+    let mut ffmpeg = FfmpegCommand::new()
+        .hide_banner()
+        .input(input.to_string_lossy())
+        .args(format.encoder_args())
+        .no_audio()
+        .output(output.to_string_lossy())
+        .overwrite()
+        .spawn()
+        .map_err(|e| Error::video_conversion(format!("FFmpeg启动失败: {}", e)))?;
+
+    // 读完 FFmpeg 的输出再等待退出，同时收集错误日志
+    let mut errors = Vec::new();
+    let events = ffmpeg
+        .iter()
+        .map_err(|e| Error::video_conversion(format!("FFmpeg迭代器错误: {}", e)))?;
+    for event in events {
+        match event {
+            FfmpegEvent::Log(LogLevel::Error | LogLevel::Fatal, message) | FfmpegEvent::Error(message) => {
+                errors.push(message)
+            }
+            _ => {}
+        }
+    }
+    let status = ffmpeg.wait()?;
+    if !status.success() || !output.exists() {
+        return Err(Error::video_conversion(format!(
+            "FFmpeg转换 {} 为 {} 失败: {}",
+            input.display(),
+            format.label(),
+            errors.join("; ")
+        )));
+    }
+    Ok(())
+}
+
+fn temp_output(format: VideoFormat) -> PathBuf {
+    let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("maya-gif-{}-{}.{}", std::process::id(), counter, format.extension()))
+}
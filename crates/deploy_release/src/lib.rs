@@ -0,0 +1,318 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use maya_common::error::{Error, Result};
+use maya_common::extract::{self, ExtractOptions};
+use maya_common::file_utils::copy_dir_recursive;
+use maya_common::time_utils::CivilTime;
+
+/// 存放历史版本的目录名
+const RELEASES_DIR: &str = "releases";
+/// 指向当前版本的符号链接名
+const CURRENT_LINK: &str = "current";
+
+/// 部署选项
+#[derive(Clone)]
+pub struct DeployOptions {
+    /// 保留的历史版本数量（包含当前版本）
+    pub keep: usize,
+    /// 加密zip产物的密码
+    pub password: Option<String>,
+}
+
+impl Default for DeployOptions {
+    fn default() -> Self {
+        Self { keep: 5, password: None }
+    }
+}
+
+impl std::fmt::Debug for DeployOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeployOptions")
+            .field("keep", &self.keep)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+/// 部署构建产物
+///
+/// 产物先解压（或复制）到 `releases/` 下的临时目录，完成后重命名为时间戳目录，
+/// 再原子地把 `current` 链接切换过去，最后清理超出保留数量的旧版本。
+///
+/// # 参数
+/// * `source` - 构建产物，可以是归档文件（zip/tar/tar.gz/tar.zst）或目录
+/// * `target_dir` - 部署目标目录
+/// * `options` - 部署选项
+///
+/// # 返回
+/// * `Result<PathBuf>` - 新版本所在目录
+pub fn deploy(source: &Path, target_dir: &Path, options: &DeployOptions) -> Result<PathBuf> {
+    if options.keep == 0 {
+        return Err(Error::invalid_argument("保留版本数量至少为 1"));
+    }
+
+    let releases_dir = target_dir.join(RELEASES_DIR);
+    fs::create_dir_all(&releases_dir)?;
+    ensure_current_is_link(target_dir)?;
+
+    let name = next_release_name(&releases_dir);
+    let staging = releases_dir.join(format!(".{}.tmp", name));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+
+    println!("📦 准备版本 {} ...", name);
+    if let Err(e) = stage_release(source, &staging, options.password.as_deref()) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    let release_dir = releases_dir.join(&name);
+    fs::rename(&staging, &release_dir)?;
+    switch_current(target_dir, &name)?;
+    println!("🔗 current -> {}/{}", RELEASES_DIR, name);
+
+    prune_releases(&releases_dir, &name, options.keep)?;
+    Ok(release_dir)
+}
+
+/// 回滚到当前版本的上一个版本
+///
+/// # 返回
+/// * `Result<PathBuf>` - 回滚后生效的版本目录
+pub fn rollback(target_dir: &Path) -> Result<PathBuf> {
+    let releases = list_releases(target_dir)?;
+    let current = current_release(target_dir)?
+        .ok_or_else(|| Error::path(format!("{} 中没有已部署的版本", target_dir.display())))?;
+
+    let index = releases
+        .iter()
+        .position(|r| *r == current)
+        .ok_or_else(|| Error::path(format!("当前版本 {} 不在 {} 目录中", current, RELEASES_DIR)))?;
+    if index == 0 {
+        return Err(Error::invalid_argument(format!("当前版本 {} 已是最早的版本，无法回滚", current)));
+    }
+
+    let previous = &releases[index - 1];
+    switch_current(target_dir, previous)?;
+    println!("🔗 current -> {}/{}（原版本 {}）", RELEASES_DIR, previous, current);
+    Ok(target_dir.join(RELEASES_DIR).join(previous))
+}
+
+/// 按时间顺序列出已有的版本名
+pub fn list_releases(target_dir: &Path) -> Result<Vec<String>> {
+    let releases_dir = target_dir.join(RELEASES_DIR);
+    if !releases_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut releases = Vec::new();
+    for entry in fs::read_dir(&releases_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        // 跳过未完成的临时目录
+        if entry.file_type()?.is_dir() && !name.starts_with('.') {
+            releases.push(name);
+        }
+    }
+    releases.sort();
+    Ok(releases)
+}
+
+/// 读取 `current` 链接当前指向的版本名
+pub fn current_release(target_dir: &Path) -> Result<Option<String>> {
+    match fs::read_link(target_dir.join(CURRENT_LINK)) {
+        Ok(link) => Ok(link.file_name().map(|n| n.to_string_lossy().to_string())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 把构建产物放入临时目录
+fn stage_release(source: &Path, staging: &Path, password: Option<&str>) -> Result<()> {
+    if source.is_dir() {
+        let count = copy_dir_recursive(source, staging)?;
+        println!("已复制 {} 个文件", count);
+    } else if source.is_file() {
+        let options = ExtractOptions { password: password.map(str::to_string), ..Default::default() };
+        let summary = extract::extract_archive(source, staging, &options)?;
+        println!("已解压 {} 个文件", summary.files);
+    } else {
+        return Err(Error::path(format!("构建产物不存在: {}", source.display())));
+    }
+    Ok(())
+}
+
+/// `current` 已存在但不是链接时拒绝继续，避免覆盖手工放置的目录
+fn ensure_current_is_link(target_dir: &Path) -> Result<()> {
+    let current = target_dir.join(CURRENT_LINK);
+    match fs::symlink_metadata(&current) {
+        Ok(metadata) if !metadata.file_type().is_symlink() => Err(Error::path(format!(
+            "{} 已存在且不是符号链接，请先手动处理",
+            current.display()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// 以当前 UTC 时间生成版本名，同一秒内重复部署时追加序号
+fn next_release_name(releases_dir: &Path) -> String {
+    let t = CivilTime::now();
+    let base = format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        t.year, t.month, t.day, t.hour, t.minute, t.second
+    );
+    if !releases_dir.join(&base).exists() {
+        return base;
+    }
+    (1..)
+        .map(|i| format!("{}-{}", base, i))
+        .find(|name| !releases_dir.join(name).exists())
+        .unwrap_or(base)
+}
+
+/// 先在临时位置创建新链接，再重命名覆盖 `current`，保证切换是原子的
+fn switch_current(target_dir: &Path, release_name: &str) -> Result<()> {
+    let current = target_dir.join(CURRENT_LINK);
+    let temp_link = target_dir.join(format!(".{}.tmp-{}", CURRENT_LINK, std::process::id()));
+    if fs::symlink_metadata(&temp_link).is_ok() {
+        remove_link(&temp_link)?;
+    }
+
+    let link_target = Path::new(RELEASES_DIR).join(release_name);
+    create_dir_link(&link_target, &temp_link)?;
+    replace_link(&temp_link, &current)
+}
+
+/// 删除超出保留数量的旧版本，当前版本始终保留
+fn prune_releases(releases_dir: &Path, current: &str, keep: usize) -> Result<()> {
+    let target_dir = releases_dir.parent().unwrap_or(releases_dir);
+    let releases = list_releases(target_dir)?;
+    if releases.len() <= keep {
+        return Ok(());
+    }
+
+    let excess = releases.len() - keep;
+    for name in releases.iter().filter(|r| *r != current).take(excess) {
+        fs::remove_dir_all(releases_dir.join(name))?;
+        println!("已删除旧版本: {}", name);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_dir_link(link_target: &Path, link_path: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(link_target, link_path)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn create_dir_link(link_target: &Path, link_path: &Path) -> Result<()> {
+    std::os::windows::fs::symlink_dir(link_target, link_path)
+        .map_err(|e| Error::path(format!("创建目录链接失败（Windows 需要开发者模式或管理员权限）: {}", e)))
+}
+
+#[cfg(unix)]
+fn remove_link(link_path: &Path) -> Result<()> {
+    fs::remove_file(link_path)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn remove_link(link_path: &Path) -> Result<()> {
+    fs::remove_dir(link_path)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn replace_link(temp_link: &Path, current: &Path) -> Result<()> {
+    // rename 会原子地替换已有的符号链接
+    fs::rename(temp_link, current)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn replace_link(temp_link: &Path, current: &Path) -> Result<()> {
+    // Windows 不能直接重命名覆盖目录链接，只能先删除旧链接
+    if fs::symlink_metadata(current).is_ok() {
+        remove_link(current)?;
+    }
+    fs::rename(temp_link, current)?;
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn make_build(root: &Path, content: &str) -> PathBuf {
+        let build = root.join("dist");
+        fs::create_dir_all(&build).unwrap();
+        fs::write(build.join("index.html"), content).unwrap();
+        build
+    }
+
+    #[test]
+    fn test_deploy_switches_current() {
+        let temp_dir = tempdir().unwrap();
+        let target = temp_dir.path().join("www");
+        let build = make_build(temp_dir.path(), "v1");
+
+        let release = deploy(&build, &target, &DeployOptions::default()).unwrap();
+        assert!(release.join("index.html").exists());
+        assert_eq!(fs::read_to_string(target.join("current/index.html")).unwrap(), "v1");
+
+        fs::write(build.join("index.html"), "v2").unwrap();
+        deploy(&build, &target, &DeployOptions::default()).unwrap();
+        assert_eq!(fs::read_to_string(target.join("current/index.html")).unwrap(), "v2");
+        assert_eq!(list_releases(&target).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rollback_and_prune() {
+        let temp_dir = tempdir().unwrap();
+        let target = temp_dir.path().join("www");
+        let build = make_build(temp_dir.path(), "v1");
+        let options = DeployOptions { keep: 2, ..Default::default() };
+
+        for version in ["v1", "v2", "v3"] {
+            fs::write(build.join("index.html"), version).unwrap();
+            deploy(&build, &target, &options).unwrap();
+        }
+        assert_eq!(list_releases(&target).unwrap().len(), 2);
+
+        rollback(&target).unwrap();
+        assert_eq!(fs::read_to_string(target.join("current/index.html")).unwrap(), "v2");
+        assert!(rollback(&target).is_err());
+    }
+
+    #[test]
+    fn test_deploy_copies_symlinked_files() {
+        let temp_dir = tempdir().unwrap();
+        let target = temp_dir.path().join("www");
+        let build = make_build(temp_dir.path(), "v1");
+        let shared = temp_dir.path().join("shared");
+        fs::create_dir_all(&shared).unwrap();
+        fs::write(shared.join("app.js"), "js").unwrap();
+        std::os::unix::fs::symlink(shared.join("app.js"), build.join("app.js")).unwrap();
+        std::os::unix::fs::symlink(&shared, build.join("assets")).unwrap();
+
+        let release = deploy(&build, &target, &DeployOptions::default()).unwrap();
+        assert_eq!(fs::read_to_string(release.join("app.js")).unwrap(), "js");
+        assert_eq!(fs::read_to_string(release.join("assets/app.js")).unwrap(), "js");
+
+        std::os::unix::fs::symlink(temp_dir.path().join("missing"), build.join("broken")).unwrap();
+        assert!(deploy(&build, &target, &DeployOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_refuses_to_replace_real_current_dir() {
+        let temp_dir = tempdir().unwrap();
+        let target = temp_dir.path().join("www");
+        fs::create_dir_all(target.join(CURRENT_LINK)).unwrap();
+        let build = make_build(temp_dir.path(), "v1");
+
+        assert!(deploy(&build, &target, &DeployOptions::default()).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::io::{self, Error, ErrorKind};
+use std::io::{self, Error};
 use std::process::{Command, Stdio};
 
 /// 在指定目录依次执行 git add .、git commit -m "feat: update"、git push
@@ -12,7 +12,7 @@ pub fn git_add_commit_push(path: String) -> io::Result<()> {
         .stderr(Stdio::inherit())
         .status()?;
     if !add_status.success() {
-        return Err(Error::new(ErrorKind::Other, "git add 失败"));
+        return Err(Error::other("git add 失败"));
     }
 
     // git commit -m "feat: update"
@@ -37,7 +37,7 @@ pub fn git_add_commit_push(path: String) -> io::Result<()> {
         .stderr(Stdio::inherit())
         .status()?;
     if !push_status.success() {
-        return Err(Error::new(ErrorKind::Other, "git push 失败"));
+        return Err(Error::other("git push 失败"));
     }
     Ok(())
 }
@@ -1,8 +1,10 @@
 use ignore::WalkBuilder;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use maya_common::error::Result;
+use maya_common::ZipOptions;
 
-pub fn handle_gitignore_pack() -> Result<()> {
+/// 根据.gitignore规则打包当前目录，返回生成的zip路径，没有.gitignore时返回 `None`
+pub fn handle_gitignore_pack(options: &ZipOptions) -> Result<Option<PathBuf>> {
     // 检查当前目录下是否有.gitignore文件
     let current_dir = std::env::current_dir()?;
 
@@ -10,18 +12,20 @@ pub fn handle_gitignore_pack() -> Result<()> {
         println!("找到.gitignore文件: {:?}", gitignore_path);
 
         // 创建zip文件
-        let zip_path = create_zip_from_gitignore(&current_dir, &current_dir)?;
+        let zip_path = create_zip_from_gitignore(&current_dir, &current_dir, options)?;
         println!("成功打包文件到: {:?}", zip_path);
+        Ok(Some(zip_path))
     } else {
         println!("没有找到.gitignore文件");
+        Ok(None)
     }
-    Ok(())
 }
 
 fn create_zip_from_gitignore(
     source_dir: &Path,
     dest_path: &Path,
-) -> Result<std::path::PathBuf> {
+    options: &ZipOptions,
+) -> Result<PathBuf> {
     // 使用ignore库来尊重.gitignore规则
     let walker = WalkBuilder::new(source_dir)
         .hidden(false) // 不跳过隐藏文件，让.gitignore规则处理
@@ -47,10 +51,15 @@ fn create_zip_from_gitignore(
     }
 
     // 创建zip文件 - 使用maya_common中的函数
-    let zip_path = maya_common::create_zip_archive(source_dir, dest_path, |path| {
-        // 只包含在allowed_files中的文件
-        path.is_file() && allowed_files.iter().any(|p| p == path)
-    })?;
+    let zip_path = maya_common::create_zip_archive_with_options(
+        source_dir,
+        dest_path,
+        |path| {
+            // 只包含在allowed_files中的文件
+            path.is_file() && allowed_files.iter().any(|p| p == path)
+        },
+        options,
+    )?;
 
     Ok(zip_path)
 }
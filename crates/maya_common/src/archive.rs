@@ -0,0 +1,288 @@
+use crate::atomic::{write_atomic_with, AtomicWriteOptions};
+use crate::error::Error;
+use crate::time_utils::{source_date_epoch, CivilTime};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use zip::write::{FileOptions, ZipWriter};
+use zip::{AesMode, DateTime};
+
+/// 可复现模式下固定使用的压缩级别
+const REPRODUCIBLE_COMPRESSION_LEVEL: i64 = 6;
+/// 可复现模式下固定使用的文件权限
+const REPRODUCIBLE_PERMISSIONS: u32 = 0o644;
+
+/// 写入zip前对文件内容的转换函数，参数为条目名和原始内容
+pub type ContentTransform = fn(&str, Vec<u8>) -> Vec<u8>;
+
+/// ZIP归档选项
+#[derive(Clone, Default)]
+pub struct ZipOptions {
+    /// 可复现模式：条目按路径排序，时间戳、权限和压缩参数固定，
+    /// 相同的目录树在不同机器上得到逐字节相同的归档
+    pub reproducible: bool,
+    /// 可选的内容转换，只影响写入zip的内容，不修改源文件
+    pub transform: Option<ContentTransform>,
+    /// 设置后所有条目使用 AES-256 加密
+    pub password: Option<String>,
+}
+
+impl std::fmt::Debug for ZipOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZipOptions")
+            .field("reproducible", &self.reproducible)
+            .field("transform", &self.transform)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+/// 创建ZIP归档。可以被不同的zip功能共享使用。
+pub fn create_zip_archive<F>(
+    source_dir: &Path,
+    dest_path: &Path,
+    file_filter: F,
+) -> io::Result<PathBuf>
+where
+    F: Fn(&Path) -> bool,
+{
+    create_zip_archive_with_options(source_dir, dest_path, file_filter, &ZipOptions::default())
+}
+
+/// 按指定选项创建ZIP归档
+///
+/// # 参数
+/// * `source_dir` - 要打包的目录
+/// * `dest_path` - zip文件输出目录，文件名取源目录名
+/// * `file_filter` - 过滤函数，返回 `true` 的路径才会被打包
+/// * `options` - 归档选项
+///
+/// # 返回
+/// * `io::Result<PathBuf>` - 生成的zip文件路径
+pub fn create_zip_archive_with_options<F>(
+    source_dir: &Path,
+    dest_path: &Path,
+    file_filter: F,
+    options: &ZipOptions,
+) -> io::Result<PathBuf>
+where
+    F: Fn(&Path) -> bool,
+{
+    // 获取源目录名称
+    let folder_name = source_dir
+        .file_name()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or("output");
+
+    // 构建zip文件路径
+    let zip_path = dest_path.join(format!("{}.zip", folder_name));
+
+    // 先收集文件，再创建zip文件，避免把正在写入的zip自身收进去
+    let entries = collect_entries(source_dir, &zip_path, &file_filter, options.reproducible);
+
+    // 先写入临时文件，完成后再替换，打包失败时不会留下损坏的zip
+    write_atomic_with(&zip_path, &AtomicWriteOptions::default(), |file| {
+        write_zip_entries(file, entries, options)?;
+        Ok(())
+    })
+    .map_err(|e| match e {
+        Error::Io(e) => e,
+        other => io::Error::other(other.to_string()),
+    })?;
+    Ok(zip_path)
+}
+
+/// 把收集到的文件写入zip
+fn write_zip_entries(file: &mut fs::File, entries: Vec<(String, PathBuf)>, options: &ZipOptions) -> io::Result<()> {
+    let mut zip = ZipWriter::new(file);
+
+    // 设置压缩选项
+    let mut file_options: FileOptions<'_, ()> = FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    if options.reproducible {
+        file_options = file_options
+            .compression_level(Some(REPRODUCIBLE_COMPRESSION_LEVEL))
+            .last_modified_time(reproducible_timestamp())
+            .unix_permissions(REPRODUCIBLE_PERMISSIONS);
+    }
+    if let Some(password) = options.password.as_deref() {
+        file_options = file_options.with_aes_encryption(AesMode::Aes256, password);
+    }
+
+    for (name, path) in entries {
+        let mut f = fs::File::open(&path)?;
+        let mut buffer = Vec::new();
+        f.read_to_end(&mut buffer)?;
+        if let Some(transform) = options.transform {
+            buffer = transform(&name, buffer);
+        }
+
+        // 将文件添加到zip中
+        zip.start_file(name, file_options)?;
+        zip.write_all(&buffer)?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// 收集要打包的文件，返回 (zip内条目名, 文件路径) 列表
+fn collect_entries<F>(
+    source_dir: &Path,
+    zip_path: &Path,
+    file_filter: &F,
+    sorted: bool,
+) -> Vec<(String, PathBuf)>
+where
+    F: Fn(&Path) -> bool,
+{
+    // 获取zip文件名，避免将自身包含在压缩文件中
+    let zip_filename = zip_path.file_name().unwrap_or_default();
+
+    let mut walkdir = WalkDir::new(source_dir);
+    if sorted {
+        walkdir = walkdir.sort_by_file_name();
+    }
+
+    let mut entries = Vec::new();
+    for entry in walkdir.into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+
+        // 如果是zip文件本身，跳过
+        if path.file_name().unwrap_or_default() == zip_filename {
+            continue;
+        }
+
+        // 应用文件过滤器
+        if !file_filter(path) || !path.is_file() {
+            continue;
+        }
+
+        // 获取相对路径
+        if let Ok(name) = path.strip_prefix(source_dir) {
+            if let Some(name_str) = zip_entry_name(name) {
+                entries.push((name_str, path.to_path_buf()));
+            }
+        }
+    }
+
+    if sorted {
+        // 目录遍历顺序与条目名的字节序不完全一致，这里按最终条目名再排一次
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    entries
+}
+
+/// 将相对路径转换为zip条目名（统一使用 `/` 分隔）
+fn zip_entry_name(relative: &Path) -> Option<String> {
+    let parts: Option<Vec<&str>> = relative.components().map(|c| c.as_os_str().to_str()).collect();
+    parts.map(|parts| parts.join("/"))
+}
+
+/// 可复现模式的时间戳：优先使用 `SOURCE_DATE_EPOCH`，否则为 1980-01-01 00:00:00
+fn reproducible_timestamp() -> DateTime {
+    source_date_epoch()
+        .and_then(|secs| {
+            let t = CivilTime::from_unix(secs);
+            // zip 时间戳只能表示 1980..=2107 年，超出范围时截断到边界
+            let (year, month, day, hour, minute, second) = if t.year < 1980 {
+                (1980, 1, 1, 0, 0, 0)
+            } else if t.year > 2107 {
+                (2107, 12, 31, 23, 59, 58)
+            } else {
+                (t.year as u16, t.month as u8, t.day as u8, t.hour as u8, t.minute as u8, t.second as u8)
+            };
+            DateTime::from_date_and_time(year, month, day, hour, minute, second).ok()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn build_tree(root: &Path) {
+        fs::create_dir_all(root.join("b/nested")).unwrap();
+        fs::write(root.join("z.txt"), "last").unwrap();
+        fs::write(root.join("a.txt"), "first").unwrap();
+        fs::write(root.join("b/nested/c.js"), "console.log(1)").unwrap();
+    }
+
+    #[test]
+    fn test_reproducible_archives_are_identical() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("dist");
+        build_tree(&source);
+        let out_a = temp_dir.path().join("a");
+        let out_b = temp_dir.path().join("b");
+        fs::create_dir_all(&out_a).unwrap();
+        fs::create_dir_all(&out_b).unwrap();
+
+        let options = ZipOptions { reproducible: true, ..Default::default() };
+        let zip_a = create_zip_archive_with_options(&source, &out_a, |_| true, &options).unwrap();
+        // 修改文件时间，确保输出不受 mtime 影响
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        fs::write(source.join("a.txt"), "first").unwrap();
+        let zip_b = create_zip_archive_with_options(&source, &out_b, |_| true, &options).unwrap();
+
+        assert_eq!(fs::read(zip_a).unwrap(), fs::read(zip_b).unwrap());
+    }
+
+    #[test]
+    fn test_reproducible_entries_are_sorted() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("dist");
+        build_tree(&source);
+
+        let options = ZipOptions { reproducible: true, ..Default::default() };
+        let zip_path = create_zip_archive_with_options(&source, temp_dir.path(), |_| true, &options).unwrap();
+
+        let archive = zip::ZipArchive::new(fs::File::open(zip_path).unwrap()).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names, vec!["a.txt", "b/nested/c.js", "z.txt"]);
+    }
+
+    #[test]
+    fn test_transform_applies_to_archive_only() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("dist");
+        build_tree(&source);
+
+        fn upper(_: &str, data: Vec<u8>) -> Vec<u8> {
+            data.to_ascii_uppercase()
+        }
+        let options = ZipOptions { transform: Some(upper), ..Default::default() };
+        let zip_path = create_zip_archive_with_options(&source, temp_dir.path(), |_| true, &options).unwrap();
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(zip_path).unwrap()).unwrap();
+        let mut content = String::new();
+        archive.by_name("a.txt").unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "FIRST");
+        assert_eq!(fs::read_to_string(source.join("a.txt")).unwrap(), "first");
+    }
+
+    #[test]
+    fn test_encrypted_archive_requires_password() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("dist");
+        build_tree(&source);
+
+        let options = ZipOptions { password: Some("secret".to_string()), ..Default::default() };
+        let zip_path = create_zip_archive_with_options(&source, temp_dir.path(), |_| true, &options).unwrap();
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(zip_path).unwrap()).unwrap();
+        assert!(archive.by_name("a.txt").is_err());
+        assert!(archive.by_name_decrypt("a.txt", b"wrong").is_err());
+        let mut content = String::new();
+        archive
+            .by_name_decrypt("a.txt", b"secret")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "first");
+        assert!(!format!("{:?}", options).contains("secret"));
+    }
+}
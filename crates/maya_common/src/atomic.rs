@@ -0,0 +1,198 @@
+use crate::error::{Error, Result};
+use std::fs::{self, File, FileTimes, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 同一进程内临时文件名的序号，避免并行写入同一目录时冲突
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// 原子写入选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtomicWriteOptions {
+    /// 覆盖已有文件时沿用原文件的权限
+    pub preserve_permissions: bool,
+    /// 覆盖已有文件时沿用原文件的修改时间
+    pub preserve_mtime: bool,
+}
+
+impl Default for AtomicWriteOptions {
+    fn default() -> Self {
+        Self {
+            preserve_permissions: true,
+            preserve_mtime: false,
+        }
+    }
+}
+
+/// 原子地写入文件内容
+///
+/// 先写到同目录下的临时文件并落盘，再改名覆盖目标文件；
+/// 中途崩溃或磁盘写满时原文件保持不变
+///
+/// # 参数
+/// * `path` - 目标文件路径
+/// * `data` - 文件内容
+/// * `options` - 写入选项
+pub fn write_atomic(path: &Path, data: &[u8], options: &AtomicWriteOptions) -> Result<()> {
+    write_atomic_with(path, options, |file| {
+        file.write_all(data)?;
+        Ok(())
+    })
+}
+
+/// 原子地写入文件，内容由回调写入临时文件
+///
+/// 回调返回错误时删除临时文件，目标文件保持不变
+///
+/// # 参数
+/// * `path` - 目标文件路径
+/// * `options` - 写入选项
+/// * `write` - 向临时文件写入内容的回调
+pub fn write_atomic_with<F>(path: &Path, options: &AtomicWriteOptions, write: F) -> Result<()>
+where
+    F: FnOnce(&mut File) -> Result<()>,
+{
+    let target = resolve_target(path);
+    let dir = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let (temp_path, mut file) = create_temp(&dir, &target)?;
+
+    let result = write(&mut file)
+        .and_then(|_| apply_options(&file, &temp_path, &target, options))
+        .and_then(|_| Ok(file.sync_all()?));
+    drop(file);
+    let result = result.and_then(|_| Ok(fs::rename(&temp_path, &target)?));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    sync_dir(&dir);
+    Ok(())
+}
+
+/// 目标是符号链接时写入链接指向的文件，保留链接本身
+fn resolve_target(path: &Path) -> PathBuf {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// 在目标所在目录创建临时文件，保证改名不跨文件系统
+fn create_temp(dir: &Path, target: &Path) -> Result<(PathBuf, File)> {
+    let name = target
+        .file_name()
+        .ok_or_else(|| Error::path(format!("无效的文件路径: {}", target.display())))?
+        .to_string_lossy();
+    loop {
+        let temp_path = dir.join(format!(
+            ".{}.{}-{}.tmp",
+            name,
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        match OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// 把原文件的权限、修改时间复制到临时文件
+fn apply_options(file: &File, temp_path: &Path, target: &Path, options: &AtomicWriteOptions) -> Result<()> {
+    let Ok(original) = fs::metadata(target) else {
+        return Ok(());
+    };
+    if options.preserve_mtime {
+        file.set_times(FileTimes::new().set_modified(original.modified()?))?;
+    }
+    if options.preserve_permissions {
+        fs::set_permissions(temp_path, original.permissions())?;
+    }
+    Ok(())
+}
+
+/// 改名后同步目录项，确保断电后新文件名也已落盘
+#[cfg(unix)]
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    fn leftover_temps(dir: &Path) -> usize {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".tmp"))
+            .count()
+    }
+
+    #[test]
+    fn test_write_atomic_creates_and_replaces() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("a.txt");
+
+        write_atomic(&path, b"first", &AtomicWriteOptions::default()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"first");
+        write_atomic(&path, b"second", &AtomicWriteOptions::default()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(leftover_temps(temp_dir.path()), 0);
+    }
+
+    #[test]
+    fn test_failed_write_keeps_original() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("a.txt");
+        fs::write(&path, "original").unwrap();
+
+        let result = write_atomic_with(&path, &AtomicWriteOptions::default(), |file| {
+            file.write_all(b"partial")?;
+            Err(Error::other("磁盘已满"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+        assert_eq!(leftover_temps(temp_dir.path()), 0);
+    }
+
+    #[test]
+    fn test_preserve_mtime() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("a.txt");
+        fs::write(&path, "original").unwrap();
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        File::options().write(true).open(&path).unwrap().set_modified(old).unwrap();
+
+        let options = AtomicWriteOptions { preserve_mtime: true, ..Default::default() };
+        write_atomic(&path, b"new", &options).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), old);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_preserve_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("run.sh");
+        fs::write(&path, "echo 1").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+
+        write_atomic(&path, b"echo 2", &AtomicWriteOptions::default()).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o750);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::error::{Error, Result};
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 项目配置文件名，放在项目根目录
+pub const CONFIG_FILE_NAME: &str = "maya.config.json";
+/// 项目内保存报告、缓存等数据的目录
+pub const DATA_DIR: &str = ".maya";
+
+/// 读取项目配置文件中的某个配置段
+///
+/// 配置文件不存在或没有该配置段时返回 `None`
+///
+/// # 参数
+/// * `project_dir` - 项目根目录
+/// * `section` - 配置段名称，例如 `pack`
+pub fn load_config_section<T: DeserializeOwned>(project_dir: &Path, section: &str) -> Result<Option<T>> {
+    let path = project_dir.join(CONFIG_FILE_NAME);
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)?;
+    let value: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| Error::config(format!("{} 解析失败: {}", path.display(), e)))?;
+
+    match value.get(section) {
+        Some(section_value) => serde_json::from_value(section_value.clone())
+            .map(Some)
+            .map_err(|e| Error::config(format!("{} 中的 {} 配置无效: {}", path.display(), section, e))),
+        None => Ok(None),
+    }
+}
+
+/// 项目数据目录中的文件路径，会自动创建数据目录
+pub fn data_file(project_dir: &Path, file_name: &str) -> Result<PathBuf> {
+    let dir = project_dir.join(DATA_DIR);
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use tempfile::tempdir;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Sample {
+        top: usize,
+    }
+
+    #[test]
+    fn test_load_config_section() {
+        let temp_dir = tempdir().unwrap();
+        assert_eq!(load_config_section::<Sample>(temp_dir.path(), "pack").unwrap(), None);
+
+        fs::write(temp_dir.path().join(CONFIG_FILE_NAME), r#"{ "pack": { "top": 3 } }"#).unwrap();
+        assert_eq!(
+            load_config_section::<Sample>(temp_dir.path(), "pack").unwrap(),
+            Some(Sample { top: 3 })
+        );
+        assert_eq!(load_config_section::<Sample>(temp_dir.path(), "optimize").unwrap(), None);
+
+        fs::write(temp_dir.path().join(CONFIG_FILE_NAME), r#"{ "pack": { "top": "x" } }"#).unwrap();
+        assert!(load_config_section::<Sample>(temp_dir.path(), "pack").is_err());
+    }
+}
@@ -35,6 +35,14 @@ pub enum Error {
     #[error("配置文件错误: {0}")]
     Config(String),
 
+    /// 体积预算超出
+    #[error("体积预算超出: {0}")]
+    Budget(String),
+
+    /// 归档读写错误
+    #[error("归档错误: {0}")]
+    Archive(String),
+
     /// 其他未分类错误
     #[error("错误: {0}")]
     Other(String),
@@ -80,12 +88,29 @@ impl Error {
         Self::Config(msg.into())
     }
 
+    /// 创建体积预算错误
+    pub fn budget(msg: impl Into<String>) -> Self {
+        Self::Budget(msg.into())
+    }
+
+    /// 创建归档错误
+    pub fn archive(msg: impl Into<String>) -> Self {
+        Self::Archive(msg.into())
+    }
+
     /// 创建其他错误
     pub fn other(msg: impl Into<String>) -> Self {
         Self::Other(msg.into())
     }
 }
 
+/// 从 zip::result::ZipError 转换
+impl From<zip::result::ZipError> for Error {
+    fn from(err: zip::result::ZipError) -> Self {
+        Self::Archive(err.to_string())
+    }
+}
+
 /// 从 anyhow::Error 转换
 #[cfg(feature = "anyhow")]
 impl From<anyhow::Error> for Error {
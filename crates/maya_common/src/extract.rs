@@ -0,0 +1,793 @@
+use crate::error::{Error, Result};
+use std::fs;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// 压缩比检查的起始阈值，解压数据量低于该值时不做压缩比判断，避免小文件误判
+const RATIO_CHECK_MIN_BYTES: u64 = 1024 * 1024;
+/// 流式复制时的缓冲区大小
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+/// 解析符号链接时最多跟随的层数，超过时视为循环链接
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// 支持的归档格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    /// 根据文件名后缀识别归档格式
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if name.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(Self::TarGz)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Ok(Self::TarZst)
+        } else if name.ends_with(".tar") {
+            Ok(Self::Tar)
+        } else {
+            Err(Error::archive(format!(
+                "不支持的归档格式: {}（支持 zip、tar、tar.gz、tar.zst）",
+                path.display()
+            )))
+        }
+    }
+}
+
+/// 归档条目类型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    /// 符号链接，值为链接目标
+    Symlink(String),
+    /// 硬链接（仅 tar），值为归档内的目标条目名
+    Hardlink(String),
+}
+
+/// 归档条目信息
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    /// 归档内的原始条目名
+    pub name: String,
+    pub kind: EntryKind,
+    /// 解压后的大小（来自归档头，仅供展示）
+    pub size: u64,
+    /// 压缩后的大小，tar 格式没有单条目压缩大小
+    pub compressed_size: Option<u64>,
+    /// 条目是否加密（仅 zip）
+    pub encrypted: bool,
+}
+
+/// 解压安全限制
+#[derive(Debug, Clone)]
+pub struct ExtractLimits {
+    /// 解压数据总量上限（字节）
+    pub max_total_size: u64,
+    /// 解压后与压缩后大小之比的上限
+    pub max_ratio: u64,
+    /// 条目数量上限
+    pub max_entries: usize,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: 4 * 1024 * 1024 * 1024,
+            max_ratio: 100,
+            max_entries: 100_000,
+        }
+    }
+}
+
+/// 解压选项
+#[derive(Clone, Default)]
+pub struct ExtractOptions {
+    /// 去掉条目路径开头的目录层数，与 `tar --strip-components` 相同
+    pub strip_components: usize,
+    pub limits: ExtractLimits,
+    /// 加密zip的密码
+    pub password: Option<String>,
+}
+
+impl std::fmt::Debug for ExtractOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtractOptions")
+            .field("strip_components", &self.strip_components)
+            .field("limits", &self.limits)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+/// 解压结果统计
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExtractSummary {
+    pub files: usize,
+    pub dirs: usize,
+    pub links: usize,
+    /// 因 strip_components 被整体去掉的条目数
+    pub skipped: usize,
+    pub total_bytes: u64,
+}
+
+/// 判断归档中是否有加密条目（只有 zip 支持加密）
+pub fn is_encrypted(archive_path: &Path) -> Result<bool> {
+    if ArchiveFormat::from_path(archive_path)? != ArchiveFormat::Zip {
+        return Ok(false);
+    }
+    let mut archive = zip::ZipArchive::new(BufReader::new(fs::File::open(archive_path)?))?;
+    for i in 0..archive.len() {
+        if archive.by_index_raw(i)?.encrypted() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// 列出归档中的条目，加密的 zip 不需要密码也可以列出
+pub fn list_archive(archive_path: &Path) -> Result<Vec<ArchiveEntry>> {
+    let format = ArchiveFormat::from_path(archive_path)?;
+    let mut entries = Vec::new();
+    for_each_entry(archive_path, format, None, true, |entry, _| {
+        entries.push(entry);
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// 依次读取归档中的普通文件，回调参数为条目名和文件内容
+///
+/// 只读取内容不落盘，目录和链接条目会被忽略
+pub fn visit_files<F>(archive_path: &Path, password: Option<&str>, mut visit: F) -> Result<()>
+where
+    F: FnMut(&str, &mut dyn Read) -> Result<()>,
+{
+    let format = ArchiveFormat::from_path(archive_path)?;
+    for_each_entry(archive_path, format, password, false, |entry, reader| match entry.kind {
+        EntryKind::File => visit(&entry.name, reader),
+        _ => Ok(()),
+    })
+}
+
+/// 安全地解压归档到目标目录
+///
+/// 会拒绝绝对路径和包含 `..` 的条目（zip-slip）、指向目标目录之外的符号链接，
+/// 以及超过总大小或压缩比限制的归档（zip 炸弹）。遇到不安全的条目时立即中止。
+///
+/// # 参数
+/// * `archive_path` - 归档文件路径
+/// * `dest` - 解压目标目录，不存在时自动创建
+/// * `options` - 解压选项
+///
+/// # 返回
+/// * `Result<ExtractSummary>` - 解压统计
+pub fn extract_archive(archive_path: &Path, dest: &Path, options: &ExtractOptions) -> Result<ExtractSummary> {
+    let format = ArchiveFormat::from_path(archive_path)?;
+    let archive_size = fs::metadata(archive_path)?.len();
+
+    fs::create_dir_all(dest)?;
+    let mut extractor = Extractor {
+        dest: dest.canonicalize()?,
+        options,
+        archive_size,
+        entries: 0,
+        summary: ExtractSummary::default(),
+        symlinks: Vec::new(),
+    };
+
+    let password = options.password.as_deref();
+    for_each_entry(archive_path, format, password, false, |entry, reader| extractor.extract(entry, reader))?;
+    extractor.verify_symlinks()?;
+    Ok(extractor.summary)
+}
+
+/// 依次读取归档条目并交给回调处理
+///
+/// `metadata_only` 为 `true` 时遇到加密条目不会要求密码，回调拿到的内容为空
+fn for_each_entry<F>(
+    archive_path: &Path,
+    format: ArchiveFormat,
+    password: Option<&str>,
+    metadata_only: bool,
+    mut handle: F,
+) -> Result<()>
+where
+    F: FnMut(ArchiveEntry, &mut dyn Read) -> Result<()>,
+{
+    let file = BufReader::new(fs::File::open(archive_path)?);
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(file)?;
+            for i in 0..archive.len() {
+                let encrypted = archive.by_index_raw(i)?.encrypted();
+                let readable = !encrypted || password.is_some();
+                let mut zip_file = match password {
+                    _ if !encrypted => archive.by_index(i)?,
+                    Some(password) => archive
+                        .by_index_decrypt(i, password.as_bytes())
+                        .map_err(|e| match e {
+                            zip::result::ZipError::InvalidPassword => Error::archive("归档密码错误"),
+                            e => e.into(),
+                        })?,
+                    None if metadata_only => archive.by_index_raw(i)?,
+                    None => return Err(Error::archive("归档已加密，需要提供密码")),
+                };
+                let name = zip_file.name().to_string();
+                let size = zip_file.size();
+                let compressed_size = Some(zip_file.compressed_size());
+
+                if zip_file.is_dir() {
+                    let entry = ArchiveEntry { name, kind: EntryKind::Dir, size, compressed_size, encrypted };
+                    handle(entry, &mut io::empty())?;
+                } else if zip_file.is_symlink() {
+                    // zip 中符号链接的目标保存在条目内容里
+                    let mut target = String::new();
+                    if readable {
+                        zip_file.read_to_string(&mut target)?;
+                    }
+                    let entry = ArchiveEntry { name, kind: EntryKind::Symlink(target), size, compressed_size, encrypted };
+                    handle(entry, &mut io::empty())?;
+                } else {
+                    let entry = ArchiveEntry { name, kind: EntryKind::File, size, compressed_size, encrypted };
+                    if readable {
+                        handle(entry, &mut zip_file)?;
+                    } else {
+                        handle(entry, &mut io::empty())?;
+                    }
+                }
+            }
+            Ok(())
+        }
+        ArchiveFormat::Tar => for_each_tar_entry(file, &mut handle),
+        ArchiveFormat::TarGz => for_each_tar_entry(flate2::read::GzDecoder::new(file), &mut handle),
+        ArchiveFormat::TarZst => for_each_tar_entry(zstd::stream::read::Decoder::with_buffer(file)?, &mut handle),
+    }
+}
+
+fn for_each_tar_entry<R, F>(reader: R, handle: &mut F) -> Result<()>
+where
+    R: Read,
+    F: FnMut(ArchiveEntry, &mut dyn Read) -> Result<()>,
+{
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+        let link_name = entry
+            .link_name_bytes()
+            .map(|l| String::from_utf8_lossy(&l).into_owned())
+            .unwrap_or_default();
+        let size = entry.header().size()?;
+
+        let kind = match entry.header().entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => EntryKind::File,
+            tar::EntryType::Directory => EntryKind::Dir,
+            tar::EntryType::Symlink => EntryKind::Symlink(link_name),
+            tar::EntryType::Link => EntryKind::Hardlink(link_name),
+            // 设备文件、FIFO 以及扩展头等条目不做处理
+            _ => continue,
+        };
+
+        let entry_info = ArchiveEntry { name, kind, size, compressed_size: None, encrypted: false };
+        handle(entry_info, &mut entry)?;
+    }
+    Ok(())
+}
+
+struct Extractor<'a> {
+    dest: PathBuf,
+    options: &'a ExtractOptions,
+    archive_size: u64,
+    entries: usize,
+    summary: ExtractSummary,
+    /// 已创建的符号链接，解压结束后按最终的目录状态再检查一次
+    symlinks: Vec<PathBuf>,
+}
+
+impl Extractor<'_> {
+    fn extract(&mut self, entry: ArchiveEntry, reader: &mut dyn Read) -> Result<()> {
+        let limits = &self.options.limits;
+        self.entries += 1;
+        if self.entries > limits.max_entries {
+            return Err(Error::archive(format!("条目数量超过上限 {}", limits.max_entries)));
+        }
+
+        let Some(relative) = sanitize_entry_path(&entry.name, self.options.strip_components)? else {
+            self.summary.skipped += 1;
+            return Ok(());
+        };
+        ensure_no_symlink_ancestors(&self.dest, &relative)?;
+        let target = self.dest.join(&relative);
+
+        match entry.kind {
+            EntryKind::Dir => {
+                fs::create_dir_all(&target)?;
+                self.summary.dirs += 1;
+            }
+            EntryKind::File => {
+                let written = self.write_file(&target, reader)?;
+                if let Some(compressed) = entry.compressed_size {
+                    if written > RATIO_CHECK_MIN_BYTES && written / compressed.max(1) > limits.max_ratio {
+                        let _ = fs::remove_file(&target);
+                        return Err(Error::archive(format!(
+                            "条目 {} 的压缩比超过上限 {}:1，疑似 zip 炸弹",
+                            entry.name, limits.max_ratio
+                        )));
+                    }
+                }
+                self.summary.total_bytes += written;
+                self.summary.files += 1;
+                if self.summary.total_bytes > RATIO_CHECK_MIN_BYTES
+                    && self.summary.total_bytes / self.archive_size.max(1) > limits.max_ratio
+                {
+                    return Err(Error::archive(format!(
+                        "归档整体压缩比超过上限 {}:1，疑似 zip 炸弹",
+                        limits.max_ratio
+                    )));
+                }
+            }
+            EntryKind::Symlink(link_target) => {
+                validate_symlink_target(&relative, &link_target)
+                    .and_then(|_| ensure_link_inside(&self.dest, &relative, Path::new(&link_target)))
+                    .map_err(|e| Error::archive(format!("条目 {}: {}", entry.name, e)))?;
+                prepare_parent(&target)?;
+                create_symlink(Path::new(&link_target), &target)?;
+                self.symlinks.push(relative);
+                self.summary.links += 1;
+            }
+            EntryKind::Hardlink(link_target) => {
+                let source_relative = sanitize_entry_path(&link_target, self.options.strip_components)?
+                    .ok_or_else(|| Error::archive(format!("硬链接 {} 的目标无效: {}", entry.name, link_target)))?;
+                ensure_no_symlink_ancestors(&self.dest, &source_relative)?;
+                let source = self.dest.join(source_relative);
+                // 对符号链接建立硬链接会在新位置得到一个同样的符号链接，相对目标换了位置后可能越界
+                if fs::symlink_metadata(&source).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+                    return Err(Error::archive(format!("硬链接 {} 的目标是符号链接: {}", entry.name, link_target)));
+                }
+                prepare_parent(&target)?;
+                fs::hard_link(source, &target)?;
+                self.summary.links += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// 后面的条目可能把链接目标经过的路径替换成新的符号链接，解压结束后逐个重新解析，
+    /// 删除越界的链接并报错
+    fn verify_symlinks(&self) -> Result<()> {
+        let mut escaped = Vec::new();
+        for relative in &self.symlinks {
+            let link_path = self.dest.join(relative);
+            let Ok(link_target) = fs::read_link(&link_path) else {
+                continue;
+            };
+            if let Err(e) = ensure_link_inside(&self.dest, relative, &link_target) {
+                let _ = fs::remove_file(&link_path);
+                escaped.push(format!("{}: {}", relative.display(), e));
+            }
+        }
+        if escaped.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::archive(format!("已删除越界的符号链接: {}", escaped.join("; "))))
+        }
+    }
+
+    /// 在总大小限制内写出文件，返回实际写入的字节数
+    fn write_file(&self, target: &Path, reader: &mut dyn Read) -> Result<u64> {
+        prepare_parent(target)?;
+        let remaining = self.options.limits.max_total_size.saturating_sub(self.summary.total_bytes);
+        let mut file = fs::File::create(target)?;
+
+        let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+        let mut written = 0u64;
+        loop {
+            let n = reader.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            written += n as u64;
+            if written > remaining {
+                drop(file);
+                let _ = fs::remove_file(target);
+                return Err(Error::archive(format!(
+                    "解压数据总量超过上限 {} 字节，疑似 zip 炸弹",
+                    self.options.limits.max_total_size
+                )));
+            }
+            file.write_all(&buffer[..n])?;
+        }
+        file.flush()?;
+        Ok(written)
+    }
+}
+
+/// 将归档条目名转换为安全的相对路径
+///
+/// 绝对路径、盘符路径和包含 `..` 的条目直接报错；
+/// 去掉 `strip_components` 层之后为空的条目返回 `None`
+pub fn sanitize_entry_path(name: &str, strip_components: usize) -> Result<Option<PathBuf>> {
+    let normalized = name.replace('\\', "/");
+    if normalized.starts_with('/') || has_drive_prefix(&normalized) {
+        return Err(Error::archive(format!("拒绝解压绝对路径条目: {}", name)));
+    }
+
+    let mut parts = Vec::new();
+    for part in normalized.split('/') {
+        match part {
+            "" | "." => continue,
+            ".." => return Err(Error::archive(format!("拒绝解压包含 .. 的条目: {}", name))),
+            p if p.contains('\0') => return Err(Error::archive(format!("条目名包含非法字符: {}", name))),
+            p => parts.push(p),
+        }
+    }
+
+    if parts.len() <= strip_components {
+        return Ok(None);
+    }
+    Ok(Some(parts[strip_components..].iter().collect()))
+}
+
+fn has_drive_prefix(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// 检查符号链接目标在解析后仍位于解压目录内
+fn validate_symlink_target(relative: &Path, link_target: &str) -> std::result::Result<(), String> {
+    let normalized = link_target.replace('\\', "/");
+    if normalized.starts_with('/') || has_drive_prefix(&normalized) {
+        return Err(format!("拒绝指向绝对路径的符号链接: {}", link_target));
+    }
+
+    // 从链接所在目录出发按字面解析目标路径
+    let mut depth: Vec<&str> = relative
+        .parent()
+        .map(|p| p.iter().filter_map(|c| c.to_str()).collect())
+        .unwrap_or_default();
+    for part in normalized.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if depth.pop().is_none() {
+                    return Err(format!("拒绝指向解压目录之外的符号链接: {}", link_target));
+                }
+            }
+            p => depth.push(p),
+        }
+    }
+    Ok(())
+}
+
+/// 按磁盘上已有的符号链接解析链接目标，检查结果仍位于解压目录内
+///
+/// 只看目标文本无法发现 `sub/c -> b/../..` 这类经过已解压链接 `sub/b -> ..` 的越界
+fn ensure_link_inside(dest: &Path, relative: &Path, link_target: &Path) -> std::result::Result<(), String> {
+    let start = dest.join(relative.parent().unwrap_or(Path::new("")));
+    let mut hops = 0;
+    let resolved = resolve_on_disk(start, link_target, &mut hops)?;
+    if resolved.starts_with(dest) {
+        Ok(())
+    } else {
+        Err(format!("拒绝指向解压目录之外的符号链接: {}", link_target.display()))
+    }
+}
+
+/// 从 `base` 出发逐级解析 `target`，遇到符号链接时跟随，不存在的部分按字面拼接
+///
+/// `base` 中不能包含符号链接，这样 `..` 可以直接去掉上一级
+fn resolve_on_disk(base: PathBuf, target: &Path, hops: &mut usize) -> std::result::Result<PathBuf, String> {
+    let mut current = base;
+    for component in target.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => {
+                return Err(format!("拒绝指向绝对路径的符号链接: {}", target.display()));
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                current.pop();
+            }
+            Component::Normal(name) => {
+                let next = current.join(name);
+                match fs::read_link(&next) {
+                    Ok(link) => {
+                        *hops += 1;
+                        if *hops > MAX_SYMLINK_HOPS {
+                            return Err(format!("符号链接层数过多: {}", next.display()));
+                        }
+                        current = resolve_on_disk(current, &link, hops)?;
+                    }
+                    Err(_) => current = next,
+                }
+            }
+        }
+    }
+    Ok(current)
+}
+
+/// 确保条目路径上的各级父目录都不是符号链接，防止借助已解压的链接写到目录之外
+fn ensure_no_symlink_ancestors(dest: &Path, relative: &Path) -> Result<()> {
+    let mut current = dest.to_path_buf();
+    if let Some(parent) = relative.parent() {
+        for component in parent.components() {
+            current.push(component);
+            if let Ok(metadata) = fs::symlink_metadata(&current) {
+                if metadata.file_type().is_symlink() {
+                    return Err(Error::archive(format!(
+                        "拒绝经由符号链接写入: {}",
+                        current.display()
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 创建父目录，并移除目标位置已有的符号链接，避免写穿链接
+fn prepare_parent(target: &Path) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if let Ok(metadata) = fs::symlink_metadata(target) {
+        if metadata.file_type().is_symlink() {
+            fs::remove_file(target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_symlink(link_target: &Path, link_path: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(link_target, link_path)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn create_symlink(link_target: &Path, link_path: &Path) -> Result<()> {
+    std::os::windows::fs::symlink_file(link_target, link_path)
+        .map_err(|e| Error::archive(format!("创建符号链接失败（Windows 需要开发者模式或管理员权限）: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use zip::write::{FileOptions, ZipWriter};
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        let options: FileOptions<'_, ()> = FileOptions::default();
+        for (name, data) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_entry_path() {
+        assert_eq!(sanitize_entry_path("a/b.txt", 0).unwrap(), Some(PathBuf::from("a/b.txt")));
+        assert_eq!(sanitize_entry_path("./a//b.txt", 0).unwrap(), Some(PathBuf::from("a/b.txt")));
+        assert_eq!(sanitize_entry_path("dist/a/b.txt", 1).unwrap(), Some(PathBuf::from("a/b.txt")));
+        assert_eq!(sanitize_entry_path("dist/", 1).unwrap(), None);
+        assert!(sanitize_entry_path("../evil.txt", 0).is_err());
+        assert!(sanitize_entry_path("a/../../evil.txt", 0).is_err());
+        assert!(sanitize_entry_path("/etc/passwd", 0).is_err());
+        assert!(sanitize_entry_path("C:\\Windows\\evil.dll", 0).is_err());
+    }
+
+    #[test]
+    fn test_validate_symlink_target() {
+        assert!(validate_symlink_target(Path::new("a/link"), "../b.txt").is_ok());
+        assert!(validate_symlink_target(Path::new("a/link"), "../../b.txt").is_err());
+        assert!(validate_symlink_target(Path::new("link"), "/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_extract_zip_with_strip_components() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("dist.zip");
+        write_zip(&archive, &[("dist/index.html", b"<html>"), ("dist/js/app.js", b"app")]);
+
+        let dest = temp_dir.path().join("out");
+        let options = ExtractOptions { strip_components: 1, ..Default::default() };
+        let summary = extract_archive(&archive, &dest, &options).unwrap();
+
+        assert_eq!(summary.files, 2);
+        assert_eq!(fs::read(dest.join("index.html")).unwrap(), b"<html>");
+        assert_eq!(fs::read(dest.join("js/app.js")).unwrap(), b"app");
+    }
+
+    #[test]
+    fn test_extract_zip_rejects_zip_slip() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("evil.zip");
+        write_zip(&archive, &[("../evil.txt", b"evil")]);
+
+        let dest = temp_dir.path().join("out");
+        let result = extract_archive(&archive, &dest, &ExtractOptions::default());
+        assert!(result.is_err());
+        assert!(!temp_dir.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn test_extract_zip_rejects_bomb() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("bomb.zip");
+        let zeros = vec![0u8; 4 * 1024 * 1024];
+        write_zip(&archive, &[("zeros.bin", &zeros)]);
+
+        let dest = temp_dir.path().join("out");
+        let result = extract_archive(&archive, &dest, &ExtractOptions::default());
+        assert!(result.unwrap_err().to_string().contains("zip 炸弹"));
+
+        let options = ExtractOptions {
+            limits: ExtractLimits { max_total_size: 1024, max_ratio: u64::MAX, ..Default::default() },
+            ..Default::default()
+        };
+        let result = extract_archive(&archive, &dest, &options);
+        assert!(result.unwrap_err().to_string().contains("总量超过上限"));
+    }
+
+    #[test]
+    fn test_extract_encrypted_zip() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("secret.zip");
+        let mut zip = ZipWriter::new(fs::File::create(&archive).unwrap());
+        let options: FileOptions<'_, ()> =
+            FileOptions::default().with_aes_encryption(zip::AesMode::Aes256, "secret");
+        zip.start_file("data.txt", options).unwrap();
+        zip.write_all(b"classified").unwrap();
+        zip.finish().unwrap();
+
+        assert!(is_encrypted(&archive).unwrap());
+        let entries = list_archive(&archive).unwrap();
+        assert!(entries[0].encrypted);
+
+        let dest = temp_dir.path().join("out");
+        assert!(extract_archive(&archive, &dest, &ExtractOptions::default()).is_err());
+        let wrong = ExtractOptions { password: Some("wrong".into()), ..Default::default() };
+        assert!(extract_archive(&archive, &dest, &wrong).unwrap_err().to_string().contains("密码错误"));
+
+        let options = ExtractOptions { password: Some("secret".into()), ..Default::default() };
+        extract_archive(&archive, &dest, &options).unwrap();
+        assert_eq!(fs::read(dest.join("data.txt")).unwrap(), b"classified");
+    }
+
+    #[test]
+    fn test_extract_tar_gz_and_list() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("site.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(fs::File::create(&archive).unwrap(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "site/hello.txt", &b"hello"[..]).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let entries = list_archive(&archive).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "site/hello.txt");
+        assert_eq!(entries[0].kind, EntryKind::File);
+
+        let dest = temp_dir.path().join("out");
+        extract_archive(&archive, &dest, &ExtractOptions::default()).unwrap();
+        assert_eq!(fs::read(dest.join("site/hello.txt")).unwrap(), b"hello");
+    }
+
+    fn append_symlink(builder: &mut tar::Builder<fs::File>, path: &str, target: &str) {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        header.set_path(path).unwrap();
+        header.set_link_name_literal(target).unwrap();
+        header.set_cksum();
+        builder.append(&header, io::empty()).unwrap();
+    }
+
+    fn append_hardlink(builder: &mut tar::Builder<fs::File>, path: &str, target: &str) {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_size(0);
+        header.set_path(path).unwrap();
+        header.set_link_name_literal(target).unwrap();
+        header.set_cksum();
+        builder.append(&header, io::empty()).unwrap();
+    }
+
+    fn append_dir(builder: &mut tar::Builder<fs::File>, path: &str) {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_size(0);
+        header.set_mode(0o755);
+        header.set_path(path).unwrap();
+        header.set_cksum();
+        builder.append(&header, io::empty()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_tar_rejects_chained_symlink() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("chain.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive).unwrap());
+        append_dir(&mut builder, "sub/");
+        append_symlink(&mut builder, "sub/b", "..");
+        append_symlink(&mut builder, "sub/c", "b/../..");
+        builder.finish().unwrap();
+        drop(builder);
+
+        let dest = temp_dir.path().join("out");
+        let result = extract_archive(&archive, &dest, &ExtractOptions::default());
+        assert!(result.unwrap_err().to_string().contains("符号链接"));
+        assert!(fs::symlink_metadata(dest.join("sub/c")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_tar_rejects_hardlink_to_symlink() {
+        // a/b/x 指向解压根目录，硬链接到 y 后变成指向上两级
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("t.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive).unwrap());
+        append_dir(&mut builder, "a/b/");
+        append_symlink(&mut builder, "a/b/x", "../..");
+        append_hardlink(&mut builder, "y", "a/b/x");
+        builder.finish().unwrap();
+        drop(builder);
+
+        let dest = temp_dir.path().join("out");
+        let result = extract_archive(&archive, &dest, &ExtractOptions::default());
+        assert!(result.unwrap_err().to_string().contains("符号链接"));
+        assert!(fs::symlink_metadata(dest.join("y")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_tar_rejects_symlink_retargeted_later() {
+        // a/b/x 创建时 y 还不存在，之后 y 成为指向上两级的链接，x 就越界了
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("later.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive).unwrap());
+        append_dir(&mut builder, "a/b/");
+        append_symlink(&mut builder, "a/b/x", "y/../..");
+        append_symlink(&mut builder, "a/b/y", "../..");
+        builder.finish().unwrap();
+        drop(builder);
+
+        let dest = temp_dir.path().join("out");
+        let result = extract_archive(&archive, &dest, &ExtractOptions::default());
+        assert!(result.unwrap_err().to_string().contains("a/b/x"));
+        assert!(fs::symlink_metadata(dest.join("a/b/x")).is_err());
+        assert!(fs::symlink_metadata(dest.join("a/b/y")).is_ok());
+    }
+
+    #[test]
+    fn test_extract_tar_rejects_escaping_symlink() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("links.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive).unwrap());
+        append_symlink(&mut builder, "escape", "../../outside");
+        builder.finish().unwrap();
+        drop(builder);
+
+        let dest = temp_dir.path().join("out");
+        let result = extract_archive(&archive, &dest, &ExtractOptions::default());
+        assert!(result.unwrap_err().to_string().contains("符号链接"));
+        assert!(fs::symlink_metadata(dest.join("escape")).is_err());
+    }
+}
@@ -190,4 +190,42 @@ fn remove_empty_dirs_recursive(dir: &Path, count: &mut usize) -> Result<()> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 递归复制目录
+///
+/// 符号链接会被跟随并复制其指向的内容，悬空链接或循环链接直接报错
+///
+/// # 参数
+/// * `src` - 源目录
+/// * `dst` - 目标目录，不存在时自动创建
+///
+/// # 返回
+/// * `Result<usize>` - 复制的文件数量
+pub fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<usize> {
+    if !src.is_dir() {
+        return Err(Error::path(format!("路径不是目录: {}", src.display())));
+    }
+
+    let mut count = 0;
+    for entry in WalkDir::new(src).follow_links(true) {
+        let entry = entry.map_err(|e| Error::path(format!("复制目录失败: {}", e)))?;
+        let relative = entry
+            .path()
+            .strip_prefix(src)
+            .map_err(|e| Error::path(e.to_string()))?;
+        let target = dst.join(relative);
+
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_file() {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(entry.path(), &target)?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,60 @@
+use crate::error::Result;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// 流式读取时的缓冲区大小
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// 计算数据的 SHA-256，返回小写十六进制字符串
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// 流式计算文件的 SHA-256，返回小写十六进制字符串
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let (hash, _) = sha256_reader(&mut fs::File::open(path)?)?;
+    Ok(hash)
+}
+
+/// 流式计算读取内容的 SHA-256
+///
+/// # 返回
+/// * `Result<(String, u64)>` - (小写十六进制哈希, 读取的字节数)
+pub fn sha256_reader(reader: &mut dyn Read) -> Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    let mut total = 0u64;
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        total += n as u64;
+    }
+    Ok((to_hex(&hasher.finalize()), total))
+}
+
+/// 字节转小写十六进制
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_sha256() {
+        let expected = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(sha256_hex(b"abc"), expected);
+
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("abc.txt");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(sha256_file(&path).unwrap(), expected);
+    }
+}
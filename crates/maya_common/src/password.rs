@@ -0,0 +1,26 @@
+use crate::error::{Error, Result};
+
+/// 归档密码的环境变量名，设置后不再交互式询问
+pub const PASSWORD_ENV: &str = "MAYA_PACK_PASSWORD";
+
+/// 获取归档密码：优先读取 `MAYA_PACK_PASSWORD` 环境变量，否则在终端中输入（不回显）
+///
+/// # 参数
+/// * `confirm` - 是否要求再输入一次确认，加密时使用
+pub fn read_password(confirm: bool) -> Result<String> {
+    if let Ok(password) = std::env::var(PASSWORD_ENV) {
+        if password.is_empty() {
+            return Err(Error::invalid_argument(format!("环境变量 {} 为空", PASSWORD_ENV)));
+        }
+        return Ok(password);
+    }
+
+    let password = rpassword::prompt_password("请输入归档密码: ")?;
+    if password.is_empty() {
+        return Err(Error::invalid_argument("密码不能为空"));
+    }
+    if confirm && rpassword::prompt_password("请再次输入密码: ")? != password {
+        return Err(Error::invalid_argument("两次输入的密码不一致"));
+    }
+    Ok(password)
+}
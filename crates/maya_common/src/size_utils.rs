@@ -0,0 +1,77 @@
+use crate::error::{Error, Result};
+
+const KB: u64 = 1024;
+const MB: u64 = KB * 1024;
+const GB: u64 = MB * 1024;
+
+/// 解析带单位的大小字符串
+///
+/// 支持 `B`、`K`/`KB`、`M`/`MB`、`G`/`GB`（不区分大小写，按 1024 进制），
+/// 不带单位时按字节处理，例如 `500KB`、`100M`、`1.5G`
+///
+/// # 返回
+/// * `Result<u64>` - 字节数
+pub fn parse_size(s: &str) -> Result<u64> {
+    let trimmed = s.trim();
+    let split_at = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split_at);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| Error::invalid_argument(format!("无法解析大小: {}", s)))?;
+    let multiplier = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => KB,
+        "m" | "mb" | "mib" => MB,
+        "g" | "gb" | "gib" => GB,
+        _ => return Err(Error::invalid_argument(format!("不支持的大小单位: {}", s))),
+    };
+
+    Ok((value * multiplier as f64).round() as u64)
+}
+
+/// 将字节数格式化为易读的字符串，例如 `1.50 MB`
+pub fn format_size(bytes: u64) -> String {
+    let b = bytes as f64;
+    if bytes >= GB {
+        format!("{:.2} GB", b / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", b / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", b / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// 格式化带符号的大小变化，例如 `+1.00 KB`、`-512 B`，没有变化时为 `±0 B`
+pub fn format_delta(delta: i64) -> String {
+    let sign = if delta > 0 { "+" } else if delta < 0 { "-" } else { "±" };
+    format!("{}{}", sign, format_size(delta.unsigned_abs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("500KB").unwrap(), 500 * 1024);
+        assert_eq!(parse_size("100M").unwrap(), 100 * 1024 * 1024);
+        assert_eq!(parse_size("1.5g").unwrap(), 1536 * 1024 * 1024);
+        assert!(parse_size("abc").is_err());
+        assert!(parse_size("10TB").is_err());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(100), "100 B");
+        assert_eq!(format_size(1536), "1.50 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.00 MB");
+        assert_eq!(format_delta(1536), "+1.50 KB");
+        assert_eq!(format_delta(0), "±0 B");
+    }
+}
@@ -0,0 +1,286 @@
+use crate::atomic::{write_atomic, write_atomic_with, AtomicWriteOptions};
+use crate::error::{Error, Result};
+use crate::hash_utils::{sha256_file, to_hex};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// 校验文件的扩展名，例如 `dist.zip.sha256`
+pub const MANIFEST_EXTENSION: &str = "sha256";
+/// 分卷编号位数，例如 `.001`
+const VOLUME_DIGITS: usize = 3;
+/// 分卷数量上限
+const MAX_VOLUMES: usize = 999;
+/// 流式复制时的缓冲区大小
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// 分卷结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitSummary {
+    /// 按顺序排列的分卷文件
+    pub volumes: Vec<PathBuf>,
+    /// 校验文件
+    pub manifest: PathBuf,
+}
+
+/// 第 `index` 个分卷的路径（从 1 开始），例如 `dist.zip` -> `dist.zip.001`
+pub fn volume_path(archive: &Path, index: usize) -> PathBuf {
+    let mut name = archive.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{:0width$}", index, width = VOLUME_DIGITS));
+    archive.with_file_name(name)
+}
+
+/// 校验文件路径，例如 `dist.zip` -> `dist.zip.sha256`
+pub fn manifest_path(archive: &Path) -> PathBuf {
+    let mut name = archive.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", MANIFEST_EXTENSION));
+    archive.with_file_name(name)
+}
+
+/// 如果路径是分卷文件（`.001` 这类结尾），返回合并后的归档路径
+pub fn volume_base(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let (base, number) = name.rsplit_once('.')?;
+    let is_volume = number.len() == VOLUME_DIGITS && number.bytes().all(|b| b.is_ascii_digit());
+    (is_volume && !base.is_empty()).then(|| path.with_file_name(base))
+}
+
+/// 把文件按固定大小切分为编号分卷，并生成 SHA-256 校验文件
+///
+/// 分卷写在原文件旁边，切分完成后删除原文件。校验文件为 `sha256sum` 兼容格式，
+/// 包含每个分卷以及合并后完整文件的哈希
+///
+/// # 参数
+/// * `archive` - 要切分的文件
+/// * `volume_size` - 每个分卷的最大字节数
+pub fn split_file(archive: &Path, volume_size: u64) -> Result<SplitSummary> {
+    if volume_size == 0 {
+        return Err(Error::invalid_argument("分卷大小必须大于 0"));
+    }
+    let total_size = fs::metadata(archive)?.len();
+    let count = total_size.div_ceil(volume_size).max(1) as usize;
+    if count > MAX_VOLUMES {
+        return Err(Error::invalid_argument(format!(
+            "分卷数量 {} 超过上限 {}，请增大分卷大小",
+            count, MAX_VOLUMES
+        )));
+    }
+
+    let mut reader = fs::File::open(archive)?;
+    let mut whole_hasher = Sha256::new();
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    let mut volumes = Vec::with_capacity(count);
+    let mut lines = Vec::with_capacity(count + 1);
+
+    for index in 1..=count {
+        let path = volume_path(archive, index);
+        let mut hasher = Sha256::new();
+        write_atomic_with(&path, &AtomicWriteOptions::default(), |file| {
+            let mut writer = BufWriter::new(file);
+            let mut remaining = volume_size;
+            while remaining > 0 {
+                let want = remaining.min(buffer.len() as u64) as usize;
+                let n = reader.read(&mut buffer[..want])?;
+                if n == 0 {
+                    break;
+                }
+                writer.write_all(&buffer[..n])?;
+                hasher.update(&buffer[..n]);
+                whole_hasher.update(&buffer[..n]);
+                remaining -= n as u64;
+            }
+            writer.flush()?;
+            Ok(())
+        })?;
+
+        lines.push(manifest_line(&to_hex(&hasher.finalize()), &path));
+        volumes.push(path);
+    }
+    lines.push(manifest_line(&to_hex(&whole_hasher.finalize()), archive));
+
+    // 清理上一次切分留下的多余分卷，避免合并时混入旧数据
+    let mut stale = count + 1;
+    while volume_path(archive, stale).is_file() {
+        fs::remove_file(volume_path(archive, stale))?;
+        stale += 1;
+    }
+
+    let manifest = manifest_path(archive);
+    write_atomic(&manifest, (lines.join("\n") + "\n").as_bytes(), &AtomicWriteOptions::default())?;
+    fs::remove_file(archive)?;
+    Ok(SplitSummary { volumes, manifest })
+}
+
+/// 按校验文件检查所有分卷，返回按顺序排列的分卷路径
+///
+/// 没有校验文件时只检查分卷是否连续存在
+///
+/// # 参数
+/// * `archive` - 合并后的归档路径（也可以直接传入任一分卷）
+pub fn verify_volumes(archive: &Path) -> Result<Vec<PathBuf>> {
+    let archive = volume_base(archive).unwrap_or_else(|| archive.to_path_buf());
+    let volumes = find_volumes(&archive)?;
+
+    let Some(manifest) = read_manifest(&archive)? else {
+        println!("未找到校验文件 {}，跳过哈希校验", manifest_path(&archive).display());
+        return Ok(volumes);
+    };
+
+    let expected_count = manifest.iter().filter(|(name, _)| volume_base(Path::new(name)).is_some()).count();
+    if expected_count != volumes.len() {
+        return Err(Error::archive(format!(
+            "分卷数量不符: 校验文件记录 {} 个，实际找到 {} 个",
+            expected_count,
+            volumes.len()
+        )));
+    }
+
+    let mut mismatched = Vec::new();
+    for volume in &volumes {
+        let name = file_name(volume);
+        match manifest.iter().find(|(n, _)| *n == name) {
+            Some((_, hash)) if *hash == sha256_file(volume)? => {}
+            Some(_) => mismatched.push(name),
+            None => mismatched.push(format!("{}（校验文件中没有记录）", name)),
+        }
+    }
+    if !mismatched.is_empty() {
+        return Err(Error::archive(format!("分卷校验失败: {}", mismatched.join(", "))));
+    }
+    Ok(volumes)
+}
+
+/// 校验并合并分卷
+///
+/// # 参数
+/// * `archive` - 合并后的归档路径（也可以直接传入任一分卷）
+/// * `output` - 输出路径，默认为去掉分卷编号的文件名
+///
+/// # 返回
+/// * `Result<PathBuf>` - 合并后的文件
+pub fn join_volumes(archive: &Path, output: Option<&Path>) -> Result<PathBuf> {
+    let archive = volume_base(archive).unwrap_or_else(|| archive.to_path_buf());
+    let volumes = verify_volumes(&archive)?;
+    let output = output.map(Path::to_path_buf).unwrap_or_else(|| archive.clone());
+
+    let expected = read_manifest(&archive)?.and_then(|manifest| {
+        let name = file_name(&archive);
+        manifest.into_iter().find(|(n, _)| *n == name).map(|(_, hash)| hash)
+    });
+
+    // 先写入临时文件，校验通过后再替换，避免留下不完整的归档
+    write_atomic_with(&output, &AtomicWriteOptions::default(), |file| {
+        let mut writer = BufWriter::new(file);
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+        for volume in &volumes {
+            let mut reader = fs::File::open(volume)?;
+            loop {
+                let n = reader.read(&mut buffer)?;
+                if n == 0 {
+                    break;
+                }
+                writer.write_all(&buffer[..n])?;
+                hasher.update(&buffer[..n]);
+            }
+        }
+        writer.flush()?;
+
+        match expected {
+            Some(expected) if to_hex(&hasher.finalize()) != expected => {
+                Err(Error::archive(format!("合并后的文件校验失败: {}", archive.display())))
+            }
+            _ => Ok(()),
+        }
+    })?;
+    Ok(output)
+}
+
+/// 从 `.001` 开始查找连续的分卷
+fn find_volumes(archive: &Path) -> Result<Vec<PathBuf>> {
+    let volumes: Vec<PathBuf> = (1..=MAX_VOLUMES)
+        .map(|i| volume_path(archive, i))
+        .take_while(|p| p.is_file())
+        .collect();
+    if volumes.is_empty() {
+        return Err(Error::path(format!("未找到分卷: {}", volume_path(archive, 1).display())));
+    }
+    Ok(volumes)
+}
+
+/// 读取校验文件，返回 (文件名, 哈希) 列表
+fn read_manifest(archive: &Path) -> Result<Option<Vec<(String, String)>>> {
+    let path = manifest_path(archive);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let entries = fs::read_to_string(&path)?
+        .lines()
+        .filter_map(|line| {
+            let (hash, name) = line.trim().split_once(char::is_whitespace)?;
+            // sha256sum 的二进制模式会在文件名前加 `*`
+            let name = name.trim_start().trim_start_matches('*');
+            Some((name.to_string(), hash.to_lowercase()))
+        })
+        .collect();
+    Ok(Some(entries))
+}
+
+fn manifest_line(hash: &str, path: &Path) -> String {
+    format!("{}  {}", hash, file_name(path))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_split_and_join() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("video.zip");
+        let data: Vec<u8> = (0..2500u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&archive, &data).unwrap();
+        // 上一次切分留下的旧分卷
+        fs::write(volume_path(&archive, 4), b"stale").unwrap();
+
+        let summary = split_file(&archive, 1000).unwrap();
+        assert_eq!(summary.volumes.len(), 3);
+        assert_eq!(fs::metadata(&summary.volumes[2]).unwrap().len(), 500);
+        assert!(!archive.exists());
+        assert!(!volume_path(&archive, 4).exists());
+
+        let joined = join_volumes(&summary.volumes[0], None).unwrap();
+        assert_eq!(joined, archive);
+        assert_eq!(fs::read(&archive).unwrap(), data);
+    }
+
+    #[test]
+    fn test_verify_detects_corruption() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("dist.zip");
+        fs::write(&archive, vec![7u8; 300]).unwrap();
+        let summary = split_file(&archive, 100).unwrap();
+        assert_eq!(verify_volumes(&archive).unwrap(), summary.volumes);
+
+        fs::write(&summary.volumes[1], vec![8u8; 100]).unwrap();
+        let err = join_volumes(&archive, None).unwrap_err().to_string();
+        assert!(err.contains("dist.zip.002"));
+        assert!(!archive.exists());
+
+        fs::remove_file(&summary.volumes[2]).unwrap();
+        assert!(verify_volumes(&archive).unwrap_err().to_string().contains("分卷数量不符"));
+    }
+
+    #[test]
+    fn test_volume_base() {
+        assert_eq!(volume_base(Path::new("a/dist.zip.012")), Some(PathBuf::from("a/dist.zip")));
+        assert_eq!(volume_base(Path::new("dist.zip")), None);
+        assert_eq!(volume_base(Path::new("dist.zip.1")), None);
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 由 Unix 时间戳换算得到的 UTC 日历时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CivilTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl CivilTime {
+    /// 将 Unix 时间戳（秒）转换为 UTC 日历时间
+    pub fn from_unix(secs: i64) -> Self {
+        let days = secs.div_euclid(86_400);
+        let secs_of_day = secs.rem_euclid(86_400);
+
+        // Howard Hinnant 的 civil_from_days 算法
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: (secs_of_day / 3_600) as u32,
+            minute: (secs_of_day % 3_600 / 60) as u32,
+            second: (secs_of_day % 60) as u32,
+        }
+    }
+
+    /// 当前的 UTC 日历时间
+    pub fn now() -> Self {
+        Self::from_unix(unix_now())
+    }
+}
+
+/// 当前的 Unix 时间戳（秒）
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// 读取 `SOURCE_DATE_EPOCH` 环境变量，用于可复现构建
+///
+/// 变量不存在或不是合法整数时返回 `None`
+pub fn source_date_epoch() -> Option<i64> {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_time_from_unix() {
+        assert_eq!(
+            CivilTime::from_unix(0),
+            CivilTime { year: 1970, month: 1, day: 1, hour: 0, minute: 0, second: 0 }
+        );
+        assert_eq!(
+            CivilTime::from_unix(315_532_800),
+            CivilTime { year: 1980, month: 1, day: 1, hour: 0, minute: 0, second: 0 }
+        );
+        // 2024-02-29 12:34:56 UTC
+        assert_eq!(
+            CivilTime::from_unix(1_709_210_096),
+            CivilTime { year: 2024, month: 2, day: 29, hour: 12, minute: 34, second: 56 }
+        );
+    }
+}
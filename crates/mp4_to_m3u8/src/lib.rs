@@ -165,8 +165,10 @@ async fn convert_single_mp4(mp4_file: &Path) -> Result<()> {
     Ok(())
 }
 
-/// 确保FFmpeg可用，如果不可用则自动下载
-async fn ensure_ffmpeg_available() -> Result<()> {
+/// 确保FFmpeg可用，如果不可用则自动下载到程序所在目录
+///
+/// 图片压缩把 GIF 转为视频前也调用这里
+pub async fn ensure_ffmpeg_available() -> Result<()> {
     // 尝试检查FFmpeg是否已经可用
     if is_ffmpeg_available() {
         return Ok(());
@@ -199,7 +201,7 @@ async fn ensure_ffmpeg_available() -> Result<()> {
     });
 
     // 自动下载FFmpeg
-    let download_result = tokio::task::spawn_blocking(|| auto_download())
+    let download_result = tokio::task::spawn_blocking(auto_download)
         .await
         .map_err(|e| Error::video_conversion(format!("FFmpeg下载任务失败: {}", e)))?;
 
@@ -215,16 +217,9 @@ async fn ensure_ffmpeg_available() -> Result<()> {
     }
 }
 
-/// 检查ffmpeg是否可用
+/// 检查ffmpeg是否可用，程序所在目录中自动下载的ffmpeg也算
 fn is_ffmpeg_available() -> bool {
-    // 简单的检查方式：尝试执行FFmpeg版本命令
-    match std::process::Command::new("ffmpeg")
-        .args(["-version"])
-        .output()
-    {
-        Ok(output) => output.status.success(),
-        Err(_) => false,
-    }
+    ffmpeg_sidecar::command::ffmpeg_is_installed()
 }
 
 /// 获取视频时长
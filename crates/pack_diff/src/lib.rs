@@ -0,0 +1,319 @@
+use maya_common::atomic::{write_atomic_with, AtomicWriteOptions};
+use maya_common::error::{Error, Result};
+use maya_common::extract;
+use maya_common::hash_utils::sha256_reader;
+use maya_common::size_utils::{format_delta, format_size};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use walkdir::WalkDir;
+use zip::write::{SimpleFileOptions, ZipWriter};
+use zip::AesMode;
+
+/// 增量包中记录变更清单的条目名
+pub const DELTA_MANIFEST_NAME: &str = ".maya-delta.json";
+
+/// 单个文件的大小和内容哈希
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDigest {
+    pub size: u64,
+    pub hash: String,
+}
+
+/// 构建产物快照，键为相对路径（`/` 分隔）
+pub type Snapshot = BTreeMap<String, FileDigest>;
+
+/// 文件变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// 单个文件的变化
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    /// 旧版本中的大小，新增文件为 `None`
+    pub old_size: Option<u64>,
+    /// 新版本中的大小，删除文件为 `None`
+    pub new_size: Option<u64>,
+}
+
+impl FileChange {
+    /// 大小变化（字节）
+    pub fn delta(&self) -> i64 {
+        self.new_size.unwrap_or(0) as i64 - self.old_size.unwrap_or(0) as i64
+    }
+}
+
+/// 两份构建产物的差异
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildDiff {
+    /// 按路径排序的变化文件
+    pub changes: Vec<FileChange>,
+    /// 内容相同的文件数量
+    pub unchanged: usize,
+    pub old_total: u64,
+    pub new_total: u64,
+}
+
+impl BuildDiff {
+    /// 指定类型的变化文件
+    pub fn of_kind(&self, kind: ChangeKind) -> impl Iterator<Item = &FileChange> {
+        self.changes.iter().filter(move |c| c.kind == kind)
+    }
+
+    /// 是否没有任何变化
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// 增量包中的变更清单，部署时据此删除旧文件
+#[derive(Debug, Serialize)]
+struct DeltaManifest<'a> {
+    added: Vec<&'a str>,
+    changed: Vec<&'a str>,
+    removed: Vec<&'a str>,
+}
+
+/// 读取目录或归档，计算每个文件的大小和 SHA-256
+///
+/// # 参数
+/// * `path` - 构建输出目录，或 zip/tar/tar.gz/tar.zst 归档
+/// * `password` - 加密zip的密码
+pub fn snapshot(path: &Path, password: Option<&str>) -> Result<Snapshot> {
+    let mut snapshot = Snapshot::new();
+    if path.is_dir() {
+        for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let (hash, size) = sha256_reader(&mut fs::File::open(entry.path())?)?;
+            snapshot.insert(relative_name(path, entry.path()), FileDigest { size, hash });
+        }
+    } else if path.is_file() {
+        extract::visit_files(path, password, |name, reader| {
+            let (hash, size) = sha256_reader(reader)?;
+            snapshot.insert(normalize_entry_name(name), FileDigest { size, hash });
+            Ok(())
+        })?;
+    } else {
+        return Err(Error::path(format!("路径不存在: {}", path.display())));
+    }
+    Ok(snapshot)
+}
+
+/// 比较两份快照
+pub fn diff_snapshots(old: &Snapshot, new: &Snapshot) -> BuildDiff {
+    let mut diff = BuildDiff {
+        old_total: old.values().map(|d| d.size).sum(),
+        new_total: new.values().map(|d| d.size).sum(),
+        ..Default::default()
+    };
+
+    let paths: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for path in paths {
+        let change = match (old.get(path), new.get(path)) {
+            (Some(a), Some(b)) if a.hash == b.hash => {
+                diff.unchanged += 1;
+                continue;
+            }
+            (Some(a), Some(b)) => (ChangeKind::Changed, Some(a.size), Some(b.size)),
+            (None, Some(b)) => (ChangeKind::Added, None, Some(b.size)),
+            (Some(a), None) => (ChangeKind::Removed, Some(a.size), None),
+            (None, None) => continue,
+        };
+        diff.changes.push(FileChange {
+            path: path.clone(),
+            kind: change.0,
+            old_size: change.1,
+            new_size: change.2,
+        });
+    }
+    diff
+}
+
+/// 打印差异
+pub fn print_diff(diff: &BuildDiff) {
+    println!("\n--- 构建产物差异 ---");
+    for change in &diff.changes {
+        match change.kind {
+            ChangeKind::Added => println!(
+                "+ {:<50} {:>12}",
+                change.path,
+                format_size(change.new_size.unwrap_or(0))
+            ),
+            ChangeKind::Removed => println!(
+                "- {:<50} {:>12}",
+                change.path,
+                format_size(change.old_size.unwrap_or(0))
+            ),
+            ChangeKind::Changed => println!(
+                "~ {:<50} {:>12} -> {:<12} ({})",
+                change.path,
+                format_size(change.old_size.unwrap_or(0)),
+                format_size(change.new_size.unwrap_or(0)),
+                format_delta(change.delta())
+            ),
+        }
+    }
+    println!(
+        "新增 {} 个, 删除 {} 个, 修改 {} 个, 未变 {} 个",
+        diff.of_kind(ChangeKind::Added).count(),
+        diff.of_kind(ChangeKind::Removed).count(),
+        diff.of_kind(ChangeKind::Changed).count(),
+        diff.unchanged
+    );
+    println!(
+        "总大小: {} -> {} ({})",
+        format_size(diff.old_total),
+        format_size(diff.new_total),
+        format_delta(diff.new_total as i64 - diff.old_total as i64)
+    );
+    println!("--------------------");
+}
+
+/// 生成只包含新增和修改文件的增量包
+///
+/// 增量包根目录中的 `.maya-delta.json` 记录新增、修改和删除的文件，
+/// 部署时解压覆盖后再按清单删除旧文件即可得到新版本
+///
+/// # 参数
+/// * `new_side` - 新版本的目录或归档
+/// * `diff` - 差异结果
+/// * `dest` - 增量包路径
+/// * `password` - 新版本归档的密码，设置后增量包同样使用 AES-256 加密
+///
+/// # 返回
+/// * `Result<usize>` - 写入的文件数量
+pub fn write_delta_archive(new_side: &Path, diff: &BuildDiff, dest: &Path, password: Option<&str>) -> Result<usize> {
+    let wanted: BTreeSet<&str> = diff
+        .changes
+        .iter()
+        .filter(|c| c.kind != ChangeKind::Removed)
+        .map(|c| c.path.as_str())
+        .collect();
+
+    let mut options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    if let Some(password) = password {
+        options = options.with_aes_encryption(AesMode::Aes256, password);
+    }
+    // 先写入临时文件，完成后再替换，失败时不会留下不完整的增量包
+    let mut written = 0;
+    write_atomic_with(dest, &AtomicWriteOptions::default(), |file| {
+        let mut zip = ZipWriter::new(file);
+        let mut add = |name: &str, reader: &mut dyn Read, zip: &mut ZipWriter<&mut fs::File>| -> Result<()> {
+            zip.start_file(name, options)?;
+            std::io::copy(reader, zip)?;
+            written += 1;
+            Ok(())
+        };
+
+        if new_side.is_dir() {
+            for name in &wanted {
+                add(name, &mut fs::File::open(new_side.join(name))?, &mut zip)?;
+            }
+        } else {
+            extract::visit_files(new_side, password, |name, reader| {
+                let name = normalize_entry_name(name);
+                if wanted.contains(name.as_str()) {
+                    add(&name, reader, &mut zip)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        let manifest = DeltaManifest {
+            added: diff.of_kind(ChangeKind::Added).map(|c| c.path.as_str()).collect(),
+            changed: diff.of_kind(ChangeKind::Changed).map(|c| c.path.as_str()).collect(),
+            removed: diff.of_kind(ChangeKind::Removed).map(|c| c.path.as_str()).collect(),
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| Error::other(format!("变更清单序列化失败: {}", e)))?;
+        zip.start_file(DELTA_MANIFEST_NAME, options)?;
+        zip.write_all(&manifest)?;
+        zip.finish()?;
+        Ok(())
+    })?;
+    Ok(written)
+}
+
+fn relative_name(dir: &Path, path: &Path) -> String {
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 统一归档条目名，tar 中常见的 `./` 前缀和 Windows 分隔符不影响比较
+fn normalize_entry_name(name: &str) -> String {
+    name.replace('\\', "/").trim_start_matches("./").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_tree(dir: &Path, files: &[(&str, &str)]) {
+        for (name, content) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_diff_dir_against_archive() {
+        let temp_dir = tempdir().unwrap();
+        let old = temp_dir.path().join("dist");
+        write_tree(&old, &[("index.html", "v1"), ("assets/app.js", "app"), ("assets/old.css", "a{}")]);
+        let old_zip = maya_common::create_zip_archive(&old, temp_dir.path(), |_| true).unwrap();
+
+        let new = temp_dir.path().join("new");
+        write_tree(&new, &[("index.html", "v2!"), ("assets/app.js", "app"), ("assets/new.js", "new")]);
+
+        let diff = diff_snapshots(&snapshot(&old_zip, None).unwrap(), &snapshot(&new, None).unwrap());
+        let kinds: Vec<(&str, ChangeKind)> = diff.changes.iter().map(|c| (c.path.as_str(), c.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("assets/new.js", ChangeKind::Added),
+                ("assets/old.css", ChangeKind::Removed),
+                ("index.html", ChangeKind::Changed),
+            ]
+        );
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.changes[2].delta(), 1);
+        assert_eq!((diff.old_total, diff.new_total), (8, 9));
+    }
+
+    #[test]
+    fn test_write_delta_archive() {
+        let temp_dir = tempdir().unwrap();
+        let old = temp_dir.path().join("old");
+        let new = temp_dir.path().join("new");
+        write_tree(&old, &[("a.txt", "same"), ("b.txt", "old"), ("gone.txt", "x")]);
+        write_tree(&new, &[("a.txt", "same"), ("b.txt", "new"), ("c/d.txt", "added")]);
+        let diff = diff_snapshots(&snapshot(&old, None).unwrap(), &snapshot(&new, None).unwrap());
+
+        let delta = temp_dir.path().join("delta.zip");
+        assert_eq!(write_delta_archive(&new, &diff, &delta, None).unwrap(), 2);
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&delta).unwrap()).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(names, vec![DELTA_MANIFEST_NAME, "b.txt", "c/d.txt"]);
+        let mut manifest = String::new();
+        archive.by_name(DELTA_MANIFEST_NAME).unwrap().read_to_string(&mut manifest).unwrap();
+        assert!(manifest.contains("gone.txt"));
+    }
+}
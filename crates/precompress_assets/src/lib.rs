@@ -0,0 +1,343 @@
+use maya_common::atomic::{write_atomic, AtomicWriteOptions};
+use maya_common::error::{Error, Result};
+use maya_common::file_utils::find_files_by_extension;
+use maya_common::size_utils::format_size;
+use rayon::prelude::*;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 值得预压缩的文本类资源扩展名
+const TEXT_EXTENSIONS: &[&str] = &[
+    "html", "htm", "css", "js", "mjs", "cjs", "json", "map", "svg", "xml", "txt", "md", "csv",
+    "wasm", "webmanifest", "ico", "ttf", "otf", "eot",
+];
+
+/// 预压缩编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    Brotli,
+    Zstd,
+}
+
+impl Encoding {
+    /// 全部编码，用于清理不再生成的预压缩文件
+    const ALL: [Encoding; 3] = [Encoding::Gzip, Encoding::Brotli, Encoding::Zstd];
+
+    /// 生成文件的扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gz",
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zst",
+        }
+    }
+
+    /// 以最高压缩级别压缩数据（静态资源只压缩一次，多花时间换体积）
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Encoding::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Encoding::Brotli => {
+                let mut output = Vec::new();
+                {
+                    let mut writer = brotli::CompressorWriter::new(&mut output, 4096, 11, 22);
+                    writer.write_all(data)?;
+                }
+                Ok(output)
+            }
+            Encoding::Zstd => Ok(zstd::stream::encode_all(data, 19)?),
+        }
+    }
+}
+
+/// 预压缩选项
+#[derive(Debug, Clone)]
+pub struct PrecompressOptions {
+    /// 小于该大小（字节）的文件不处理
+    pub min_size: u64,
+    /// 体积至少减少的比例，达不到时不生成该编码的文件
+    pub min_saving: f64,
+    /// 是否额外生成 .zst 文件
+    pub zstd: bool,
+}
+
+impl Default for PrecompressOptions {
+    fn default() -> Self {
+        Self {
+            min_size: 1024,
+            min_saving: 0.05,
+            zstd: false,
+        }
+    }
+}
+
+impl PrecompressOptions {
+    fn encodings(&self) -> Vec<Encoding> {
+        let mut encodings = vec![Encoding::Gzip, Encoding::Brotli];
+        if self.zstd {
+            encodings.push(Encoding::Zstd);
+        }
+        encodings
+    }
+}
+
+/// 单个编码的统计
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingStats {
+    pub encoding: Encoding,
+    /// 生成的文件数量
+    pub written: u32,
+    /// 压缩收益不足而跳过的文件数量
+    pub skipped: u32,
+    /// 生成文件对应的原始总大小
+    pub original_bytes: u64,
+    /// 生成文件的总大小
+    pub compressed_bytes: u64,
+}
+
+/// 预压缩结果统计
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecompressSummary {
+    /// 参与压缩的文件数量
+    pub files: u32,
+    /// 小于最小大小而忽略的文件数量
+    pub too_small: u32,
+    /// 处理失败的文件数量
+    pub failed: u32,
+    pub encodings: Vec<EncodingStats>,
+}
+
+/// 为目录中的文本资源并行生成预压缩文件
+///
+/// # 参数
+/// * `dir` - 构建输出目录
+/// * `options` - 预压缩选项
+///
+/// # 返回
+/// * `Result<PrecompressSummary>` - 统计结果
+pub fn precompress_dir(dir: &Path, options: &PrecompressOptions) -> Result<PrecompressSummary> {
+    println!("开始预压缩 {} 中的静态资源...", dir.display());
+
+    let files = find_files_by_extension(dir, TEXT_EXTENSIONS)?;
+    let encodings = options.encodings();
+
+    let mut summary = PrecompressSummary {
+        files: 0,
+        too_small: 0,
+        failed: 0,
+        encodings: encodings
+            .iter()
+            .map(|&encoding| EncodingStats {
+                encoding,
+                written: 0,
+                skipped: 0,
+                original_bytes: 0,
+                compressed_bytes: 0,
+            })
+            .collect(),
+    };
+
+    // 并行处理每个文件
+    let results: Vec<(PathBuf, Result<Option<Vec<Outcome>>>)> = files
+        .par_iter()
+        .map(|file_path| (file_path.clone(), precompress_file(file_path, &encodings, options)))
+        .collect();
+
+    for (file_path, result) in results {
+        match result {
+            Ok(Some(outcomes)) => {
+                summary.files += 1;
+                for (stats, outcome) in summary.encodings.iter_mut().zip(outcomes) {
+                    match outcome {
+                        Outcome::Written { original, compressed } => {
+                            stats.written += 1;
+                            stats.original_bytes += original;
+                            stats.compressed_bytes += compressed;
+                        }
+                        Outcome::Skipped => stats.skipped += 1,
+                    }
+                }
+            }
+            Ok(None) => summary.too_small += 1,
+            Err(e) => {
+                summary.failed += 1;
+                eprintln!("预压缩失败 {}: {}", file_path.display(), e);
+            }
+        }
+    }
+
+    print_summary(&summary);
+    Ok(summary)
+}
+
+/// 单个文件在某个编码下的处理结果
+enum Outcome {
+    Written { original: u64, compressed: u64 },
+    Skipped,
+}
+
+/// 为单个文件生成各编码的预压缩文件，文件过小时返回 `None`
+///
+/// 没有生成的编码（文件过小、收益不足或未启用）会删除旧的预压缩文件，避免服务器返回过期内容
+fn precompress_file(
+    file_path: &Path,
+    encodings: &[Encoding],
+    options: &PrecompressOptions,
+) -> Result<Option<Vec<Outcome>>> {
+    let data = fs::read(file_path)?;
+    let original = data.len() as u64;
+    if original < options.min_size {
+        remove_sidecars(file_path, &Encoding::ALL)?;
+        return Ok(None);
+    }
+    let disabled: Vec<Encoding> = Encoding::ALL.into_iter().filter(|e| !encodings.contains(e)).collect();
+    remove_sidecars(file_path, &disabled)?;
+
+    let mut outcomes = Vec::with_capacity(encodings.len());
+    for &encoding in encodings {
+        let compressed = encoding.compress(&data)?;
+        let sidecar = sidecar_path(file_path, encoding)?;
+
+        if (compressed.len() as f64) > original as f64 * (1.0 - options.min_saving) {
+            remove_sidecars(file_path, &[encoding])?;
+            outcomes.push(Outcome::Skipped);
+            continue;
+        }
+
+        write_atomic(&sidecar, &compressed, &AtomicWriteOptions::default())?;
+        outcomes.push(Outcome::Written {
+            original,
+            compressed: compressed.len() as u64,
+        });
+    }
+    Ok(Some(outcomes))
+}
+
+/// 删除指定编码的旧预压缩文件
+fn remove_sidecars(file_path: &Path, encodings: &[Encoding]) -> Result<()> {
+    for &encoding in encodings {
+        let sidecar = sidecar_path(file_path, encoding)?;
+        if sidecar.exists() {
+            fs::remove_file(&sidecar)?;
+        }
+    }
+    Ok(())
+}
+
+/// 预压缩文件路径，例如 `app.js` -> `app.js.br`
+fn sidecar_path(file_path: &Path, encoding: Encoding) -> Result<PathBuf> {
+    let file_name = file_path
+        .file_name()
+        .ok_or_else(|| Error::path(format!("无法获取文件名: {}", file_path.display())))?;
+    let mut sidecar_name = file_name.to_os_string();
+    sidecar_name.push(".");
+    sidecar_name.push(encoding.extension());
+    Ok(file_path.with_file_name(sidecar_name))
+}
+
+fn print_summary(summary: &PrecompressSummary) {
+    println!("\n--- 预压缩总结 ---");
+    println!("参与压缩文件数量: {}", summary.files);
+    println!("过小而忽略的文件: {}", summary.too_small);
+    println!("处理失败文件数量: {}", summary.failed);
+    for stats in &summary.encodings {
+        let saved = stats.original_bytes.saturating_sub(stats.compressed_bytes);
+        let ratio = if stats.original_bytes > 0 {
+            saved as f64 / stats.original_bytes as f64 * 100.0
+        } else {
+            0.0
+        };
+        println!(
+            ".{:<3} 生成 {} 个, 跳过 {} 个, {} -> {} (节省 {}, {:.2}%)",
+            stats.encoding.extension(),
+            stats.written,
+            stats.skipped,
+            format_size(stats.original_bytes),
+            format_size(stats.compressed_bytes),
+            format_size(saved),
+            ratio
+        );
+    }
+    println!("--------------------");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_precompress_dir_generates_sidecars() {
+        let temp_dir = tempdir().unwrap();
+        let js = "function hello() { return 'hello world'; }\n".repeat(200);
+        fs::write(temp_dir.path().join("app.js"), &js).unwrap();
+        fs::write(temp_dir.path().join("tiny.css"), "a{}").unwrap();
+        fs::write(temp_dir.path().join("photo.png"), vec![0u8; 4096]).unwrap();
+
+        let options = PrecompressOptions { zstd: true, ..Default::default() };
+        let summary = precompress_dir(temp_dir.path(), &options).unwrap();
+
+        assert_eq!(summary.files, 1);
+        assert_eq!(summary.too_small, 1);
+        for ext in ["gz", "br", "zst"] {
+            assert!(temp_dir.path().join(format!("app.js.{}", ext)).exists());
+        }
+        assert!(!temp_dir.path().join("tiny.css.gz").exists());
+        assert!(!temp_dir.path().join("photo.png.gz").exists());
+
+        let gz = fs::read(temp_dir.path().join("app.js.gz")).unwrap();
+        let mut decoded = String::new();
+        use std::io::Read;
+        flate2::read::GzDecoder::new(&gz[..]).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, js);
+    }
+
+    #[test]
+    fn test_precompress_skips_incompressible_files() {
+        let temp_dir = tempdir().unwrap();
+        // 伪随机数据几乎无法压缩
+        let mut state = 0x2545_f491_u32;
+        let noise: Vec<u8> = (0..8192)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        fs::write(temp_dir.path().join("font.ttf"), noise).unwrap();
+        fs::write(temp_dir.path().join("font.ttf.gz"), b"stale").unwrap();
+
+        let summary = precompress_dir(temp_dir.path(), &PrecompressOptions::default()).unwrap();
+        assert_eq!(summary.encodings[0].skipped, 1);
+        assert!(!temp_dir.path().join("font.ttf.gz").exists());
+    }
+
+    #[test]
+    fn test_precompress_removes_stale_sidecars() {
+        let temp_dir = tempdir().unwrap();
+        let js = "function hello() { return 'hello world'; }\n".repeat(200);
+        fs::write(temp_dir.path().join("app.js"), &js).unwrap();
+        precompress_dir(temp_dir.path(), &PrecompressOptions { zstd: true, ..Default::default() }).unwrap();
+        assert!(temp_dir.path().join("app.js.zst").exists());
+
+        // 不再启用 zstd 时删除旧的 .zst
+        precompress_dir(temp_dir.path(), &PrecompressOptions::default()).unwrap();
+        assert!(temp_dir.path().join("app.js.br").exists());
+        assert!(!temp_dir.path().join("app.js.zst").exists());
+
+        // 文件变得过小时删除全部预压缩文件
+        fs::write(temp_dir.path().join("app.js"), "a()").unwrap();
+        fs::write(temp_dir.path().join("app.js.zst"), b"stale").unwrap();
+        let summary = precompress_dir(temp_dir.path(), &PrecompressOptions::default()).unwrap();
+        assert_eq!(summary.too_small, 1);
+        for ext in ["gz", "br", "zst"] {
+            assert!(!temp_dir.path().join(format!("app.js.{}", ext)).exists(), "{}", ext);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use maya_common::error::{Error, Result};
+use regex::bytes::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// 始终排除的系统垃圾文件
+const DEFAULT_EXCLUDES: &[&str] = &[".DS_Store", "Thumbs.db", "desktop.ini"];
+/// 去掉sourcemap时排除的文件
+const SOURCEMAP_GLOB: &str = "*.map";
+
+/// 打包文件过滤器，路径按相对输出目录、`/` 分隔的形式匹配
+///
+/// 不含 `/` 的模式匹配任意层级的文件名，例如 `*.map` 等价于 `**/*.map`
+#[derive(Debug, Clone)]
+pub struct PackFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl PackFilter {
+    /// 创建过滤器
+    ///
+    /// # 参数
+    /// * `include` - 包含模式，为空时包含全部文件
+    /// * `exclude` - 排除模式，优先于包含模式
+    /// * `strip_sourcemaps` - 是否排除 `*.map` 文件
+    pub fn new(include: &[String], exclude: &[String], strip_sourcemaps: bool) -> Result<Self> {
+        let include = if include.is_empty() {
+            None
+        } else {
+            Some(build_glob_set(include.iter().map(String::as_str))?)
+        };
+
+        let mut excludes: Vec<&str> = DEFAULT_EXCLUDES.to_vec();
+        excludes.extend(exclude.iter().map(String::as_str));
+        if strip_sourcemaps {
+            excludes.push(SOURCEMAP_GLOB);
+        }
+
+        Ok(Self {
+            include,
+            exclude: build_glob_set(excludes.into_iter())?,
+        })
+    }
+
+    /// 判断输出目录中的文件是否需要打包
+    pub fn matches(&self, root: &Path, path: &Path) -> bool {
+        if !path.is_file() {
+            return false;
+        }
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if self.exclude.is_match(&relative) {
+            return false;
+        }
+        self.include.as_ref().is_none_or(|set| set.is_match(&relative))
+    }
+}
+
+fn build_glob_set<'a>(patterns: impl Iterator<Item = &'a str>) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let normalized = pattern.trim().trim_start_matches("./");
+        let full = if normalized.contains('/') {
+            normalized.to_string()
+        } else {
+            format!("**/{}", normalized)
+        };
+        let glob = GlobBuilder::new(&full)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| Error::invalid_argument(format!("无效的匹配模式 '{}': {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| Error::invalid_argument(format!("匹配模式构建失败: {}", e)))
+}
+
+/// 去掉JS/CSS文件中的 `sourceMappingURL` 注释，其他文件原样返回
+///
+/// 签名与 [`maya_common::archive::ContentTransform`] 一致，只作用于zip内的内容
+pub fn strip_source_map_comment(name: &str, data: Vec<u8>) -> Vec<u8> {
+    let lower = name.to_lowercase();
+    let is_script_or_style = [".js", ".mjs", ".cjs", ".css"].iter().any(|ext| lower.ends_with(ext));
+    if !is_script_or_style {
+        return data;
+    }
+
+    static SOURCE_MAP_RE: OnceLock<Regex> = OnceLock::new();
+    let re = SOURCE_MAP_RE.get_or_init(|| {
+        Regex::new(
+            r"(?m)^[ \t]*(?://[#@][ \t]*sourceMappingURL=[^\r\n]*|/\*[#@][ \t]*sourceMappingURL=[^*]*\*/[ \t]*)(?:\r?\n)?",
+        )
+        .unwrap()
+    });
+
+    match re.replace_all(&data, &b""[..]) {
+        std::borrow::Cow::Borrowed(_) => data,
+        std::borrow::Cow::Owned(stripped) => stripped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_pack_filter() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("assets")).unwrap();
+        for name in ["index.html", ".DS_Store", "assets/app.js", "assets/app.js.map", "assets/stats.html"] {
+            fs::write(root.join(name), "x").unwrap();
+        }
+
+        let filter = PackFilter::new(&[], &["stats.html".to_string()], true).unwrap();
+        assert!(filter.matches(root, &root.join("index.html")));
+        assert!(filter.matches(root, &root.join("assets/app.js")));
+        assert!(!filter.matches(root, &root.join(".DS_Store")));
+        assert!(!filter.matches(root, &root.join("assets/app.js.map")));
+        assert!(!filter.matches(root, &root.join("assets/stats.html")));
+
+        let filter = PackFilter::new(&["assets/*".to_string()], &[], false).unwrap();
+        assert!(!filter.matches(root, &root.join("index.html")));
+        assert!(filter.matches(root, &root.join("assets/app.js.map")));
+    }
+
+    #[test]
+    fn test_strip_source_map_comment() {
+        let js = b"console.log(1);\n//# sourceMappingURL=app.js.map\n".to_vec();
+        assert_eq!(strip_source_map_comment("assets/app.js", js), b"console.log(1);\n");
+
+        let css = b"a{color:red}\n/*# sourceMappingURL=app.css.map */".to_vec();
+        assert_eq!(strip_source_map_comment("app.css", css), b"a{color:red}\n");
+
+        let html = b"<!-- //# sourceMappingURL=x -->".to_vec();
+        assert_eq!(strip_source_map_comment("index.html", html.clone()), html);
+    }
+}
@@ -1,34 +1,108 @@
 use std::fs;
 use std::path::{Path, PathBuf};
+use maya_common::config::{data_file, load_config_section};
 use maya_common::error::Result;
+use maya_common::ZipOptions;
+use serde::Deserialize;
+
+pub mod filter;
+pub mod report;
+
+pub use filter::{strip_source_map_comment, PackFilter};
+pub use report::{Budget, BundleReport};
+
+/// 项目配置文件中 `pack` 段的内容
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PackConfig {
+    /// 体积预算，任一项超出时打包失败
+    pub budgets: Vec<Budget>,
+    /// 默认的包含模式
+    pub include: Vec<String>,
+    /// 默认的排除模式
+    pub exclude: Vec<String>,
+    /// 是否保留sourcemap，设为 `false` 时等同于始终传入 `--no-sourcemaps`
+    pub sourcemaps: Option<bool>,
+}
+
+/// Vite打包选项
+#[derive(Debug, Clone, Default)]
+pub struct VitePackOptions {
+    pub zip: ZipOptions,
+    /// 体积报告中列出的最大文件数量
+    pub top: usize,
+    /// 包含模式，与项目配置中的合并
+    pub include: Vec<String>,
+    /// 排除模式，与项目配置中的合并
+    pub exclude: Vec<String>,
+    /// 去掉 `*.map` 文件以及JS/CSS中的 `sourceMappingURL` 注释
+    pub no_sourcemaps: bool,
+}
 
 /// Vite打包模块，负责查找Vite配置并将输出目录打包为zip
-pub fn handle_vite_pack() -> Result<()> {
+///
+/// 返回生成的zip路径，找不到输出目录时返回 `None`
+pub fn handle_vite_pack(options: &VitePackOptions) -> Result<Option<PathBuf>> {
+    let Some(out_dir) = find_out_dir()? else {
+        return Ok(None);
+    };
+    let current_dir = std::env::current_dir()?;
+    let config: PackConfig = load_config_section(&current_dir, "pack")?.unwrap_or_default();
+
+    // 命令行参数与项目配置合并
+    let include: Vec<String> = config.include.iter().chain(&options.include).cloned().collect();
+    let exclude: Vec<String> = config.exclude.iter().chain(&options.exclude).cloned().collect();
+    let strip_sourcemaps = options.no_sourcemaps || config.sourcemaps == Some(false);
+
+    let pack_filter = PackFilter::new(&include, &exclude, strip_sourcemaps)?;
+    let file_filter = |path: &Path| pack_filter.matches(&out_dir, path);
+    let mut zip_options = options.zip.clone();
+    if strip_sourcemaps {
+        println!("已启用 sourcemap 移除");
+        zip_options.transform = Some(strip_source_map_comment);
+    }
+
+    // 打包前分析输出目录体积，并与上一次保存的报告比较
+    let bundle_report = report::analyze(&out_dir, file_filter, zip_options.transform)?;
+    let report_path = data_file(&current_dir, report::REPORT_FILE_NAME)?;
+    let previous = report::load_report(&report_path);
+    report::print_report(&bundle_report, previous.as_ref(), options.top);
+    report::check_budgets(&bundle_report, &config.budgets)?;
+
+    let zip_path = create_zip(&out_dir, &current_dir, file_filter, &zip_options)?;
+    report::save_report(&bundle_report, &report_path)?;
+    Ok(Some(zip_path))
+}
+
+/// 查找当前Vite项目的输出目录
+///
+/// 优先使用配置中的outDir，不存在时回退到dist。
+/// 找不到配置文件或输出目录时打印提示并返回 `None`
+pub fn find_out_dir() -> Result<Option<PathBuf>> {
     // 检查vite配置文件
-    let vite_config = find_vite_config();
-
-    if let Some(config_path) = vite_config {
-        // 读取outDir配置
-        let out_dir = get_out_dir(&config_path).unwrap_or_else(|| "dist".to_string());
-
-        // 检查outDir文件夹是否存在
-        let current_dir = std::env::current_dir()?;
-        let target_dir = current_dir.join(&out_dir);
-        if !target_dir.exists() {
-            // 检查dist文件夹是否存在
-            let dist_dir = current_dir.join("dist");
-            if dist_dir.exists() {
-                create_zip(&dist_dir, &current_dir)?;
-            } else {
-                println!("没有检测到对应打包文件夹，请检查Vite配置");
-            }
-        } else {
-            create_zip(&target_dir, &current_dir)?;
-        }
-    } else {
+    let Some(config_path) = find_vite_config() else {
         println!("没有检测到vite.config.js或vite.config.ts文件");
+        return Ok(None);
+    };
+
+    // 读取outDir配置
+    let out_dir = get_out_dir(&config_path).unwrap_or_else(|| "dist".to_string());
+
+    // 检查outDir文件夹是否存在
+    let current_dir = std::env::current_dir()?;
+    let target_dir = current_dir.join(&out_dir);
+    if target_dir.exists() {
+        return Ok(Some(target_dir));
+    }
+
+    // 检查dist文件夹是否存在
+    let dist_dir = current_dir.join("dist");
+    if dist_dir.exists() {
+        Ok(Some(dist_dir))
+    } else {
+        println!("没有检测到对应打包文件夹，请检查Vite配置");
+        Ok(None)
     }
-    Ok(())
 }
 
 /// 查找Vite配置文件
@@ -91,13 +165,12 @@ fn get_out_dir(config_path: &Path) -> Option<String> {
 }
 
 /// 创建ZIP文件
-fn create_zip(source_dir: &Path, dest_path: &Path) -> Result<PathBuf> {
-    // 使用共享库的create_zip_archive函数
-    let zip_path = maya_common::create_zip_archive(
-        source_dir,
-        dest_path,
-        |path| path.is_file() // 包含所有文件
-    )?;
+fn create_zip<F>(source_dir: &Path, dest_path: &Path, file_filter: F, options: &ZipOptions) -> Result<PathBuf>
+where
+    F: Fn(&Path) -> bool,
+{
+    // 使用共享库的create_zip_archive_with_options函数
+    let zip_path = maya_common::create_zip_archive_with_options(source_dir, dest_path, file_filter, options)?;
     
     println!("成功打包到: {:?}", zip_path);
     Ok(zip_path)
@@ -0,0 +1,286 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use maya_common::atomic::{write_atomic, AtomicWriteOptions};
+use maya_common::error::{Error, Result};
+use maya_common::ContentTransform;
+use maya_common::size_utils::{format_delta, format_size, parse_size};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// 报告保存在项目数据目录中的文件名
+pub const REPORT_FILE_NAME: &str = "bundle-report.json";
+/// 预算中表示所有文件合计的类型名
+const TOTAL_TYPE: &str = "total";
+
+/// 一组文件的大小统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeStats {
+    pub files: u64,
+    pub raw: u64,
+    pub gzip: u64,
+}
+
+/// 单个文件的大小
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSize {
+    /// 相对输出目录的路径（`/` 分隔）
+    pub path: String,
+    pub raw: u64,
+    pub gzip: u64,
+}
+
+/// 输出目录体积报告
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleReport {
+    pub total: SizeStats,
+    /// 按扩展名分组的统计，没有扩展名的文件归入空字符串
+    pub by_type: BTreeMap<String, SizeStats>,
+    /// 按原始大小降序排列的文件
+    pub files: Vec<FileSize>,
+}
+
+/// 体积预算，例如 `{ "type": "js", "maxGzip": "500KB" }`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Budget {
+    /// 文件扩展名，或 `total` 表示全部文件
+    #[serde(rename = "type")]
+    pub file_type: String,
+    /// 原始大小上限
+    pub max_raw: Option<String>,
+    /// gzip 后大小上限
+    pub max_gzip: Option<String>,
+}
+
+/// 分析目录中各文件的原始大小和 gzip 大小
+///
+/// # 参数
+/// * `dir` - 构建输出目录
+/// * `file_filter` - 与打包相同的过滤函数，只统计会被打包的文件
+/// * `transform` - 与打包相同的内容转换，统计的是写入zip的内容
+pub fn analyze<F>(dir: &Path, file_filter: F, transform: Option<ContentTransform>) -> Result<BundleReport>
+where
+    F: Fn(&Path) -> bool,
+{
+    let mut report = BundleReport::default();
+
+    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || !file_filter(path) {
+            continue;
+        }
+
+        let name = relative_name(dir, path);
+        let mut data = fs::read(path)?;
+        if let Some(transform) = transform {
+            data = transform(&name, data);
+        }
+        let size = FileSize {
+            path: name,
+            raw: data.len() as u64,
+            gzip: gzip_size(&data)?,
+        };
+
+        let file_type = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        add_stats(report.by_type.entry(file_type).or_default(), &size);
+        add_stats(&mut report.total, &size);
+        report.files.push(size);
+    }
+
+    report
+        .files
+        .sort_by(|a, b| b.raw.cmp(&a.raw).then_with(|| a.path.cmp(&b.path)));
+    Ok(report)
+}
+
+/// 打印报告，有上一次报告时同时显示变化量
+pub fn print_report(report: &BundleReport, previous: Option<&BundleReport>, top: usize) {
+    println!("\n--- 构建产物体积报告 ---");
+    println!("{:<10} {:>6} {:>12} {:>12}", "类型", "文件数", "原始大小", "gzip");
+    for (file_type, stats) in &report.by_type {
+        let name = if file_type.is_empty() { "(无扩展名)" } else { file_type };
+        let prev = previous.and_then(|p| p.by_type.get(file_type));
+        print_stats_row(name, stats, prev);
+    }
+    print_stats_row(TOTAL_TYPE, &report.total, previous.map(|p| &p.total));
+
+    if top > 0 && !report.files.is_empty() {
+        println!("\n最大的 {} 个文件:", top.min(report.files.len()));
+        for file in report.files.iter().take(top) {
+            println!("{:>12} {:>12}  {}", format_size(file.raw), format_size(file.gzip), file.path);
+        }
+    }
+
+    if let Some(previous) = previous {
+        let added = report
+            .files
+            .iter()
+            .filter(|f| !previous.files.iter().any(|p| p.path == f.path))
+            .count();
+        let removed = previous
+            .files
+            .iter()
+            .filter(|p| !report.files.iter().any(|f| f.path == p.path))
+            .count();
+        println!("\n与上次报告相比: 新增 {} 个文件, 删除 {} 个文件", added, removed);
+    }
+    println!("--------------------");
+}
+
+/// 检查预算，超出时返回包含全部超出项的错误
+pub fn check_budgets(report: &BundleReport, budgets: &[Budget]) -> Result<()> {
+    let mut violations = Vec::new();
+
+    for budget in budgets {
+        let file_type = budget.file_type.to_lowercase();
+        let empty = SizeStats::default();
+        let stats = if file_type == TOTAL_TYPE {
+            &report.total
+        } else {
+            report.by_type.get(&file_type).unwrap_or(&empty)
+        };
+
+        if let Some(max_raw) = &budget.max_raw {
+            let limit = parse_size(max_raw)?;
+            if stats.raw > limit {
+                violations.push(format!(
+                    "{} 原始大小 {} 超过预算 {}",
+                    file_type,
+                    format_size(stats.raw),
+                    format_size(limit)
+                ));
+            }
+        }
+        if let Some(max_gzip) = &budget.max_gzip {
+            let limit = parse_size(max_gzip)?;
+            if stats.gzip > limit {
+                violations.push(format!(
+                    "{} gzip 大小 {} 超过预算 {}",
+                    file_type,
+                    format_size(stats.gzip),
+                    format_size(limit)
+                ));
+            }
+        }
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::budget(violations.join("; ")))
+    }
+}
+
+/// 读取上一次保存的报告，文件不存在或格式不兼容时返回 `None`
+pub fn load_report(path: &Path) -> Option<BundleReport> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 保存报告
+pub fn save_report(report: &BundleReport, path: &Path) -> Result<()> {
+    let content = serde_json::to_string_pretty(report)
+        .map_err(|e| Error::other(format!("报告序列化失败: {}", e)))?;
+    write_atomic(path, content.as_bytes(), &AtomicWriteOptions::default())
+}
+
+fn add_stats(stats: &mut SizeStats, size: &FileSize) {
+    stats.files += 1;
+    stats.raw += size.raw;
+    stats.gzip += size.gzip;
+}
+
+fn print_stats_row(name: &str, stats: &SizeStats, previous: Option<&SizeStats>) {
+    let delta = previous
+        .map(|p| {
+            format!(
+                "  ({} / gzip {})",
+                format_delta(stats.raw as i64 - p.raw as i64),
+                format_delta(stats.gzip as i64 - p.gzip as i64)
+            )
+        })
+        .unwrap_or_default();
+    println!(
+        "{:<10} {:>6} {:>12} {:>12}{}",
+        name,
+        stats.files,
+        format_size(stats.raw),
+        format_size(stats.gzip),
+        delta
+    );
+}
+
+fn gzip_size(data: &[u8]) -> Result<u64> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?.len() as u64)
+}
+
+fn relative_name(dir: &Path, path: &Path) -> String {
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample_dir() -> tempfile::TempDir {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("assets")).unwrap();
+        fs::write(temp_dir.path().join("index.html"), "<html></html>").unwrap();
+        fs::write(temp_dir.path().join("assets/app.js"), "let a = 1;\n".repeat(500)).unwrap();
+        fs::write(temp_dir.path().join("assets/app.css"), "a{color:red}").unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_analyze_groups_by_type() {
+        let temp_dir = sample_dir();
+        let report = analyze(temp_dir.path(), |_| true, None).unwrap();
+
+        assert_eq!(report.total.files, 3);
+        assert_eq!(report.by_type["js"].raw, 5500);
+        assert!(report.by_type["js"].gzip < report.by_type["js"].raw);
+        assert_eq!(report.files[0].path, "assets/app.js");
+    }
+
+    #[test]
+    fn test_check_budgets() {
+        let temp_dir = sample_dir();
+        let report = analyze(temp_dir.path(), |_| true, None).unwrap();
+
+        let ok = vec![Budget { file_type: "js".into(), max_raw: Some("10KB".into()), max_gzip: None }];
+        assert!(check_budgets(&report, &ok).is_ok());
+
+        let exceeded = vec![
+            Budget { file_type: "JS".into(), max_raw: Some("1KB".into()), max_gzip: None },
+            Budget { file_type: "total".into(), max_raw: None, max_gzip: Some("10".into()) },
+        ];
+        let err = check_budgets(&report, &exceeded).unwrap_err().to_string();
+        assert!(err.contains("js 原始大小"));
+        assert!(err.contains("total gzip 大小"));
+    }
+
+    #[test]
+    fn test_report_roundtrip() {
+        let temp_dir = sample_dir();
+        let report = analyze(temp_dir.path(), |_| true, None).unwrap();
+        let path = temp_dir.path().join("report.json");
+        save_report(&report, &path).unwrap();
+        assert_eq!(load_report(&path), Some(report));
+        assert_eq!(format_delta(-2048), "-2.00 KB");
+    }
+}
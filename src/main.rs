@@ -5,10 +5,13 @@ use std::path::PathBuf;
 // 导入自定义模块
 mod modules {
     pub mod clean_ops;
+    pub mod deploy_ops;
     pub mod git_ops;
     pub mod optimize_ops;
     pub mod pack_ops;
+    pub mod precompress_ops;
     pub mod transform_ops;
+    pub mod unpack_ops;
 }
 
 /// Maya CLI 工具集
@@ -44,10 +47,107 @@ enum Command {
     },
 
     /// 打包操作
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Pack {
-        /// 打包操作类型 (g: 根据gitignore打包, a: Vite项目打包)
+        #[command(subcommand)]
+        action: Option<PackAction>,
+
+        /// 打包操作类型 (g: 根据gitignore打包, a: Vite项目打包, d: 打包 --dir 指定的目录)
         #[arg(short = 't', long, value_name = "PACK_TYPE", required = true)]
-        pack_type: String,
+        pack_type: Option<String>,
+
+        /// 要打包的目录，配合 -t d 使用
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+
+        /// 可复现打包：条目排序，时间戳取 SOURCE_DATE_EPOCH（默认 1980-01-01），权限和压缩参数固定
+        #[arg(long)]
+        reproducible: bool,
+
+        /// Vite 打包前的体积报告中列出的最大文件数量
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+
+        /// Vite 打包只包含匹配的文件（glob，可多个，例如 "assets/**"）
+        #[arg(long, num_args = 1.., value_name = "GLOB")]
+        include: Vec<String>,
+
+        /// Vite 打包排除匹配的文件（glob，可多个，例如 "*.txt" "stats.html"）
+        #[arg(long, num_args = 1.., value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Vite 打包去掉 *.map 文件以及 JS/CSS 中的 sourceMappingURL 注释
+        #[arg(long)]
+        no_sourcemaps: bool,
+
+        /// 使用 AES-256 加密zip，密码从 MAYA_PACK_PASSWORD 读取或交互输入
+        #[arg(long, conflicts_with = "reproducible")]
+        encrypt: bool,
+
+        /// 按大小切分为 name.zip.001、.002 ... 分卷并生成 .sha256 校验文件，例如 100M
+        #[arg(long, value_name = "SIZE")]
+        split_size: Option<String>,
+    },
+
+    /// 为构建产物生成 .gz/.br/.zst 预压缩文件
+    Precompress {
+        /// 构建输出目录，默认为 Vite 项目的输出目录
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// 额外生成 .zst 文件
+        #[arg(long)]
+        zstd: bool,
+
+        /// 小于该大小的文件不处理，例如 1K、512
+        #[arg(long, value_name = "SIZE", default_value = "1K")]
+        min_size: String,
+    },
+
+    /// 解压操作（支持 zip、tar、tar.gz、tar.zst）
+    Unpack {
+        /// 归档文件路径
+        #[arg(value_name = "ARCHIVE")]
+        archive: PathBuf,
+
+        /// 解压目标目录，默认为当前目录下与归档同名的文件夹
+        #[arg(value_name = "DEST")]
+        dest: Option<PathBuf>,
+
+        /// 只列出归档内容，不解压
+        #[arg(short = 'l', long)]
+        list: bool,
+
+        /// 去掉条目路径开头的 N 级目录
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        strip_components: usize,
+
+        /// 解压数据总量上限，例如 500M、4G
+        #[arg(long, value_name = "SIZE", default_value = "4G")]
+        max_size: String,
+
+        /// 解压后与压缩后大小之比的上限，超过时视为 zip 炸弹
+        #[arg(long, value_name = "RATIO", default_value_t = 100)]
+        max_ratio: u64,
+    },
+
+    /// 部署构建产物到本地或挂载目录（保留历史版本，支持回滚）
+    Deploy {
+        /// 部署目标目录，版本保存在其中的 releases/<时间戳>，current 链接指向当前版本
+        #[arg(value_name = "TARGET_DIR")]
+        target_dir: PathBuf,
+
+        /// 构建产物（zip/tar 归档或目录），默认使用 Vite 输出目录对应的 zip 或目录本身
+        #[arg(long, value_name = "SOURCE")]
+        from: Option<PathBuf>,
+
+        /// 保留的历史版本数量
+        #[arg(long, value_name = "N", default_value_t = 5)]
+        keep: usize,
+
+        /// 回滚到上一个版本
+        #[arg(long)]
+        rollback: bool,
     },
 
     /// 图片压缩操作
@@ -56,23 +156,59 @@ enum Command {
         #[arg(default_value = ".", value_name = "PATH")]
         path: PathBuf,
 
-        /// 图片压缩类型 (png/jpg/jpeg/all, 添加n参数创建新文件)
+        /// 图片压缩类型 (png/jpg/jpeg/gif/svg/all, 添加n参数创建新文件)
         #[arg(short = 't', long, num_args = 1.., value_name = "OPT_TYPES", required = true)]
         types: Vec<String>,
+
+        #[command(flatten)]
+        args: Box<modules::optimize_ops::OptimizeArgs>,
     },
 
-    /// 视频转换操作
+    /// 视频转换操作，以及 BMP/TIFF 等旧图片格式到现代格式的批量转换
     Transform {
         /// 操作目录路径，默认为当前目录
         #[arg(default_value = ".", value_name = "PATH")]
         path: PathBuf,
 
-        /// 源格式 目标格式，例如: mp4 m3u8
+        /// 源格式 目标格式，例如: mp4 m3u8、bmp png、tiff jpg（图片可转为 png/jpg/webp/avif）
         #[arg(short = 't', long, num_args = 2.., value_name = "TRANSFORM_TYPES", required = true)]
         types: Vec<String>,
     },
 }
 
+#[derive(Subcommand)]
+enum PackAction {
+    /// 校验并合并分卷
+    Join {
+        /// 任一分卷（如 dist.zip.001）或合并后的文件名（如 dist.zip）
+        #[arg(value_name = "VOLUME")]
+        volume: PathBuf,
+
+        /// 输出文件，默认为去掉分卷编号的文件名
+        #[arg(short = 'o', long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// 只校验分卷，不合并
+        #[arg(long)]
+        verify: bool,
+    },
+
+    /// 比较两份构建产物（目录或 zip/tar 归档）的差异
+    Diff {
+        /// 旧版本
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+
+        /// 新版本
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+
+        /// 生成只包含新增和修改文件的增量包（附带 .maya-delta.json 变更清单）
+        #[arg(long, value_name = "ZIP")]
+        delta: Option<PathBuf>,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -84,11 +220,55 @@ async fn main() -> Result<()> {
         Command::Git { ops, path } => {
             modules::git_ops::handle_git_ops(&ops, &path)?;
         }
-        Command::Pack { pack_type } => {
-            modules::pack_ops::handle_pack_ops(&pack_type)?;
+        Command::Pack { action: Some(PackAction::Join { volume, output, verify }), .. } => {
+            modules::pack_ops::handle_pack_join(&volume, output.as_deref(), verify)?;
+        }
+        Command::Pack { action: Some(PackAction::Diff { old, new, delta }), .. } => {
+            modules::pack_ops::handle_pack_diff(&old, &new, delta.as_deref())?;
+        }
+        Command::Pack {
+            action: None,
+            pack_type,
+            dir,
+            reproducible,
+            top,
+            include,
+            exclude,
+            no_sourcemaps,
+            encrypt,
+            split_size,
+        } => {
+            let args = modules::pack_ops::PackArgs {
+                reproducible,
+                top,
+                include,
+                exclude,
+                no_sourcemaps,
+                encrypt,
+                split_size,
+                dir,
+            };
+            // 没有子命令时 clap 保证 -t 已传入
+            modules::pack_ops::handle_pack_ops(pack_type.as_deref().unwrap_or_default(), &args)?;
+        }
+        Command::Precompress { path, zstd, min_size } => {
+            modules::precompress_ops::handle_precompress_ops(path.as_deref(), zstd, &min_size)?;
+        }
+        Command::Unpack { archive, dest, list, strip_components, max_size, max_ratio } => {
+            modules::unpack_ops::handle_unpack_ops(
+                &archive,
+                dest.as_deref(),
+                list,
+                strip_components,
+                &max_size,
+                max_ratio,
+            )?;
+        }
+        Command::Deploy { target_dir, from, keep, rollback } => {
+            modules::deploy_ops::handle_deploy_ops(&target_dir, from.as_deref(), keep, rollback)?;
         }
-        Command::Optimize { types, path } => {
-            modules::optimize_ops::handle_optimize_ops(&types, &path)?;
+        Command::Optimize { types, path, args } => {
+            modules::optimize_ops::handle_optimize_ops(&types, &path, &args).await?;
         }
         Command::Transform { types, path } => {
             modules::transform_ops::handle_transform_ops(&types, &path).await?;
@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use deploy_release::DeployOptions;
+use maya_common::error::{Error, Result};
+use maya_common::extract;
+use maya_common::file_utils::find_files;
+use maya_common::password::read_password;
+
+/// 处理部署操作的模块
+pub fn handle_deploy_ops(target_dir: &Path, from: Option<&Path>, keep: usize, rollback: bool) -> Result<()> {
+    if rollback {
+        let release = deploy_release::rollback(target_dir)?;
+        println!("✅ 已回滚到: {}", release.display());
+        return Ok(());
+    }
+
+    let source = match from {
+        Some(source) => source.to_path_buf(),
+        None => find_default_source()?,
+    };
+    println!("部署 {} 到 {}", source.display(), target_dir.display());
+
+    let password = if source.is_file() && extract::is_encrypted(&source)? {
+        Some(read_password(false)?)
+    } else {
+        None
+    };
+
+    let release = deploy_release::deploy(&source, target_dir, &DeployOptions { keep, password })?;
+    println!("✅ 部署完成: {}", release.display());
+    Ok(())
+}
+
+/// 默认的构建产物：Vite 输出目录和 `maya pack -t a` 生成的zip中较新的一个
+///
+/// zip 比输出目录中最新的文件还旧时说明之后重新构建过，此时部署输出目录
+fn find_default_source() -> Result<PathBuf> {
+    let out_dir = vite_pack_add_zip::find_out_dir()?
+        .ok_or_else(|| Error::path("未找到构建产物，请先构建项目或通过 --from 指定"))?;
+
+    let zip_name = format!("{}.zip", out_dir.file_name().unwrap_or_default().to_string_lossy());
+    let zip_path = std::env::current_dir()?.join(zip_name);
+    if !zip_path.is_file() {
+        return Ok(out_dir);
+    }
+
+    let zip_modified = fs::metadata(&zip_path)?.modified()?;
+    let dir_modified = find_files(&out_dir, |_| true)?
+        .iter()
+        .filter_map(|file| fs::metadata(file).and_then(|metadata| metadata.modified()).ok())
+        .max();
+    if dir_modified.is_some_and(|modified| modified > zip_modified) {
+        println!("{} 比 {} 旧，使用输出目录", zip_path.display(), out_dir.display());
+        Ok(out_dir)
+    } else {
+        println!("使用压缩包 {}（比输出目录新）", zip_path.display());
+        Ok(zip_path)
+    }
+}
@@ -1,13 +1,199 @@
-use compress_pictures;
+use compress_pictures::{
+    self, CacheLocation, ChromaSubsampling, CompressOptions, Fit, ImageType, MetadataMode, MetadataOptions, PngOptions, PngStrip,
+    ReportFormat, ResizeOptions, ResponsiveOptions, SvgOptions, VideoFormat, DEFAULT_SVG_PRECISION,
+};
+use clap::Args;
+use maya_common::config::data_file;
 use maya_common::error::{Error, Result};
-use maya_common::file_utils::find_files_by_extension;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-const PARALLEL_THRESHOLD: usize = 10;
+/// 命令行传入的图片压缩参数
+#[derive(Args)]
+pub struct OptimizeArgs {
+    /// 转换为 webp 或 avif（GIF 动图可以转换为动态 webp、mp4 或 webm），默认保留原图，转换后变大的文件会被跳过
+    #[arg(long, value_name = "FORMAT")]
+    pub to: Option<String>,
 
-pub fn handle_optimize_ops(types: &[String], path: &Path) -> Result<()> {
+    /// 无损模式: WebP 使用无损编码，JPEG 只做无损优化不重新量化
+    #[arg(long)]
+    pub lossless: bool,
+
+    /// 转换成功后删除原图
+    #[arg(long, requires = "to")]
+    pub remove_original: bool,
+
+    /// 有损编码质量 1-100（JPEG 默认 80，WebP 默认 80，AVIF 默认 70）
+    #[arg(short = 'q', long, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub quality: Option<u8>,
+
+    /// 目标 SSIM (0-1，例如 0.95)，为每张图片搜索达到目标的最低质量，代替固定的 --quality
+    #[arg(long, value_name = "SSIM", conflicts_with = "quality")]
+    pub target_ssim: Option<f64>,
+
+    /// 元数据处理方式 (strip/keep/keep-icc/keep-copyright)，默认 keep；重新编码的 JPEG 会按 EXIF 方向摆正
+    #[arg(long, value_name = "MODE")]
+    pub metadata: Option<String>,
+
+    /// 保留 GPS 位置信息，默认出于隐私考虑移除
+    #[arg(long)]
+    pub keep_gps: bool,
+
+    /// 并行处理的线程数，默认使用全部 CPU 核心
+    #[arg(short = 'j', long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: Option<u16>,
+
+    #[command(flatten)]
+    pub jpeg: JpegArgs,
+
+    #[command(flatten)]
+    pub png: PngArgs,
+
+    #[command(flatten)]
+    pub svg: SvgArgs,
+
+    #[command(flatten)]
+    pub resize: ResizeArgs,
+
+    #[command(flatten)]
+    pub cache: CacheArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+/// JPEG 编码参数
+#[derive(Args)]
+#[command(next_help_heading = "JPEG")]
+pub struct JpegArgs {
+    /// JPEG 输出基线格式，默认输出渐进式
+    #[arg(long)]
+    pub baseline: bool,
+
+    /// JPEG 色度抽样 (420/422/444)，默认 420
+    #[arg(long, value_name = "SUBSAMPLING")]
+    pub chroma: Option<String>,
+}
+
+/// PNG 量化和 oxipng 参数
+#[derive(Args)]
+#[command(next_help_heading = "PNG")]
+pub struct PngArgs {
+    /// PNG 有损调色板量化的质量范围，例如 65-80，不设置时只做无损优化
+    #[arg(long, value_name = "MIN-MAX")]
+    pub png_quality: Option<String>,
+
+    /// PNG 量化的抖动强度 0.0-1.0，默认 1.0
+    #[arg(long, value_name = "LEVEL", requires = "png_quality")]
+    pub dither: Option<f32>,
+
+    /// oxipng 预设等级 0-6，默认 2，越高越慢
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=6))]
+    pub png_level: Option<u8>,
+
+    /// PNG 使用 Zopfli 压缩，体积更小但速度慢很多
+    #[arg(long)]
+    pub zopfli: bool,
+
+    /// 直接指定 oxipng 的 PNG 元数据清理方式 (none/safe/all)，不能与 --metadata 同时使用
+    #[arg(long, value_name = "MODE", conflicts_with = "metadata")]
+    pub strip: Option<String>,
+
+    /// PNG 修改完全透明像素的颜色以提高压缩率
+    #[arg(long)]
+    pub optimize_alpha: bool,
+}
+
+/// SVG 优化参数
+#[derive(Args)]
+#[command(next_help_heading = "SVG")]
+pub struct SvgArgs {
+    /// SVG 路径坐标保留的小数位数 0-8，默认 3
+    #[arg(long, value_name = "DIGITS", value_parser = clap::value_parser!(u8).range(0..=8))]
+    pub svg_precision: Option<u8>,
+
+    /// SVG 保留和 width/height 相同的 viewBox，便于用 CSS 缩放
+    #[arg(long)]
+    pub svg_keep_viewbox: bool,
+
+    /// SVG 保留所有 ID（被外部 CSS、脚本或 sprite 引用时使用），默认删除文件内没有引用的 ID
+    #[arg(long)]
+    pub svg_keep_ids: bool,
+}
+
+/// 尺寸限制参数
+#[derive(Args)]
+#[command(next_help_heading = "尺寸")]
+pub struct ResizeArgs {
+    /// 最大宽度，超出时先等比缩小再编码（不会放大）
+    #[arg(long, value_name = "PX", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_width: Option<u32>,
+
+    /// 最大高度，超出时先等比缩小再编码（不会放大）
+    #[arg(long, value_name = "PX", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_height: Option<u32>,
+
+    /// 超出尺寸限制时的缩放方式 (contain/cover)，默认 contain；cover 会居中裁剪
+    #[arg(long, value_name = "MODE")]
+    pub fit: Option<String>,
+}
+
+/// 优化缓存参数
+#[derive(Args)]
+#[command(next_help_heading = "缓存")]
+pub struct CacheArgs {
+    /// 启用优化缓存，已优化且未变化的图片会被跳过；缓存保存在被优化目录的 .maya/optimize-cache.json
+    #[arg(long)]
+    pub cache: bool,
+
+    /// 忽略优化缓存，重新处理所有图片；转换格式时覆盖已存在的同名文件
+    #[arg(long)]
+    pub force: bool,
+
+    /// 把优化缓存保存到指定目录（多个项目共用），指定后自动启用缓存
+    #[arg(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+}
+
+/// 输出位置、报告、响应式图片和占位数据参数
+#[derive(Args)]
+#[command(next_help_heading = "输出")]
+pub struct OutputArgs {
+    /// 覆写原图时保留原来的修改时间
+    #[arg(long)]
+    pub preserve_mtime: bool,
+
+    /// 把优化后的图片按源目录结构输出到指定目录，原图保持不变
+    #[arg(long, value_name = "DIR")]
+    pub out_dir: Option<PathBuf>,
+
+    /// 导出逐文件的优化报告 (json/csv/html)，默认保存到被优化目录的 .maya/optimize-report.<格式>
+    #[arg(long, value_name = "FORMAT")]
+    pub report: Option<String>,
+
+    /// 报告文件路径，需要同时指定 --report
+    #[arg(long, value_name = "FILE", requires = "report")]
+    pub report_file: Option<PathBuf>,
+
+    /// 生成响应式图片的宽度列表，例如 320,640,1280,1920；原图保持不变，并输出清单和 <picture> 代码片段
+    #[arg(long, value_name = "WIDTHS", conflicts_with = "to")]
+    pub responsive: Option<String>,
+
+    /// 响应式图片额外生成的格式，按优先级排列，例如 avif,webp（原格式总是作为兜底生成）
+    #[arg(long, value_name = "FORMATS", requires = "responsive")]
+    pub responsive_formats: Option<String>,
+
+    /// <picture> 代码片段中的 sizes 属性，默认 100vw
+    #[arg(long, value_name = "SIZES", requires = "responsive")]
+    pub sizes: Option<String>,
+
+    /// 为每张图片生成懒加载占位数据（BlurHash、主色和内联的小尺寸 WebP），写入 placeholders.json
+    #[arg(long)]
+    pub placeholders: bool,
+}
+
+pub async fn handle_optimize_ops(types: &[String], path: &Path, args: &OptimizeArgs) -> Result<()> {
     if types.is_empty() {
-        return Err(Error::invalid_argument("请指定要压缩的图片类型 (png/jpg/jpeg/all)".to_string()));
+        return Err(Error::invalid_argument("请指定要压缩的图片类型 (png/jpg/jpeg/gif/svg/all)".to_string()));
     }
 
     // 检查是否有n参数，表示创建新文件而不是覆盖
@@ -20,35 +206,221 @@ pub fn handle_optimize_ops(types: &[String], path: &Path) -> Result<()> {
         .map(|s| s.as_str())
         .unwrap_or("all"); // 默认压缩所有类型
 
-    let img_type = img_type_str.parse::<compress_pictures::ImageType>()
+    let img_type = img_type_str.parse::<ImageType>()
         .map_err(|e| Error::invalid_argument(format!("图片类型参数 '{}' 错误: {}", img_type_str, e)))?;
+    if img_type.is_convert_target() {
+        return Err(Error::invalid_argument(format!(
+            "{} 只能作为转换目标格式使用，例如: maya optimize -t all --to {}",
+            img_type.label(),
+            img_type_str
+        )));
+    }
 
-    // 根据图片类型获取扩展名列表
-    let extensions: Vec<&str> = match img_type {
-        compress_pictures::ImageType::Png => vec!["png"],
-        compress_pictures::ImageType::Jpg => vec!["jpg"],
-        compress_pictures::ImageType::Jpeg => vec!["jpeg"],
-        compress_pictures::ImageType::All => vec!["png", "jpg", "jpeg"],
+    let is_gif = img_type == ImageType::Gif;
+    if img_type == ImageType::Svg && (args.to.is_some() || args.output.responsive.is_some()) {
+        return Err(Error::invalid_argument("SVG 只做压缩，不支持 --to 和 --responsive"));
+    }
+    let (convert_to, video) = match args.to.as_deref() {
+        Some(to) => match to.parse::<VideoFormat>() {
+            Ok(_) if !is_gif => {
+                return Err(Error::invalid_argument(format!("只有 GIF 动图可以转换为 {}，请使用 -t gif", to)));
+            }
+            Ok(video) => (None, Some(video)),
+            Err(_) => {
+                let target = to.parse::<ImageType>().map_err(Error::invalid_argument)?;
+                if !target.is_convert_target() {
+                    return Err(Error::invalid_argument(format!(
+                        "--to 只支持 webp 或 avif（GIF 还支持 mp4、webm），收到: {}",
+                        to
+                    )));
+                }
+                if is_gif && target == ImageType::Avif {
+                    return Err(Error::invalid_argument("GIF 动图只能转换为 webp、mp4 或 webm"));
+                }
+                (Some(target), None)
+            }
+        },
+        None => (None, None),
+    };
+    if is_gif && args.output.responsive.is_some() {
+        return Err(Error::invalid_argument("--responsive 只支持 png/jpg/jpeg 图片"));
+    }
+    if (args.output.out_dir.is_some() || args.output.responsive.is_some()) && create_new_file {
+        println!("提示: 已指定 --out-dir 或 --responsive，n 参数将被忽略");
+    } else if (convert_to.is_some() || video.is_some()) && create_new_file {
+        println!("提示: 转换格式时总是生成新文件，n 参数将被忽略");
+    }
+    if args.output.out_dir.is_some() && args.output.preserve_mtime {
+        println!("提示: 使用 --out-dir 时不会覆写原图，--preserve-mtime 将被忽略");
+    }
+    let chroma = match args.jpeg.chroma.as_deref() {
+        Some(chroma) => chroma.parse::<ChromaSubsampling>().map_err(Error::invalid_argument)?,
+        None => ChromaSubsampling::default(),
+    };
+    let png = png_options(&args.png)?;
+    let resize = ResizeOptions {
+        max_width: args.resize.max_width,
+        max_height: args.resize.max_height,
+        fit: match args.resize.fit.as_deref() {
+            Some(fit) => fit.parse::<Fit>().map_err(Error::invalid_argument)?,
+            None => Fit::default(),
+        },
+    };
+    if is_gif && resize.is_enabled() {
+        println!("提示: GIF 动图不会调整尺寸，--max-width/--max-height 将被忽略");
+    } else if img_type == ImageType::Svg && resize.is_enabled() {
+        println!("提示: SVG 是矢量图，--max-width/--max-height 将被忽略");
+    }
+    if img_type == ImageType::Svg && args.output.placeholders {
+        println!("提示: SVG 是矢量图，不会生成占位数据");
+    }
+    if args.resize.fit.is_some() && !resize.is_enabled() {
+        println!("提示: 没有设置 --max-width/--max-height，--fit 将被忽略");
+    }
+    let metadata = MetadataOptions {
+        mode: match args.metadata.as_deref() {
+            Some(mode) => mode.parse::<MetadataMode>().map_err(Error::invalid_argument)?,
+            None => MetadataMode::default(),
+        },
+        keep_gps: args.keep_gps,
+    };
+    if convert_to.is_some() && (args.metadata.is_some() || args.keep_gps) {
+        println!("提示: 转换格式时不会保留元数据，--metadata/--keep-gps 将被忽略");
+    }
+    if args.lossless && resize.is_enabled() && convert_to.is_none() && !is_gif {
+        println!("提示: 超出尺寸限制的 JPEG 需要重新编码，不会使用无损优化");
+    }
+    if args.lossless && args.quality.is_some() && convert_to.is_none() && !is_gif {
+        println!("提示: JPEG 无损优化不会重新量化，--quality 将被忽略");
+    }
+    if let Some(target) = args.target_ssim {
+        if !(target > 0.0 && target < 1.0) {
+            return Err(Error::invalid_argument(format!("--target-ssim 必须在 0 到 1 之间，收到: {}", target)));
+        }
+        if args.lossless {
+            println!("提示: 无损编码没有质量参数，--target-ssim 将被忽略");
+        } else if is_gif {
+            println!("提示: GIF 动图转换使用固定质量，--target-ssim 将被忽略");
+        } else if matches!(convert_to, Some(ImageType::Avif)) {
+            println!("提示: AVIF 编码结果无法解码比较，将使用固定质量，--target-ssim 将被忽略");
+        }
+    }
+    if args.cache.force && args.to.is_none() && !args.cache.cache && args.cache.cache_dir.is_none() {
+        println!("提示: 没有启用 --cache 或 --cache-dir，--force 将被忽略");
+    }
+    let options = CompressOptions {
+        create_new_file,
+        convert_to,
+        video,
+        lossless: args.lossless,
+        remove_original: args.remove_original,
+        quality: args.quality,
+        target_ssim: args.target_ssim,
+        baseline: args.jpeg.baseline,
+        chroma,
+        png,
+        svg: SvgOptions {
+            precision: args.svg.svg_precision.unwrap_or(DEFAULT_SVG_PRECISION),
+            keep_viewbox: args.svg.svg_keep_viewbox,
+            keep_ids: args.svg.svg_keep_ids,
+        },
+        resize,
+        metadata,
+        cache: match (&args.cache.cache_dir, args.cache.cache) {
+            (Some(dir), _) => CacheLocation::Central(dir.clone()),
+            (None, true) => CacheLocation::Project,
+            (None, false) => CacheLocation::Disabled,
+        },
+        force: args.cache.force,
+        preserve_mtime: args.output.preserve_mtime,
+        out_dir: args.output.out_dir.clone(),
+        jobs: args.jobs.map(usize::from).unwrap_or(0),
+        responsive: responsive_options(&args.output)?,
+        placeholders: args.output.placeholders,
     };
 
-    // 获取文件列表以决定是否使用并行处理
-    let image_files = find_files_by_extension(path, &extensions)?;
-    let file_count = image_files.len();
+    let report = args
+        .output
+        .report
+        .as_deref()
+        .map(str::parse::<ReportFormat>)
+        .transpose()
+        .map_err(Error::invalid_argument)?;
 
-    let (successful_compressions, failed_compressions, _avg_compression_ratio) = if file_count >= PARALLEL_THRESHOLD {
-        println!("检测到 {} 个文件，启用并行压缩...", file_count);
-        compress_pictures::compress_images_parallel(path, img_type, create_new_file)?
-    } else {
-        println!("检测到 {} 个文件，使用串行压缩...", file_count);
-        compress_pictures::compress_images(path, img_type, create_new_file)?
-    };
-    
-    if successful_compressions == 0 && failed_compressions == 0 {
+    if video.is_some() {
+        mp4_to_m3u8::ensure_ffmpeg_available().await?;
+    }
+    let summary = compress_pictures::compress_images(path, img_type, &options)?;
+    if let Some(format) = report {
+        let report_path = match &args.output.report_file {
+            Some(file) => file.clone(),
+            None => data_file(path, &format.file_name())?,
+        };
+        compress_pictures::write_report(&summary, path, format, &report_path)?;
+        println!("优化报告已保存到: {}", report_path.display());
+    }
+    if summary.files.is_empty() {
         println!("未找到符合指定类型的图片进行处理。");
-    } else if successful_compressions > 0 {
-        // 成功，compress_images 内部已打印总结
-    } else if failed_compressions > 0 && successful_compressions == 0 {
+    } else if summary.successful() == 0 && summary.failed() > 0 {
         println!("所有找到的图片都压缩失败了。");
     }
     Ok(())
 }
+
+/// 根据命令行参数生成响应式图片选项，未指定 `--responsive` 时返回 `None`
+fn responsive_options(args: &OutputArgs) -> Result<Option<ResponsiveOptions>> {
+    let Some(widths) = args.responsive.as_deref() else {
+        return Ok(None);
+    };
+    let mut responsive = ResponsiveOptions {
+        widths: compress_pictures::parse_widths(widths).map_err(Error::invalid_argument)?,
+        ..Default::default()
+    };
+    if let Some(formats) = args.responsive_formats.as_deref() {
+        responsive.formats = formats
+            .split(',')
+            .map(|format| {
+                let format = format.trim();
+                match format.parse::<ImageType>() {
+                    Ok(target) if target.is_convert_target() => Ok(target),
+                    _ => Err(Error::invalid_argument(format!(
+                        "--responsive-formats 只支持 webp 和 avif，收到: {}",
+                        format
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+    }
+    if let Some(sizes) = &args.sizes {
+        responsive.sizes = sizes.clone();
+    }
+    Ok(Some(responsive))
+}
+
+/// 根据命令行参数生成 PNG 压缩选项
+fn png_options(args: &PngArgs) -> Result<PngOptions> {
+    let mut png = PngOptions {
+        quantize: args
+            .png_quality
+            .as_deref()
+            .map(compress_pictures::parse_quality_range)
+            .transpose()
+            .map_err(Error::invalid_argument)?,
+        zopfli: args.zopfli,
+        optimize_alpha: args.optimize_alpha,
+        ..Default::default()
+    };
+    if let Some(dither) = args.dither {
+        if !(0.0..=1.0).contains(&dither) {
+            return Err(Error::invalid_argument(format!("--dither 的取值范围是 0.0-1.0，收到: {}", dither)));
+        }
+        png.dithering = dither;
+    }
+    if let Some(level) = args.png_level {
+        png.level = level;
+    }
+    if let Some(strip) = args.strip.as_deref() {
+        png.strip = strip.parse::<PngStrip>().map_err(Error::invalid_argument)?;
+    }
+    Ok(png)
+}
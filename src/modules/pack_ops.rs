@@ -1,19 +1,135 @@
+use std::path::{Path, PathBuf};
 use maya_common::error::{Error, Result};
+use maya_common::extract;
+use maya_common::password::read_password;
+use maya_common::size_utils::{format_size, parse_size};
+use maya_common::{split, ZipOptions};
+use vite_pack_add_zip::VitePackOptions;
+
+/// 命令行传入的打包参数
+pub struct PackArgs {
+    pub reproducible: bool,
+    pub top: usize,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub no_sourcemaps: bool,
+    pub encrypt: bool,
+    /// 分卷大小，例如 `100M`
+    pub split_size: Option<String>,
+    /// `-t d` 时要打包的目录
+    pub dir: Option<PathBuf>,
+}
 
 /// 处理打包操作的模块
-pub fn handle_pack_ops(pack_type: &str) -> Result<()> {
-    match pack_type {
+pub fn handle_pack_ops(pack_type: &str, args: &PackArgs) -> Result<()> {
+    // 先解析分卷大小，避免打包完成后才发现参数错误
+    let split_size = args.split_size.as_deref().map(parse_size).transpose()?;
+
+    let mut options = ZipOptions { reproducible: args.reproducible, ..Default::default() };
+    if args.reproducible {
+        println!("已启用可复现打包模式");
+    }
+    if args.encrypt {
+        options.password = Some(read_password(true)?);
+        println!("🔒 已启用 AES-256 加密");
+    }
+
+    let zip_path = match pack_type {
         "g" => {
             println!("根据.gitignore规则打包文件");
-            gitignore_add_zip::handle_gitignore_pack()?;
+            if !args.include.is_empty() || !args.exclude.is_empty() || args.no_sourcemaps {
+                println!("提示: --include/--exclude/--no-sourcemaps 只对 Vite 打包 (-t a) 生效");
+            }
+            gitignore_add_zip::handle_gitignore_pack(&options)?
         }
         "a" => {
             println!("打包Vite项目输出目录");
-            vite_pack_add_zip::handle_vite_pack()?;
+            let vite_options = VitePackOptions {
+                zip: options,
+                top: args.top,
+                include: args.include.clone(),
+                exclude: args.exclude.clone(),
+                no_sourcemaps: args.no_sourcemaps,
+            };
+            vite_pack_add_zip::handle_vite_pack(&vite_options)?
+        }
+        "d" => {
+            let dir = args
+                .dir
+                .as_deref()
+                .ok_or_else(|| Error::invalid_argument("打包目录 (-t d) 需要通过 --dir 指定目录"))?;
+            Some(pack_dir(dir, &options)?)
         }
         _ => {
-            return Err(Error::invalid_argument(format!("未知的打包类型: {}。可用选项: g (gitignore) 或 a (vite)。", pack_type)));
+            return Err(Error::invalid_argument(format!("未知的打包类型: {}。可用选项: g (gitignore)、a (vite) 或 d (目录)。", pack_type)));
+        }
+    };
+
+    if let (Some(zip_path), Some(split_size)) = (zip_path, split_size) {
+        let summary = split::split_file(&zip_path, split_size)?;
+        println!("已切分为 {} 个分卷（每卷最大 {}）:", summary.volumes.len(), format_size(split_size));
+        for volume in &summary.volumes {
+            println!("  {}", volume.display());
+        }
+        println!("校验文件: {}", summary.manifest.display());
+    }
+    Ok(())
+}
+
+/// 校验并合并分卷
+pub fn handle_pack_join(volume: &Path, output: Option<&Path>, verify_only: bool) -> Result<()> {
+    if verify_only {
+        let volumes = split::verify_volumes(volume)?;
+        println!("✅ {} 个分卷校验通过", volumes.len());
+        return Ok(());
+    }
+
+    let joined = split::join_volumes(volume, output)?;
+    println!("✅ 已合并到: {}", joined.display());
+    Ok(())
+}
+
+/// 比较两份构建产物，可选生成增量包
+pub fn handle_pack_diff(old: &Path, new: &Path, delta: Option<&Path>) -> Result<()> {
+    let old_password = archive_password(old)?;
+    let new_password = archive_password(new)?;
+
+    println!("比较 {} -> {}", old.display(), new.display());
+    let old_snapshot = pack_diff::snapshot(old, old_password.as_deref())?;
+    let new_snapshot = pack_diff::snapshot(new, new_password.as_deref())?;
+    let diff = pack_diff::diff_snapshots(&old_snapshot, &new_snapshot);
+    pack_diff::print_diff(&diff);
+
+    if let Some(delta) = delta {
+        if diff.is_empty() {
+            println!("两份产物没有差异，不生成增量包");
+            return Ok(());
         }
+        let written = pack_diff::write_delta_archive(new, &diff, delta, new_password.as_deref())?;
+        println!("✅ 增量包已生成: {}（{} 个文件）", delta.display(), written);
     }
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// 加密的zip需要密码才能读取内容
+fn archive_password(path: &Path) -> Result<Option<String>> {
+    if path.is_file() && extract::is_encrypted(path)? {
+        println!("🔒 {} 已加密", path.display());
+        return read_password(false).map(Some);
+    }
+    Ok(None)
+}
+
+/// 把任意目录中的全部文件打包到当前目录
+fn pack_dir(dir: &Path, options: &ZipOptions) -> Result<PathBuf> {
+    if !dir.is_dir() {
+        return Err(Error::path(format!("目录不存在: {}", dir.display())));
+    }
+    // 规范化路径，保证 `.` 这类参数也能取到目录名作为zip文件名
+    let dir = dir.canonicalize()?;
+    println!("打包目录 {}", dir.display());
+    let current_dir = std::env::current_dir()?;
+    let zip_path = maya_common::create_zip_archive_with_options(&dir, &current_dir, |path| path.is_file(), options)?;
+    println!("成功打包到: {:?}", zip_path);
+    Ok(zip_path)
+}
@@ -0,0 +1,25 @@
+use std::path::{Path, PathBuf};
+use maya_common::error::{Error, Result};
+use maya_common::size_utils::parse_size;
+use precompress_assets::PrecompressOptions;
+
+/// 处理预压缩操作的模块
+pub fn handle_precompress_ops(path: Option<&Path>, zstd: bool, min_size: &str) -> Result<()> {
+    let dir: PathBuf = match path {
+        Some(path) => path.to_path_buf(),
+        // 未指定目录时使用Vite项目的输出目录
+        None => vite_pack_add_zip::find_out_dir()?
+            .ok_or_else(|| Error::path("未找到Vite输出目录，请指定要预压缩的目录"))?,
+    };
+
+    let options = PrecompressOptions {
+        min_size: parse_size(min_size)?,
+        zstd,
+        ..Default::default()
+    };
+    let summary = precompress_assets::precompress_dir(&dir, &options)?;
+    if summary.files == 0 && summary.failed == 0 {
+        println!("未找到需要预压缩的文本资源。");
+    }
+    Ok(())
+}
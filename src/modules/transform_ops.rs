@@ -1,5 +1,6 @@
 use std::path::Path;
 use mp4_to_m3u8;
+use compress_pictures::{convert_legacy_images, ImageType, LegacyFormat};
 use maya_common::error::{Error, Result};
 
 pub async fn handle_transform_ops(types: &[String], path: &Path) -> Result<()> {
@@ -21,8 +22,18 @@ pub async fn handle_transform_ops(types: &[String], path: &Path) -> Result<()> {
                 println!("❌ 所有找到的mp4文件都转换失败了。");
             }
         },
-        _ => {
-            return Err(Error::invalid_argument(format!("暂不支持从 {} 转换到 {} 的格式。目前支持的转换: mp4 -> m3u8", source_format, target_format)));
+        (source, target) => {
+            let (Ok(legacy_format), Ok(image_type)) = (source.parse::<LegacyFormat>(), target.parse::<ImageType>()) else {
+                return Err(Error::invalid_argument(format!("暂不支持从 {} 转换到 {} 的格式。目前支持的转换: mp4 -> m3u8, bmp/tiff/tga/pnm -> png/jpg/webp/avif", source_format, target_format)));
+            };
+            let (successful_conversions, failed_conversions) = convert_legacy_images(path, legacy_format, &image_type)?;
+            if successful_conversions == 0 && failed_conversions == 0 {
+                println!("未找到任何{}文件进行转换。", source);
+            } else if successful_conversions > 0 {
+                println!("✅ {}到{}转换任务完成！", source, target);
+            } else {
+                println!("❌ 所有找到的{}文件都转换失败了。", source);
+            }
         }
     }
     Ok(())
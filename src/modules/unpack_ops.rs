@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+use maya_common::error::{Error, Result};
+use maya_common::password::read_password;
+use maya_common::extract::{self, EntryKind, ExtractLimits, ExtractOptions};
+use maya_common::size_utils::{format_size, parse_size};
+use maya_common::split;
+
+/// 处理解压操作的模块
+pub fn handle_unpack_ops(
+    archive: &Path,
+    dest: Option<&Path>,
+    list: bool,
+    strip_components: usize,
+    max_size: &str,
+    max_ratio: u64,
+) -> Result<()> {
+    if let Some(base) = split::volume_base(archive) {
+        return Err(Error::invalid_argument(format!(
+            "{} 是分卷文件，请先执行 maya pack join 合并为 {}",
+            archive.display(),
+            base.display()
+        )));
+    }
+    if !archive.is_file() {
+        return Err(Error::path(format!("归档文件不存在: {}", archive.display())));
+    }
+
+    if list {
+        return list_entries(archive);
+    }
+
+    let dest = match dest {
+        Some(dest) => dest.to_path_buf(),
+        None => default_dest(archive)?,
+    };
+
+    let password = if extract::is_encrypted(archive)? {
+        println!("🔒 归档已加密");
+        Some(read_password(false)?)
+    } else {
+        None
+    };
+
+    let options = ExtractOptions {
+        strip_components,
+        limits: ExtractLimits {
+            max_total_size: parse_size(max_size)?,
+            max_ratio,
+            ..Default::default()
+        },
+        password,
+    };
+
+    println!("解压 {} 到 {}", archive.display(), dest.display());
+    let summary = extract::extract_archive(archive, &dest, &options)?;
+    println!(
+        "✅ 解压完成: {} 个文件, {} 个目录, {} 个链接, 共 {}",
+        summary.files,
+        summary.dirs,
+        summary.links,
+        format_size(summary.total_bytes)
+    );
+    if summary.skipped > 0 {
+        println!("因 --strip-components 跳过 {} 个条目", summary.skipped);
+    }
+    Ok(())
+}
+
+/// 打印归档内容
+fn list_entries(archive: &Path) -> Result<()> {
+    let entries = extract::list_archive(archive)?;
+    let mut total = 0u64;
+    for entry in &entries {
+        match &entry.kind {
+            EntryKind::File => {
+                total += entry.size;
+                let lock = if entry.encrypted { " 🔒" } else { "" };
+                println!("{:>12}  {}{}", format_size(entry.size), entry.name, lock);
+            }
+            EntryKind::Dir => println!("{:>12}  {}", "<DIR>", entry.name),
+            EntryKind::Symlink(target) => println!("{:>12}  {} -> {}", "<LINK>", entry.name, target),
+            EntryKind::Hardlink(target) => println!("{:>12}  {} => {}", "<HARD>", entry.name, target),
+        }
+    }
+    println!("共 {} 个条目, 解压后约 {}", entries.len(), format_size(total));
+    Ok(())
+}
+
+/// 默认解压目录：当前目录下与归档同名（去掉扩展名）的文件夹
+fn default_dest(archive: &Path) -> Result<PathBuf> {
+    let name = archive
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| Error::path(format!("无法获取归档文件名: {}", archive.display())))?;
+    let stem = [".tar.gz", ".tar.zst", ".tgz", ".tzst", ".tar", ".zip"]
+        .iter()
+        .find_map(|ext| {
+            name.len()
+                .checked_sub(ext.len())
+                .filter(|&i| name.get(i..).is_some_and(|s| s.eq_ignore_ascii_case(ext)))
+                .map(|i| name[..i].to_string())
+        })
+        .unwrap_or(name);
+    Ok(std::env::current_dir()?.join(stem))
+}
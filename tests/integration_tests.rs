@@ -94,6 +94,54 @@ fn test_optimize_missing_required_args() {
         .stderr(predicate::str::contains("required"));
 }
 
+// 测试只有 GIF 可以转换为视频
+#[test]
+fn test_optimize_video_requires_gif() {
+    let mut cmd = Command::cargo_bin("maya").unwrap();
+    cmd.arg("optimize").arg(".").arg("-t").arg("png").arg("--to").arg("mp4");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("只有 GIF 动图可以转换为 mp4"));
+}
+
+// 测试损坏的 JPEG 只计为失败，不会终止整个批处理（用 cargo test --release 覆盖发布配置）
+#[test]
+fn test_optimize_corrupt_jpeg_reports_failure() {
+    use tempfile::tempdir;
+
+    let temp_dir = tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("bad.jpg"), b"\xff\xd8\xff\xe0not a jpeg at all").unwrap();
+
+    let mut cmd = Command::cargo_bin("maya").unwrap();
+    cmd.arg("optimize").arg(temp_dir.path()).arg("-t").arg("jpg").arg("--lossless");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("失败压缩文件数量: 1"));
+}
+
+// 测试 transform 不支持的格式组合
+#[test]
+fn test_transform_unsupported_pair() {
+    let mut cmd = Command::cargo_bin("maya").unwrap();
+    cmd.arg("transform").arg(".").arg("-t").arg("bmp").arg("mp4");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("bmp/tiff/tga/pnm -> png/jpg/webp/avif"));
+}
+
+// 测试 SVG 不能转换格式
+#[test]
+fn test_optimize_svg_rejects_conversion() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("icon.svg"), r#"<svg xmlns="http://www.w3.org/2000/svg"/>"#).unwrap();
+    let mut cmd = Command::cargo_bin("maya").unwrap();
+    cmd.arg("optimize").arg(temp_dir.path()).args(["-t", "svg", "--to", "webp"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("SVG 只做压缩"));
+    assert!(!temp_dir.path().join("icon.webp").exists());
+}
+
 // 测试 clean 子命令在实际目录中执行（无 node_modules 目录）
 #[test]
 fn test_clean_execution_no_node_modules() {
@@ -123,4 +171,120 @@ fn test_clean_invalid_type() {
     cmd.assert()
         .failure()
         .stderr(predicate::str::contains("不支持的清理类型"));
-}
\ No newline at end of file
+}
+// 测试 unpack 子命令列出并解压 zip
+#[test]
+fn test_unpack_list_and_extract() {
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    let temp_dir = tempdir().unwrap();
+    let archive = temp_dir.path().join("site.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+    zip.start_file("site/index.html", zip::write::SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"<html></html>").unwrap();
+    zip.finish().unwrap();
+
+    let mut cmd = Command::cargo_bin("maya").unwrap();
+    cmd.arg("unpack").arg(&archive).arg("--list");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("site/index.html"));
+
+    let dest = temp_dir.path().join("out");
+    let mut cmd = Command::cargo_bin("maya").unwrap();
+    cmd.arg("unpack")
+        .arg(&archive)
+        .arg(&dest)
+        .arg("--strip-components")
+        .arg("1");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("解压完成"));
+    assert!(dest.join("index.html").exists());
+}
+
+#[test]
+fn test_pack_split_and_join() {
+    use tempfile::tempdir;
+
+    let temp_dir = tempdir().unwrap();
+    let video_dir = temp_dir.path().join("video");
+    std::fs::create_dir_all(&video_dir).unwrap();
+    let segment: Vec<u8> = (0..8192u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+    std::fs::write(video_dir.join("index0.ts"), &segment).unwrap();
+
+    let mut cmd = Command::cargo_bin("maya").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["pack", "-t", "d", "--dir", "video", "--split-size", "2K"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("video.zip.001"));
+    assert!(temp_dir.path().join("video.zip.sha256").exists());
+    assert!(!temp_dir.path().join("video.zip").exists());
+
+    let mut cmd = Command::cargo_bin("maya").unwrap();
+    cmd.current_dir(temp_dir.path()).args(["pack", "join", "video.zip.001"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("已合并"));
+
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(temp_dir.path().join("video.zip")).unwrap()).unwrap();
+    let mut content = Vec::new();
+    std::io::Read::read_to_end(&mut archive.by_name("index0.ts").unwrap(), &mut content).unwrap();
+    assert_eq!(content, segment);
+}
+
+#[test]
+fn test_pack_diff_with_delta() {
+    use tempfile::tempdir;
+
+    let temp_dir = tempdir().unwrap();
+    let old = temp_dir.path().join("old");
+    let new = temp_dir.path().join("new");
+    std::fs::create_dir_all(&old).unwrap();
+    std::fs::create_dir_all(&new).unwrap();
+    std::fs::write(old.join("index.html"), "v1").unwrap();
+    std::fs::write(new.join("index.html"), "v2").unwrap();
+    std::fs::write(new.join("app.js"), "app").unwrap();
+
+    let delta = temp_dir.path().join("delta.zip");
+    let mut cmd = Command::cargo_bin("maya").unwrap();
+    cmd.args(["pack", "diff"]).arg(&old).arg(&new).arg("--delta").arg(&delta);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("+ app.js"))
+        .stdout(predicate::str::contains("~ index.html"));
+
+    let archive = zip::ZipArchive::new(std::fs::File::open(&delta).unwrap()).unwrap();
+    assert_eq!(archive.len(), 3);
+}
+
+// 测试 deploy 默认使用较新的构建产物，不会部署重新构建前留下的旧 zip
+#[test]
+fn test_deploy_prefers_newer_out_dir() {
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    let temp_dir = tempdir().unwrap();
+    let project = temp_dir.path().join("project");
+    std::fs::create_dir_all(project.join("dist")).unwrap();
+    std::fs::write(project.join("vite.config.js"), "export default {}").unwrap();
+    std::fs::write(project.join("dist").join("index.html"), "new").unwrap();
+
+    let zip_path = project.join("dist.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+    zip.start_file("index.html", zip::write::SimpleFileOptions::default()).unwrap();
+    std::io::Write::write_all(&mut zip, b"old").unwrap();
+    zip.finish().unwrap();
+    let old = SystemTime::now() - Duration::from_secs(3600);
+    std::fs::File::options().write(true).open(&zip_path).unwrap().set_modified(old).unwrap();
+
+    let site = temp_dir.path().join("site");
+    let mut cmd = Command::cargo_bin("maya").unwrap();
+    cmd.current_dir(&project).arg("deploy").arg(&site);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("使用输出目录"));
+    assert_eq!(std::fs::read_to_string(site.join("current").join("index.html")).unwrap(), "new");
+}